@@ -0,0 +1,258 @@
+// build.rs
+//
+use crate::parse_properties;
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use tfon::{CharClass, ConvertOptions, Font, Format, WriteOptions};
+
+/// build fonts from a TOML manifest
+#[derive(clap::Args, PartialEq, Debug)]
+pub struct BuildCommand {
+    /// manifest file
+    #[arg(default_value = "fonts.toml")]
+    manifest: String,
+
+    /// print planned steps (and dropped properties) without writing
+    #[arg(long)]
+    dry_run: bool,
+}
+
+/// Build manifest
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Manifest {
+    /// Fonts to build
+    #[serde(default)]
+    font: Vec<FontSpec>,
+}
+
+/// Build specification for one font
+///
+/// Transforms are applied in a fixed order: compose accents, mirror, small
+/// caps, subset (ranges, then classes), scale, stroke, monospace, then
+/// baseline / descent.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FontSpec {
+    /// Source font file
+    source: PathBuf,
+    /// Code point ranges to keep, such as "32-126"
+    #[serde(default)]
+    subset: Vec<String>,
+    /// Character classes to keep, such as "digits" or "uppercase"
+    #[serde(default)]
+    keep: Vec<String>,
+    /// Integer scale factor
+    scale: Option<u8>,
+    /// Pixels to thicken strokes (negative to thin)
+    #[serde(default)]
+    stroke: i8,
+    /// Convert to fixed width
+    #[serde(default)]
+    monospace: bool,
+    /// Compose missing accented letters
+    #[serde(default)]
+    compose_accents: bool,
+    /// Add missing mirrored glyphs, such as arrows
+    #[serde(default)]
+    mirror: bool,
+    /// Add missing lowercase letters as small capitals
+    #[serde(default)]
+    small_caps: bool,
+    /// Rows above baseline
+    baseline: Option<u8>,
+    /// Rows below baseline
+    descent: Option<u8>,
+    /// Output files (format from extension, such as `bdf` or `u8g2`)
+    output: Vec<PathBuf>,
+}
+
+/// Parse a code point range, such as "65-90" or "32"
+pub fn parse_range(range: &str) -> Result<(u16, u16)> {
+    let (lo, hi) = range.split_once('-').unwrap_or((range, range));
+    let lo = lo
+        .trim()
+        .parse()
+        .with_context(|| format!("range {range}"))?;
+    let hi = hi
+        .trim()
+        .parse()
+        .with_context(|| format!("range {range}"))?;
+    Ok((lo, hi))
+}
+
+/// Read a source font file, decompressing if needed
+fn read_source(path: &Path) -> Result<String> {
+    let data = tfon::gzip::decode(std::fs::read(path)?)?;
+    Ok(String::from_utf8(data)?)
+}
+
+impl FontSpec {
+    /// Get names of transforms to apply, in order
+    fn transforms(&self) -> Vec<String> {
+        let mut transforms = Vec::new();
+        if self.compose_accents {
+            transforms.push("compose accents".to_string());
+        }
+        if self.mirror {
+            transforms.push("mirror".to_string());
+        }
+        if self.small_caps {
+            transforms.push("small caps".to_string());
+        }
+        if !self.subset.is_empty() {
+            transforms.push(format!("subset {}", self.subset.join(",")));
+        }
+        if !self.keep.is_empty() {
+            transforms.push(format!("keep {}", self.keep.join(",")));
+        }
+        if let Some(factor) = self.scale {
+            transforms.push(format!("scale {factor}"));
+        }
+        if self.stroke != 0 {
+            transforms.push(format!("stroke {}", self.stroke));
+        }
+        if self.monospace {
+            transforms.push("monospace".to_string());
+        }
+        if let Some(baseline) = self.baseline {
+            transforms.push(format!("baseline {baseline}"));
+        }
+        if let Some(descent) = self.descent {
+            transforms.push(format!("descent {descent}"));
+        }
+        transforms
+    }
+
+    /// Load source font and apply transforms
+    fn build(&self, dir: &Path) -> Result<Font> {
+        let path = dir.join(&self.source);
+        let buf = read_source(&path)
+            .with_context(|| format!("reading {}", path.display()))?;
+        let mut font =
+            Font::from_props(parse_properties(&buf, None)?.into_iter())?;
+        if self.compose_accents {
+            font.compose_accents();
+        }
+        if self.mirror {
+            font.mirror_glyphs(Font::MIRRORED);
+        }
+        if self.small_caps {
+            font.synthesize_small_caps();
+        }
+        if !self.subset.is_empty() {
+            let ranges = self
+                .subset
+                .iter()
+                .map(|r| parse_range(r))
+                .collect::<Result<Vec<_>>>()?;
+            font.retain(|cp| {
+                ranges.iter().any(|(lo, hi)| (*lo..=*hi).contains(&cp))
+            });
+        }
+        if !self.keep.is_empty() {
+            let presets = self
+                .keep
+                .iter()
+                .map(|name| Ok(CharClass::from_str(name)?))
+                .collect::<Result<Vec<_>>>()?;
+            font.retain_classes(&presets);
+        }
+        if let Some(factor) = self.scale {
+            font = font.scale(factor)?;
+        }
+        if self.stroke != 0 {
+            font.adjust_stroke(self.stroke);
+        }
+        if self.monospace {
+            font.monospace();
+        }
+        let options = ConvertOptions {
+            baseline: self.baseline,
+            descent: self.descent,
+            ..Default::default()
+        };
+        if options != ConvertOptions::default() {
+            font = font.convert(&options)?;
+        }
+        Ok(font)
+    }
+}
+
+/// Get output format from file extension
+fn output_format(path: &Path) -> Result<Format> {
+    let format = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .and_then(Format::from_output_extension);
+    let Some(format) = format else {
+        bail!("unknown output format: {}", path.display());
+    };
+    Ok(format)
+}
+
+/// Write a font, with format from file extension
+fn write_font(font: &Font, path: &Path) -> Result<()> {
+    let format = output_format(path)?;
+    let writer = BufWriter::new(
+        File::create(path)
+            .with_context(|| format!("creating {}", path.display()))?,
+    );
+    format.write_with(writer, font.props(), &WriteOptions::default())?;
+    Ok(())
+}
+
+/// Check writing a font, without creating the file
+fn check_font(font: &Font, path: &Path) -> Result<()> {
+    let format = output_format(path)?;
+    let report = format.write_with(
+        std::io::sink(),
+        font.props(),
+        &WriteOptions::default(),
+    )?;
+    println!("write: {format} to {}", path.display());
+    for key in &report.dropped {
+        eprintln!("dropped unknown property: {key}");
+    }
+    for seq in &report.ligatures {
+        eprintln!("dropped ligature: {seq}");
+    }
+    Ok(())
+}
+
+impl BuildCommand {
+    /// Run build
+    pub fn run(&self) -> Result<()> {
+        let path = Path::new(&self.manifest);
+        let toml = std::fs::read_to_string(path)
+            .with_context(|| format!("reading {}", path.display()))?;
+        let manifest: Manifest = toml::from_str(&toml)?;
+        // paths are relative to manifest
+        let dir = path.parent().unwrap_or(Path::new("."));
+        for spec in &manifest.font {
+            if self.dry_run {
+                println!("parse: {}", dir.join(&spec.source).display());
+                for transform in spec.transforms() {
+                    println!("transform: {transform}");
+                }
+            }
+            let font = spec.build(dir).with_context(|| {
+                format!("building {}", spec.source.display())
+            })?;
+            for output in &spec.output {
+                let path = dir.join(output);
+                if self.dry_run {
+                    check_font(&font, &path)?;
+                } else {
+                    write_font(&font, &path)?;
+                    eprintln!("wrote {}", path.display());
+                }
+            }
+        }
+        Ok(())
+    }
+}
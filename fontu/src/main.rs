@@ -1,118 +1,1322 @@
 // fonto: Program to convert between font formats
 #![forbid(unsafe_code)]
 
-use anyhow::Result;
-use argh::FromArgs;
-use std::io::{stdin, stdout, IsTerminal, Read};
-use tfon::Prop;
+mod build;
+mod map;
+mod widths;
 
-/// Command-line arguments
-#[derive(FromArgs, PartialEq, Debug)]
+use anyhow::{bail, Context, Result};
+use clap::{CommandFactory, Parser, Subcommand};
+use clap_complete::Shell;
+use serde::Serialize;
+use std::fs::File;
+use std::io::{stdin, stdout, BufWriter, IsTerminal, Read, Write};
+use std::path::Path;
+use std::time::SystemTime;
+use tfon::footprint::Packing;
+use tfon::ntcip;
+use tfon::plotter::{Dialect, PlotOptions};
+use tfon::{
+    CharClass, Constraints, ConvertOptions, Font, Format, LineEnding, Prop,
+    WriteOptions, WriteReport,
+};
+
+/// Bitmap font converter
+#[derive(Parser, PartialEq, Debug)]
+#[command(name = "fontu", version)]
 struct Args {
-    #[argh(subcommand)]
+    #[command(subcommand)]
     cmd: Command,
 }
 
 /// Sub-commands
-#[derive(FromArgs, PartialEq, Debug)]
-#[argh(subcommand)]
+#[derive(Subcommand, PartialEq, Debug)]
 enum Command {
-    Ifnt(IfntCommand),
-    Tfon(TfonCommand),
+    Build(build::BuildCommand),
+    Canon(CanonCommand),
+    Check(CheckCommand),
+    Completions(CompletionsCommand),
+    Confusables(ConfusablesCommand),
+    Convert(ConvertCommand),
+    Diff(DiffCommand),
+    Footprint(FootprintCommand),
+    Glyph(GlyphCommand),
+    Heatmap(HeatmapCommand),
+    Inspect(InspectCommand),
+    Manpage(ManpageCommand),
+    Map(map::MapCommand),
+    Merge3(Merge3Command),
+    Ntcip(NtcipCommand),
+    Plot(PlotCommand),
+    Render(RenderCommand),
+    Rotate(RotateCommand),
+}
+
+/// rewrite a tfon font in canonical form
+///
+/// Suitable as a git clean filter: `fontu canon`.
+#[derive(clap::Args, PartialEq, Debug)]
+struct CanonCommand {
+    /// font file (default: stdin)
+    font: Option<String>,
+}
+
+/// check a font for problems
+#[derive(clap::Args, PartialEq, Debug)]
+struct CheckCommand {
+    /// input format (default: detect)
+    #[arg(long)]
+    from: Option<Format>,
+
+    /// font file (default: stdin)
+    font: Option<String>,
+}
+
+/// write shell completion script
+#[derive(Clone, Copy, clap::Args, PartialEq, Debug)]
+struct CompletionsCommand {
+    /// shell (bash, elvish, fish, powershell or zsh)
+    shell: Shell,
+}
+
+/// show commonly confused character pairs
+#[derive(clap::Args, PartialEq, Debug)]
+struct ConfusablesCommand {
+    /// similarity threshold for flagging pairs
+    #[arg(long, default_value_t = 0.75)]
+    threshold: f32,
+
+    /// font file (default: stdin)
+    font: Option<String>,
+}
+
+/// compare glyphs of two fonts, most changed first
+#[derive(clap::Args, PartialEq, Debug)]
+struct DiffCommand {
+    /// input format (default: detect)
+    #[arg(long)]
+    from: Option<Format>,
+
+    /// original font file
+    old: String,
+
+    /// changed font file
+    new: String,
+}
+
+/// convert font to another format
+#[derive(clap::Args, PartialEq, Debug)]
+struct ConvertCommand {
+    /// output format (bdf, ctable, escpos, gfx, ifnt, iris, tfon or u8g2;
+    /// default: from output file extension)
+    #[arg(long, short)]
+    to: Option<Format>,
+
+    /// output file (default: stdout)
+    #[arg(long, short)]
+    output: Option<String>,
+
+    /// input format (default: detect)
+    #[arg(long)]
+    from: Option<Format>,
+
+    /// rows above baseline, for aligning glyphs
+    #[arg(long)]
+    baseline: Option<u8>,
+
+    /// rows below baseline, for aligning glyphs
+    #[arg(long)]
+    descent: Option<u8>,
+
+    /// preserve formatting of tfon input byte-for-byte
+    #[arg(long)]
+    exact: bool,
+
+    /// keep unknown properties
+    #[arg(long)]
+    keep_unknown: bool,
+
+    /// add ruler and row number comments to tfon pixel grids
+    #[arg(long)]
+    guides: bool,
+
+    /// add blank tfon glyphs for missing code points in a range, such as
+    /// "32-126" (bare `ch` lines in tfon input also become blank glyphs)
+    #[arg(long, value_parser = build::parse_range)]
+    scaffold: Option<(u16, u16)>,
+
+    /// target display, for compile-time checks in C tables (ssd1306)
+    #[arg(long, value_parser = parse_target)]
+    target: Option<Constraints>,
+
+    /// write ifnt with CRLF line endings, for legacy Windows sign tools
+    #[arg(long)]
+    crlf: bool,
+
+    /// width table (TOML), for converting to a fixed width
+    #[arg(long)]
+    widths: Option<String>,
+
+    /// shift glyphs vertically to center their ink in the cell
+    #[arg(long)]
+    recenter: bool,
+
+    /// keep only glyphs in character classes (digits, uppercase, lowercase,
+    /// letters, spaces, pictograms or symbols)
+    #[arg(long, value_delimiter = ',')]
+    keep: Vec<CharClass>,
+
+    /// warn instead of failing on a `.sha256` checksum mismatch
+    #[arg(long)]
+    checksum_warn: bool,
+
+    /// print planned steps (and dropped properties) without writing
+    #[arg(long)]
+    dry_run: bool,
+
+    /// re-parse output, failing if the font does not match the input
+    #[arg(long)]
+    check_idempotent: bool,
+
+    /// font file or URL (default: stdin)
+    font: Option<String>,
+}
+
+/// estimate memory footprint for embedded targets
+#[derive(clap::Args, PartialEq, Debug)]
+struct FootprintCommand {
+    /// input format (default: detect)
+    #[arg(long)]
+    from: Option<Format>,
+
+    /// show bytes for each glyph
+    #[arg(long)]
+    glyphs: bool,
+
+    /// font file (default: stdin)
+    font: Option<String>,
+}
+
+/// show ink used at each position across all glyphs
+#[derive(clap::Args, PartialEq, Debug)]
+struct HeatmapCommand {
+    /// input format (default: detect)
+    #[arg(long)]
+    from: Option<Format>,
+
+    /// write a PNG image instead of text
+    #[arg(long)]
+    png: Option<String>,
+
+    /// PNG pixels per position
+    #[arg(long, default_value_t = 8)]
+    scale: u8,
+
+    /// font file (default: stdin)
+    font: Option<String>,
+}
+
+/// merge changes to a font from two sides, as tfon
+///
+/// Suitable as a git merge driver: `fontu merge3 %O %A %B -o %A`.
+/// Conflicting glyphs keep our side, and the exit status is non-zero.
+#[derive(clap::Args, PartialEq, Debug)]
+struct Merge3Command {
+    /// input format (default: detect)
+    #[arg(long)]
+    from: Option<Format>,
+
+    /// output file (default: stdout)
+    #[arg(long, short)]
+    output: Option<String>,
+
+    /// common ancestor font file
+    base: String,
+
+    /// our font file
+    ours: String,
+
+    /// their font file
+    theirs: String,
+}
+
+/// edit glyphs of a font
+#[derive(clap::Args, PartialEq, Debug)]
+struct GlyphCommand {
+    #[command(subcommand)]
+    action: GlyphAction,
+}
+
+/// Glyph sub-commands
+#[derive(Subcommand, PartialEq, Debug)]
+enum GlyphAction {
+    Find(GlyphFindCommand),
+    Set(GlyphSetCommand),
+}
+
+/// show a glyph, found by character, name or code point
+#[derive(clap::Args, PartialEq, Debug)]
+struct GlyphFindCommand {
+    /// font file (default: stdin)
+    #[arg(long)]
+    font: Option<String>,
+
+    /// input format (default: detect)
+    #[arg(long)]
+    from: Option<Format>,
+
+    /// character (`é`), name (`NBSP`), `U+00E9`, `0xE9` or decimal (`233`)
+    #[arg(value_parser = parse_code_point)]
+    code_point: u16,
+}
+
+/// set a glyph from pixel rows on stdin (`.` off, `@` on)
+///
+/// The font file is edited in place, recording the time and author of the
+/// change on the glyph.
+#[derive(clap::Args, PartialEq, Debug)]
+struct GlyphSetCommand {
+    /// author of change (default: $USER)
+    #[arg(long)]
+    author: Option<String>,
+
+    /// remove the glyph instead
+    #[arg(long)]
+    remove: bool,
+
+    /// font file
+    font: String,
+
+    /// character (`é`), name (`NBSP`), `U+00E9`, `0xE9` or decimal (`233`)
+    #[arg(value_parser = parse_code_point)]
+    code_point: u16,
+}
+
+/// show font properties
+#[derive(clap::Args, PartialEq, Debug)]
+struct InspectCommand {
+    /// input format (default: detect)
+    #[arg(long)]
+    from: Option<Format>,
+
+    /// write JSON, with a fingerprint of each glyph
+    #[arg(long)]
+    json: bool,
+
+    /// font file (default: stdin)
+    font: Option<String>,
+}
+
+/// Font properties, for JSON output
+#[derive(Serialize)]
+struct FontInfo<'a> {
+    /// Source format name
+    format: &'static str,
+    /// MIME type of source format
+    mime_type: &'static str,
+    /// Font name
+    font_name: &'a str,
+    /// Family of related fonts
+    #[serde(skip_serializing_if = "Option::is_none")]
+    family: Option<&'a str>,
+    /// Style within family
+    style: &'a str,
+    /// Font number
+    font_number: u8,
+    /// Font height
+    height: u8,
+    /// Font width (0 for proportional)
+    width: u8,
+    /// Pixel spacing between characters
+    char_spacing: u8,
+    /// Pixel spacing between lines
+    line_spacing: u8,
+    /// Baseline (rows above)
+    baseline: u8,
+    /// Glyphs, ordered by code point
+    glyphs: Vec<GlyphInfo>,
+}
+
+/// Glyph properties, for JSON output
+#[derive(Serialize)]
+struct GlyphInfo {
+    /// Code point
+    code_point: u16,
+    /// Glyph width
+    width: u8,
+    /// Glyph height
+    height: u8,
+    /// Bitmap fingerprint (16 hex digits)
+    fingerprint: String,
+}
+
+/// write man page (roff format)
+#[derive(Clone, Copy, clap::Args, PartialEq, Debug)]
+struct ManpageCommand {}
+
+/// list NTCIP 1203 font objects, as `snmpset` arguments
+///
+/// One object per line: OID, type (`i` or `x`) and value.
+#[derive(clap::Args, PartialEq, Debug)]
+struct NtcipCommand {
+    /// row index in fontTable
+    #[arg(long, default_value_t = 1)]
+    index: u8,
+
+    /// dump object names with BER and OER encodings (hex), including
+    /// fontVersionID
+    #[arg(long)]
+    dump: bool,
+
+    /// input format (default: detect)
+    #[arg(long)]
+    from: Option<Format>,
+
+    /// font file (default: stdin)
+    font: Option<String>,
+}
+
+/// plot text with a pen plotter or engraver (HPGL or G-code)
+#[derive(clap::Args, PartialEq, Debug)]
+struct PlotCommand {
+    /// font file (default: stdin)
+    #[arg(long)]
+    font: Option<String>,
+
+    /// input format (default: detect)
+    #[arg(long)]
+    from: Option<Format>,
+
+    /// write G-code instead of HPGL
+    #[arg(long)]
+    gcode: bool,
+
+    /// size of each pixel, in mm (pen or tool width)
+    #[arg(long, default_value_t = 1.0)]
+    pitch: f32,
+
+    /// text to plot
+    text: String,
+}
+
+/// render text as pixel art
+#[derive(clap::Args, PartialEq, Debug)]
+struct RenderCommand {
+    /// font file (default: stdin)
+    #[arg(long)]
+    font: Option<String>,
+
+    /// input format (default: detect)
+    #[arg(long)]
+    from: Option<Format>,
+
+    /// text to render
+    text: String,
+}
+
+/// rotate font clockwise, for portrait-mounted displays
+#[derive(clap::Args, PartialEq, Debug)]
+struct RotateCommand {
+    /// rotation in degrees (90, 180 or 270)
+    #[arg(long, short)]
+    degrees: u16,
+
+    /// output format (default: input format, or tfon)
+    #[arg(long, short)]
+    to: Option<Format>,
+
+    /// input format (default: detect)
+    #[arg(long)]
+    from: Option<Format>,
+
+    /// font file (default: stdin)
+    font: Option<String>,
+}
+
+impl CheckCommand {
+    fn run(&self) -> Result<()> {
+        let buf = read_input(self.font.as_deref())?;
+        let props = parse_properties(&buf, self.from)?;
+        let skipped = props
+            .iter()
+            .filter(|p| matches!(p, Prop::Skipped(_)))
+            .count();
+        let font = Font::from_props(props.into_iter())?;
+        let mut problems = skipped;
+        for (cp, bmap) in font.glyphs() {
+            if bmap.height() != font.height() {
+                println!(
+                    "glyph {cp}: height {} does not match font height {}",
+                    bmap.height(),
+                    font.height()
+                );
+                problems += 1;
+            }
+        }
+        let report = tfon::quality::score(&font);
+        println!("glyphs: {}", font.glyphs().count());
+        match font.vertical_offset() {
+            0 => (),
+            off => println!("off center: {off} rows (convert --recenter)"),
+        }
+        println!("legibility score: {:.0}%", report.score * 100.0);
+        if problems > 0 {
+            bail!("{problems} problem(s) found");
+        }
+        println!("ok");
+        Ok(())
+    }
+}
+
+impl CompletionsCommand {
+    fn run(self) -> Result<()> {
+        let mut cmd = Args::command();
+        clap_complete::generate(self.shell, &mut cmd, "fontu", &mut stdout());
+        Ok(())
+    }
+}
+
+impl CanonCommand {
+    fn run(&self) -> Result<()> {
+        let buf = read_input(self.font.as_deref())?;
+        let mut writer = output(None, Format::Tfon)?;
+        tfon::tfon::write_canonical(&mut writer, &buf)?;
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+impl ConfusablesCommand {
+    fn run(&self) -> Result<()> {
+        let buf = read_input(self.font.as_deref())?;
+        let font = Font::from_props(parse_properties(&buf, None)?.into_iter())?;
+        for (a, b) in tfon::quality::CONFUSABLE {
+            let (Some(ga), Some(gb)) =
+                (font.glyph_char(*a), font.glyph_char(*b))
+            else {
+                continue;
+            };
+            let sim = tfon::quality::similarity(ga, gb);
+            let flag = if sim >= self.threshold {
+                " (confusable)"
+            } else {
+                ""
+            };
+            println!("{a} / {b}: {:.0}% similar{flag}", sim * 100.0);
+            for y in 0..ga.height().max(gb.height()) {
+                println!("  {}   {}", art_row(ga, y), art_row(gb, y));
+            }
+            println!();
+        }
+        Ok(())
+    }
+}
+
+impl DiffCommand {
+    fn run(&self) -> Result<()> {
+        let buf = read_input(Some(&self.old))?;
+        let old =
+            Font::from_props(parse_properties(&buf, self.from)?.into_iter())?;
+        let buf = read_input(Some(&self.new))?;
+        let new =
+            Font::from_props(parse_properties(&buf, self.from)?.into_iter())?;
+        for diff in tfon::diff::diff(&old, &new) {
+            let ch = char::from_u32(diff.code_point.into())
+                .filter(|c| !c.is_control())
+                .unwrap_or(' ');
+            println!(
+                "{:5} {ch}  {:7} {:4} / {:4} pixels  {:5.1}%",
+                diff.code_point,
+                diff.change,
+                diff.pixels,
+                diff.total,
+                diff.percent()
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Get one row of a bitmap as pixel art
+fn art_row(bmap: &tfon::Bitmap, y: u8) -> String {
+    (0..bmap.width())
+        .map(|x| if bmap.pixel(x, y) { '@' } else { '.' })
+        .collect()
+}
+
+impl ConvertCommand {
+    /// Get output format, from option or output file extension
+    fn format(&self) -> Result<Format> {
+        let ext = self
+            .output
+            .as_deref()
+            .and_then(|path| std::path::Path::new(path).extension())
+            .and_then(|ext| ext.to_str());
+        let to = match (self.to, ext) {
+            (Some(to), _) => to,
+            (None, Some(ext)) => match Format::from_output_extension(ext) {
+                Some(to) => to,
+                None => bail!("unknown output format: {ext}"),
+            },
+            (None, None) => bail!("output format required (--to)"),
+        };
+        if !to.can_write() {
+            bail!("cannot write {to} format");
+        }
+        if self.check_idempotent && !to.can_read() {
+            bail!("cannot check idempotence: {to} format is not readable");
+        }
+        Ok(to)
+    }
+
+    fn run(&self) -> Result<()> {
+        let to = self.format()?;
+        let buf = read_input_with(self.font.as_deref(), self.checksum_warn)?;
+        if self.dry_run {
+            for src in tfon::split(&buf) {
+                self.plan(src, to)?;
+                self.convert(src, to, &mut std::io::sink())?;
+            }
+            return Ok(());
+        }
+        if let Some(path) = &self.output {
+            // the file is only replaced once every font is converted
+            let mut out = Vec::new();
+            for src in tfon::split(&buf) {
+                self.convert(src, to, &mut out)?;
+            }
+            return replace_file(Path::new(path), &out);
+        }
+        let mut writer = output(None, to)?;
+        for src in tfon::split(&buf) {
+            self.convert(src, to, &mut writer)?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Print planned steps to convert one font source
+    fn plan(&self, buf: &str, to: Format) -> Result<()> {
+        let from = self.from.or_else(|| Format::detect(buf));
+        let glyphs = parse_properties(buf, from)?
+            .iter()
+            .filter(|prop| matches!(prop, Prop::CodePoint(_)))
+            .count();
+        match from {
+            Some(from) => println!("parse: {from}, {glyphs} glyphs"),
+            None => println!("parse: unknown format"),
+        }
+        if !self.keep.is_empty() {
+            let keep: Vec<_> =
+                self.keep.iter().map(|c| c.to_string()).collect();
+            println!("transform: keep {}", keep.join(","));
+        }
+        if let Some(baseline) = self.baseline {
+            println!("transform: baseline {baseline}");
+        }
+        if let Some(descent) = self.descent {
+            println!("transform: descent {descent}");
+        }
+        if let Some(widths) = &self.widths {
+            println!("transform: widths {widths}");
+        }
+        if self.recenter {
+            println!("transform: recenter");
+        }
+        if let Some((lo, hi)) = self.scaffold {
+            println!("transform: scaffold {lo}-{hi}");
+        }
+        let path = self.output.as_deref().unwrap_or("stdout");
+        println!("write: {to} to {path}");
+        Ok(())
+    }
+
+    /// Convert one font source
+    fn convert<W: Write>(
+        &self,
+        buf: &str,
+        to: Format,
+        writer: &mut W,
+    ) -> Result<()> {
+        let widths = match &self.widths {
+            Some(path) => Some(widths::read_widths(Path::new(path))?),
+            None => None,
+        };
+        let options = ConvertOptions {
+            baseline: self.baseline,
+            descent: self.descent,
+            widths,
+        };
+        let mut font = None;
+        let props = match self.from.or_else(|| Format::detect(buf)) {
+            Some(Format::Tfon) if self.scaffold.is_some() => {
+                tfon::tfon::Parser::new(buf).scaffold().collect()
+            }
+            _ => parse_properties(buf, self.from)?,
+        };
+        let props =
+            convert(props, &mut font, &options, &self.keep, self.recenter)?;
+        let source = if self.check_idempotent {
+            Some(Font::from_props(props.clone().into_iter())?)
+        } else {
+            None
+        };
+        // buffer output when checking, so nothing is written on failure
+        let mut out = Vec::new();
+        let w: &mut dyn Write = match source {
+            Some(_) => &mut out,
+            None => writer,
+        };
+        if self.exact
+            && to == Format::Tfon
+            && Format::detect(buf) == Some(Format::Tfon)
+        {
+            tfon::tfon::write_exact(w, buf, props.into_iter())?;
+        } else {
+            let options = WriteOptions {
+                keep_unknown: self.keep_unknown,
+                guides: self.guides,
+                scaffold: self.scaffold,
+                constraints: self.target,
+                line_ending: if self.crlf {
+                    LineEnding::Crlf
+                } else {
+                    LineEnding::Lf
+                },
+            };
+            let report = to.write_with(w, props.into_iter(), &options)?;
+            print_report(&report);
+        }
+        if let Some(source) = source {
+            check_idempotent(&source, &out, to)?;
+            writer.write_all(&out)?;
+        }
+        Ok(())
+    }
+}
+
+impl FootprintCommand {
+    fn run(&self) -> Result<()> {
+        let buf = read_input(self.font.as_deref())?;
+        let font =
+            Font::from_props(parse_properties(&buf, self.from)?.into_iter())?;
+        let footprints = Packing::ALL
+            .iter()
+            .map(|packing| font.footprint(*packing))
+            .collect::<Result<Vec<_>, _>>()?;
+        if self.glyphs {
+            print!("{:>6}", "cp");
+            for fp in &footprints {
+                print!(" {:>9}", fp.packing.name());
+            }
+            println!();
+            for (i, (cp, _bmap)) in font.glyphs().enumerate() {
+                print!("{cp:>6}");
+                for fp in &footprints {
+                    print!(" {:>9}", fp.glyphs[i].1);
+                }
+                println!();
+            }
+            println!();
+        }
+        println!(
+            "{:<9} {:>9} {:>9} {:>9}",
+            "packing", "glyphs", "overhead", "total"
+        );
+        for fp in &footprints {
+            let glyphs = fp.total() - fp.overhead;
+            println!(
+                "{:<9} {glyphs:>9} {:>9} {:>9}",
+                fp.packing.name(),
+                fp.overhead,
+                fp.total()
+            );
+        }
+        Ok(())
+    }
+}
+
+impl HeatmapCommand {
+    fn run(&self) -> Result<()> {
+        let buf = read_input(self.font.as_deref())?;
+        let font =
+            Font::from_props(parse_properties(&buf, self.from)?.into_iter())?;
+        let heatmap = font.pixel_heatmap();
+        match &self.png {
+            Some(path) => {
+                let writer = BufWriter::new(File::create(path)?);
+                heatmap.write_png(writer, self.scale)?;
+            }
+            None => {
+                println!("{} glyphs", heatmap.glyphs());
+                print!("{}", heatmap.to_text());
+            }
+        }
+        Ok(())
+    }
 }
 
-/// convert font to ifnt format
-#[derive(Clone, Copy, FromArgs, PartialEq, Debug)]
-#[argh(subcommand, name = "ifnt")]
-struct IfntCommand {}
+impl InspectCommand {
+    fn run(&self) -> Result<()> {
+        let buf = read_input(self.font.as_deref())?;
+        let format = match self.from {
+            Some(format) => format,
+            None => Format::detect(&buf).ok_or(tfon::Error::UnknownFormat())?,
+        };
+        let font = Font::from_props(
+            parse_properties(&buf, Some(format))?.into_iter(),
+        )?;
+        if self.json {
+            return print_json(&font, format);
+        }
+        println!("format: {format}");
+        println!("mime_type: {}", format.mime_type());
+        println!("font_name: {}", font.name());
+        if let Some(family) = font.family() {
+            println!("family: {family}");
+            println!("style: {}", font.style());
+        }
+        println!("font_number: {}", font.number());
+        println!("height: {}", font.height());
+        if font.width() > 0 {
+            println!("width: {}", font.width());
+        } else {
+            println!("width: proportional");
+        }
+        println!("char_spacing: {}", font.char_spacing());
+        println!("line_spacing: {}", font.line_spacing());
+        println!("baseline: {}", font.baseline());
+        println!("glyphs: {}", font.glyphs().count());
+        let frames = font
+            .glyphs()
+            .filter(|(cp, _)| font.frame(*cp).is_some())
+            .count();
+        if frames > 0 {
+            println!("blinking: {frames}");
+        }
+        println!("code points: {}", code_point_ranges(&font));
+        Ok(())
+    }
+}
 
-/// convert font to tfon format
-#[derive(Clone, Copy, FromArgs, PartialEq, Debug)]
-#[argh(subcommand, name = "tfon")]
-struct TfonCommand {}
+/// Print font properties and glyph fingerprints as JSON
+fn print_json(font: &Font, format: Format) -> Result<()> {
+    let info = FontInfo {
+        format: format.name(),
+        mime_type: format.mime_type(),
+        font_name: font.name(),
+        family: font.family(),
+        style: font.style(),
+        font_number: font.number(),
+        height: font.height(),
+        width: font.width(),
+        char_spacing: font.char_spacing(),
+        line_spacing: font.line_spacing(),
+        baseline: font.baseline(),
+        glyphs: font
+            .glyphs()
+            .map(|(cp, bmap)| GlyphInfo {
+                code_point: cp,
+                width: bmap.width(),
+                height: bmap.height(),
+                fingerprint: format!("{:016x}", bmap.fingerprint()),
+            })
+            .collect(),
+    };
+    serde_json::to_writer_pretty(stdout(), &info)?;
+    println!();
+    Ok(())
+}
 
-/// Example font property iterator
-#[derive(Clone, Debug)]
-struct PropIter<'a> {
-    pos: u16,
-    #[allow(dead_code)]
-    buf: &'a str,
+/// Get code point ranges of all glyphs, such as "32-126, 160"
+fn code_point_ranges(font: &Font) -> String {
+    tfon::segment::segments(font.glyphs().map(|(cp, _)| cp), 0)
+        .iter()
+        .map(|seg| seg.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
 }
 
-impl<'a> Iterator for PropIter<'a> {
-    type Item = Prop<'a>;
+impl ManpageCommand {
+    fn run(self) -> Result<()> {
+        clap_mangen::Man::new(Args::command()).render(&mut stdout())?;
+        Ok(())
+    }
+}
 
-    fn next(&mut self) -> Option<Self::Item> {
-        let pos = self.pos;
-        self.pos += 1;
-        match pos {
-            0 => Some(Prop::FontName("Example Font")),
-            1 => Some(Prop::FontNumber(1)),
-            2 => Some(Prop::CharSpacing(1)),
-            3 => Some(Prop::LineSpacing(1)),
-            p if p < 256 + 3 => Some(Prop::CodePoint(p - 3)),
-            _ => None,
+impl NtcipCommand {
+    fn run(&self) -> Result<()> {
+        let buf = read_input(self.font.as_deref())?;
+        let font =
+            Font::from_props(parse_properties(&buf, self.from)?.into_iter())?;
+        let rows = ntcip::character_rows(&font, self.index)?;
+        let font_row = ntcip::FontRow::new(&font, self.index, &rows);
+        let mut objects = font_row.objects();
+        if self.dump {
+            objects.push(font_row.version_id_object());
+        }
+        objects.extend(rows.iter().flat_map(|row| row.objects()));
+        let mut out = BufWriter::new(stdout().lock());
+        for obj in objects {
+            if self.dump {
+                writeln!(
+                    out,
+                    "{} ber:{} oer:{}",
+                    obj.name,
+                    ntcip::hex(&obj.value.ber()),
+                    ntcip::hex(&obj.value.oer())
+                )?;
+            } else {
+                writeln!(out, "{obj}")?;
+            }
         }
+        out.flush()?;
+        Ok(())
     }
 }
 
-impl<'a> PropIter<'a> {
-    /// Create a new example font property iterator
-    fn new(buf: &'a str) -> Self {
-        PropIter { pos: 0, buf }
+impl PlotCommand {
+    fn run(&self) -> Result<()> {
+        let buf = read_input(self.font.as_deref())?;
+        let font =
+            Font::from_props(parse_properties(&buf, self.from)?.into_iter())?;
+        let bmap = tfon::render::text(&font, &self.text)?;
+        let options = PlotOptions {
+            dialect: if self.gcode {
+                Dialect::Gcode
+            } else {
+                Dialect::Hpgl
+            },
+            pitch: self.pitch,
+            ..Default::default()
+        };
+        let mut writer = stdout().lock();
+        tfon::plotter::write(&mut writer, &bmap, &options)?;
+        writer.flush()?;
+        Ok(())
     }
 }
 
-impl IfntCommand {
-    fn convert(self) -> Result<()> {
-        let mut buf = String::with_capacity(1024);
-        tfon::ifnt::write(stdout(), font_properties(&mut buf)?.into_iter())?;
+impl RenderCommand {
+    fn run(&self) -> Result<()> {
+        let buf = read_input(self.font.as_deref())?;
+        let font =
+            Font::from_props(parse_properties(&buf, self.from)?.into_iter())?;
+        let bmap = tfon::render::text(&font, &self.text)?;
+        print!("{}", bmap.to_art());
         Ok(())
     }
 }
 
-impl TfonCommand {
-    fn convert(self) -> Result<()> {
-        let mut buf = String::with_capacity(1024);
-        tfon::tfon::write(stdout(), font_properties(&mut buf)?.into_iter())?;
+impl GlyphCommand {
+    fn run(&self) -> Result<()> {
+        match &self.action {
+            GlyphAction::Find(find) => find.run(),
+            GlyphAction::Set(set) => set.run(),
+        }
+    }
+}
+
+impl GlyphFindCommand {
+    fn run(&self) -> Result<()> {
+        let buf = read_input(self.font.as_deref())?;
+        let font =
+            Font::from_props(parse_properties(&buf, self.from)?.into_iter())?;
+        let cp = self.code_point;
+        let mut title = format!("U+{cp:04X} ({cp})");
+        match tfon::names::name(cp) {
+            Some((abbr, name)) => title.push_str(&format!(" {abbr} {name}")),
+            None => {
+                if let Some(c) = char::from_u32(cp.into()) {
+                    title.push_str(&format!(" {c}"));
+                }
+            }
+        }
+        let Some(bmap) = font.glyph(cp) else {
+            bail!("{title}: no glyph");
+        };
+        println!("{title}");
+        print!("width: {}, height: {}", bmap.width(), bmap.height());
+        match bmap.ink_bounds() {
+            Some(ink) => println!(
+                ", ink: {}x{} at {},{}",
+                ink.width, ink.height, ink.x, ink.y
+            ),
+            None => println!(", no ink"),
+        }
+        print!("{}", bmap.to_art());
         Ok(())
     }
 }
 
-/// Create a vec of font properties
-fn font_properties(buf: &mut String) -> Result<Vec<Prop>> {
+impl GlyphSetCommand {
+    fn run(&self) -> Result<()> {
+        let buf = read_input(Some(&self.font))?;
+        let Some(format) = Format::detect(&buf).filter(|f| f.can_write())
+        else {
+            bail!("cannot edit {}: unknown format", self.font);
+        };
+        let mut font = Font::from_props(
+            parse_properties(&buf, Some(format))?.into_iter(),
+        )?;
+        let bmap = if self.remove {
+            None
+        } else {
+            let mut rows = String::new();
+            stdin().read_to_string(&mut rows)?;
+            Some(tfon::Bitmap::from_art(&rows)?)
+        };
+        let author = match &self.author {
+            Some(author) => author.clone(),
+            None => std::env::var("USER").unwrap_or_else(|_| "unknown".into()),
+        };
+        let modified = tfon::Modified {
+            time: humantime::format_rfc3339_seconds(SystemTime::now())
+                .to_string(),
+            author,
+        };
+        font.set_glyph(self.code_point, bmap, Some(modified))?;
+        let mut out = Vec::new();
+        if format == Format::Tfon {
+            tfon::tfon::write_exact(&mut out, &buf, font.props())?;
+        } else {
+            format.write_with(
+                &mut out,
+                font.props(),
+                &WriteOptions::default(),
+            )?;
+        }
+        replace_file(Path::new(&self.font), &out)
+    }
+}
+
+/// Parse a code point, as a number, character or name
+fn parse_code_point(val: &str) -> Result<u16> {
+    match tfon::names::resolve(val) {
+        Some(cp) => Ok(cp),
+        None => bail!("invalid code point: {val}"),
+    }
+}
+
+impl Merge3Command {
+    fn run(&self) -> Result<()> {
+        let fonts = [&self.base, &self.ours, &self.theirs]
+            .into_iter()
+            .map(|path| {
+                let buf = read_input(Some(path))?;
+                Ok(Font::from_props(
+                    parse_properties(&buf, self.from)?.into_iter(),
+                )?)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let merge = tfon::merge3(&fonts[0], &fonts[1], &fonts[2])?;
+        let mut writer = output(self.output.as_deref(), Format::Tfon)?;
+        tfon::tfon::write(&mut writer, merge.font.props())?;
+        writer.flush()?;
+        for conflict in &merge.conflicts {
+            eprintln!("conflict: {conflict}");
+        }
+        if !merge.conflicts.is_empty() {
+            bail!("{} conflicts (keeping ours)", merge.conflicts.len());
+        }
+        Ok(())
+    }
+}
+
+impl RotateCommand {
+    fn run(&self) -> Result<()> {
+        let buf = read_input(self.font.as_deref())?;
+        let from = self.from.or_else(|| Format::detect(&buf));
+        let to = match self.to {
+            Some(to) if !to.can_write() => bail!("cannot write {to} format"),
+            Some(to) => to,
+            None => from.filter(|f| f.can_write()).unwrap_or(Format::Tfon),
+        };
+        let font = Font::from_props(parse_properties(&buf, from)?.into_iter())?;
+        let font = font.rotate(self.degrees)?;
+        let mut writer = output(None, to)?;
+        let report =
+            to.write_with(&mut writer, font.props(), &WriteOptions::default())?;
+        print_report(&report);
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+/// Convert font properties, if any options are set
+fn convert<'a>(
+    props: Vec<Prop<'a>>,
+    font: &'a mut Option<Font>,
+    options: &ConvertOptions,
+    keep: &[CharClass],
+    recenter: bool,
+) -> Result<Vec<Prop<'a>>> {
+    if *options == ConvertOptions::default() && keep.is_empty() && !recenter {
+        return Ok(props);
+    }
+    let mut converted = Font::from_props(props.into_iter())?;
+    if !keep.is_empty() {
+        converted.retain_classes(keep);
+    }
+    if *options != ConvertOptions::default() {
+        converted = converted.convert(options)?;
+    }
+    if recenter {
+        let shift = converted.vertical_offset();
+        let moved = converted.recenter();
+        let dir = if shift > 0 { "up" } else { "down" };
+        eprintln!("recentered {moved} glyphs, {} rows {dir}", shift.abs());
+    }
+    let font = font.insert(converted);
+    Ok(font.props().collect())
+}
+
+/// Parse a target display name
+fn parse_target(name: &str) -> Result<Constraints> {
+    match name {
+        "ssd1306" => Ok(Constraints::SSD1306),
+        _ => bail!("unknown target: {name}"),
+    }
+}
+
+/// Check that converted output parses to the same font as the source
+///
+/// Differences are printed to stderr.
+fn check_idempotent(source: &Font, out: &[u8], to: Format) -> Result<()> {
+    let out = std::str::from_utf8(out)?;
+    let font = Font::from_props(parse_properties(out, Some(to))?.into_iter())
+        .with_context(|| format!("re-parsing {to} output"))?;
+    let (source, font) = (normalize(source)?, normalize(&font)?);
+    if font == source {
+        return Ok(());
+    }
+    let header = |font: &Font| -> Vec<String> {
+        font.props()
+            .take_while(|prop| !matches!(prop, Prop::CodePoint(_)))
+            .map(|prop| format!("{prop:?}"))
+            .collect()
+    };
+    let (before, after) = (header(&source), header(&font));
+    for prop in before.iter().filter(|prop| !after.contains(prop)) {
+        eprintln!("- {prop}");
+    }
+    for prop in after.iter().filter(|prop| !before.contains(prop)) {
+        eprintln!("+ {prop}");
+    }
+    for diff in tfon::diff::diff(&source, &font) {
+        eprintln!("glyph {} {}", diff.code_point, diff.change);
+    }
+    bail!("{to} output does not match input font");
+}
+
+/// Make default baseline and maximum character number explicit
+fn normalize(font: &Font) -> Result<Font> {
+    let explicit = [
+        Prop::Baseline(font.baseline()),
+        Prop::MaxCharNumber(font.max_char_number()),
+    ];
+    let props = font.props().filter(|prop| {
+        !matches!(prop, Prop::Baseline(_) | Prop::MaxCharNumber(_))
+    });
+    Ok(Font::from_props(explicit.into_iter().chain(props))?)
+}
+
+/// Print conversion report to stderr
+fn print_report(report: &WriteReport) {
+    for key in &report.dropped {
+        eprintln!("dropped unknown property: {key}");
+    }
+    for seq in &report.ligatures {
+        eprintln!("dropped ligature: {seq}");
+    }
+}
+
+/// Open output file, or stdout
+///
+/// Binary output is not written to a terminal.
+fn output(path: Option<&str>, format: Format) -> Result<Box<dyn Write>> {
+    match path {
+        Some(path) => Ok(Box::new(BufWriter::new(File::create(path)?))),
+        None if format.is_binary() && stdout().is_terminal() => {
+            bail!("{format} output is binary: use --output or redirect stdout")
+        }
+        None => Ok(Box::new(BufWriter::new(stdout().lock()))),
+    }
+}
+
+/// Replace the contents of a file
+///
+/// Data is written to a temporary file in the same directory, which is then
+/// renamed, so the file is never left partially written.
+fn replace_file(path: &Path, data: &[u8]) -> Result<()> {
+    let name = path.file_name().context("invalid output path")?;
+    let mut tmp_name = std::ffi::OsString::from(".");
+    tmp_name.push(name);
+    tmp_name.push(format!(".{}.tmp", std::process::id()));
+    let tmp = path.with_file_name(tmp_name);
+    let res = File::create(&tmp).and_then(|mut file| {
+        file.write_all(data)?;
+        file.sync_all()
+    });
+    match res.and_then(|_| std::fs::rename(&tmp, path)) {
+        Ok(()) => Ok(()),
+        Err(e) => {
+            let _ = std::fs::remove_file(&tmp);
+            Err(e).with_context(|| format!("writing {}", path.display()))
+        }
+    }
+}
+
+/// Check if a path is an HTTP(S) URL
+fn is_url(path: &str) -> bool {
+    path.starts_with("http://") || path.starts_with("https://")
+}
+
+/// Fetch font input from a URL, with checksum if available
+#[cfg(feature = "http")]
+fn fetch(url: &str) -> Result<(Vec<u8>, Option<String>)> {
+    let mut buf = Vec::new();
+    ureq::get(url).call()?.into_reader().read_to_end(&mut buf)?;
+    let sha256 = match ureq::get(&format!("{url}.sha256")).call() {
+        Ok(rsp) => Some(rsp.into_string()?),
+        Err(ureq::Error::Status(404, _)) => None,
+        Err(e) => return Err(e.into()),
+    };
+    Ok((buf, sha256))
+}
+
+/// Fetch font input from a URL, with checksum if available
+#[cfg(not(feature = "http"))]
+fn fetch(url: &str) -> Result<(Vec<u8>, Option<String>)> {
+    bail!("cannot fetch {url}: fontu was built without the `http` feature")
+}
+
+/// Check the result of a checksum verification
+fn check_verify(res: Result<(), tfon::Error>, warn: bool) -> Result<()> {
+    match res {
+        Err(e @ tfon::Error::ChecksumMismatch(_)) if warn => {
+            eprintln!("warning: {e}");
+            Ok(())
+        }
+        res => Ok(res?),
+    }
+}
+
+/// Read font input from a file, URL, or stdin
+///
+/// If stdin is a terminal, a demo font is used instead.
+fn read_input(path: Option<&str>) -> Result<String> {
+    read_input_with(path, false)
+}
+
+/// Read font input, verifying any `.sha256` checksum
+///
+/// Gzip-compressed input is decompressed.
+///
+/// * `warn` Warn instead of failing on checksum mismatch.
+fn read_input_with(path: Option<&str>, warn: bool) -> Result<String> {
+    let buf = tfon::gzip::decode(read_bytes(path, warn)?)?;
+    match String::from_utf8(buf) {
+        Ok(buf) => Ok(buf),
+        Err(_) => bail!("input is not UTF-8 text: binary fonts cannot be read"),
+    }
+}
+
+/// Read font input as bytes, verifying any `.sha256` checksum
+///
+/// Input is read as bytes, so checksums are verified before any conversion
+/// to text.
+fn read_bytes(path: Option<&str>, warn: bool) -> Result<Vec<u8>> {
+    if let Some(path) = path {
+        if is_url(path) {
+            let (buf, sidecar) = fetch(path)?;
+            if let Some(sidecar) = sidecar {
+                let res = match tfon::checksum::parse_sidecar(&sidecar) {
+                    Some(sum) => tfon::checksum::verify(&buf, sum, path),
+                    None => Err(tfon::Error::ChecksumMismatch(path.into())),
+                };
+                check_verify(res, warn)?;
+            }
+            return Ok(buf);
+        }
+        let buf = std::fs::read(path)?;
+        let res = tfon::checksum::verify_file(Path::new(path), &buf);
+        check_verify(res.map(|_| ()), warn)?;
+        return Ok(buf);
+    }
+    let mut buf = Vec::with_capacity(1024);
     if stdin().is_terminal() {
-        Ok(PropIter::new(buf).collect())
+        let font = tfon::demo::font(7);
+        tfon::tfon::write(&mut buf, font.props())?;
     } else {
-        stdin().read_to_string(buf)?;
-        // What format is this font?
-        if buf.starts_with("STARTFONT") {
-            Ok(tfon::bdf::Parser::new(buf).collect())
-        } else if buf.starts_with("[FontInfo]") {
-            Ok(tfon::ifnt::Parser::new(buf).collect())
-        } else if buf.starts_with("name: ") {
-            Ok(tfon::ifntx::Parser::new(buf).collect())
-        } else if buf.starts_with("font_name: ") {
-            Ok(tfon::tfon::Parser::new(buf).collect())
-        } else {
-            Err(tfon::Error::UnknownFormat())?
+        stdin().read_to_end(&mut buf)?;
+    }
+    Ok(buf)
+}
+
+/// Parse font properties, detecting the format if not specified
+fn parse_properties(
+    buf: &str,
+    format: Option<Format>,
+) -> Result<Vec<Prop<'_>>> {
+    let format = match format {
+        Some(format) => format,
+        None => Format::detect(buf).ok_or(tfon::Error::UnknownFormat())?,
+    };
+    let mut parser = format.parse(buf)?;
+    let mut props = Vec::new();
+    while let Some(prop) = parser.next() {
+        if let Prop::Skipped(line) = &prop {
+            match parser.line_number() {
+                Some(n) => {
+                    eprintln!("skipped malformed block at line {n}: {line}")
+                }
+                None => eprintln!("skipped malformed block: {line}"),
+            }
         }
+        props.push(prop);
     }
+    Ok(props)
 }
 
 impl Args {
     /// Run selected command
     fn run(self) -> Result<()> {
         match &self.cmd {
-            Command::Ifnt(ifnt) => ifnt.convert(),
-            Command::Tfon(tfon) => tfon.convert(),
+            Command::Build(build) => build.run(),
+            Command::Canon(canon) => canon.run(),
+            Command::Check(check) => check.run(),
+            Command::Completions(completions) => completions.run(),
+            Command::Confusables(confusables) => confusables.run(),
+            Command::Convert(convert) => convert.run(),
+            Command::Diff(diff) => diff.run(),
+            Command::Footprint(footprint) => footprint.run(),
+            Command::Glyph(glyph) => glyph.run(),
+            Command::Heatmap(heatmap) => heatmap.run(),
+            Command::Inspect(inspect) => inspect.run(),
+            Command::Manpage(manpage) => manpage.run(),
+            Command::Map(map) => map.run(),
+            Command::Merge3(merge3) => merge3.run(),
+            Command::Ntcip(ntcip) => ntcip.run(),
+            Command::Plot(plot) => plot.run(),
+            Command::Render(render) => render.run(),
+            Command::Rotate(rotate) => rotate.run(),
         }
     }
 }
 
+/// Check if an error is from a closed output pipe (such as `| head`)
+fn is_broken_pipe(e: &anyhow::Error) -> bool {
+    e.chain()
+        .filter_map(|e| e.downcast_ref::<std::io::Error>())
+        .any(|e| e.kind() == std::io::ErrorKind::BrokenPipe)
+}
+
 /// Program entry point
 fn main() -> Result<()> {
-    let args: Args = argh::from_env();
-    args.run()?;
-    Ok(())
+    let args = Args::parse();
+    match args.run() {
+        Err(e) if is_broken_pipe(&e) => Ok(()),
+        res => res,
+    }
 }
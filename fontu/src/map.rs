@@ -0,0 +1,212 @@
+// map.rs
+//
+use crate::{output, parse_properties, print_report, read_input};
+use anyhow::{bail, Context, Result};
+use std::io::Write;
+use tfon::{Bitmap, Font, Format, Prop, Rect, WriteOptions};
+
+/// transform every glyph with a pipeline expression
+///
+/// Steps are separated by `|`, and applied in order:
+/// trim, trim-left, trim-right, pad-left N, pad-right N, shift-up N,
+/// shift-down N, shift-left N, shift-right N, mirror, thin and thicken.
+/// A missing N is 1.
+#[derive(clap::Args, PartialEq, Debug)]
+pub struct MapCommand {
+    /// pipeline expression, such as "trim | pad-right 1 | shift-up 1"
+    #[arg(long, short)]
+    expr: String,
+
+    /// output format (default: input format, or tfon)
+    #[arg(long, short)]
+    to: Option<Format>,
+
+    /// input format (default: detect)
+    #[arg(long)]
+    from: Option<Format>,
+
+    /// font file (default: stdin)
+    font: Option<String>,
+}
+
+/// Step of a pipeline
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Step {
+    /// Remove blank columns on both sides
+    Trim,
+    /// Remove blank columns on the left
+    TrimLeft,
+    /// Remove blank columns on the right
+    TrimRight,
+    /// Add blank columns on the left
+    PadLeft(u8),
+    /// Add blank columns on the right
+    PadRight(u8),
+    /// Move pixels up, keeping size
+    ShiftUp(u8),
+    /// Move pixels down, keeping size
+    ShiftDown(u8),
+    /// Move pixels left, keeping size
+    ShiftLeft(u8),
+    /// Move pixels right, keeping size
+    ShiftRight(u8),
+    /// Flip horizontally
+    Mirror,
+    /// Thin strokes by one pixel
+    Thin,
+    /// Thicken strokes by one pixel
+    Thicken,
+}
+
+/// Parse a pipeline expression
+fn parse_expr(expr: &str) -> Result<Vec<Step>> {
+    expr.split('|')
+        .map(|step| {
+            parse_step(step.trim())
+                .with_context(|| format!("pipeline step \"{}\"", step.trim()))
+        })
+        .collect()
+}
+
+/// Parse one pipeline step
+fn parse_step(step: &str) -> Result<Step> {
+    let mut words = step.split_whitespace();
+    let name = words.next().unwrap_or_default();
+    let n = match words.next() {
+        Some(n) => n.parse()?,
+        None => 1,
+    };
+    if let Some(extra) = words.next() {
+        bail!("unexpected {extra}");
+    }
+    Ok(match name {
+        "trim" => Step::Trim,
+        "trim-left" => Step::TrimLeft,
+        "trim-right" => Step::TrimRight,
+        "pad-left" => Step::PadLeft(n),
+        "pad-right" => Step::PadRight(n),
+        "shift-up" => Step::ShiftUp(n),
+        "shift-down" => Step::ShiftDown(n),
+        "shift-left" => Step::ShiftLeft(n),
+        "shift-right" => Step::ShiftRight(n),
+        "mirror" => Step::Mirror,
+        "thin" => Step::Thin,
+        "thicken" => Step::Thicken,
+        "" => bail!("empty step"),
+        _ => bail!("unknown step"),
+    })
+}
+
+impl Step {
+    /// Apply step to a bitmap
+    ///
+    /// Blank bitmaps (such as spaces) are not trimmed.
+    fn apply(self, mut bmap: Bitmap) -> Result<Bitmap> {
+        let (width, height) = (bmap.width(), bmap.height());
+        let ink = bmap.ink_bounds();
+        let cols = |x, w| Rect {
+            x,
+            y: 0,
+            width: w,
+            height,
+        };
+        match self {
+            Step::Trim => {
+                if let Some(ink) = ink {
+                    bmap = bmap.crop(cols(ink.x, ink.width));
+                }
+            }
+            Step::TrimLeft => {
+                if let Some(ink) = ink {
+                    bmap = bmap.crop(cols(ink.x, width - ink.x));
+                }
+            }
+            Step::TrimRight => {
+                if let Some(ink) = ink {
+                    bmap = bmap.crop(cols(0, ink.x + ink.width));
+                }
+            }
+            Step::PadLeft(n) => {
+                for _ in 0..n {
+                    bmap.insert_col(0)?;
+                }
+            }
+            Step::PadRight(n) => {
+                for _ in 0..n {
+                    bmap.insert_col(bmap.width())?;
+                }
+            }
+            Step::ShiftUp(n) => {
+                for _ in 0..n.min(height) {
+                    bmap.delete_row(0);
+                    bmap.insert_row(height)?;
+                }
+            }
+            Step::ShiftDown(n) => {
+                for _ in 0..n.min(height) {
+                    bmap.delete_row(height - 1);
+                    bmap.insert_row(0)?;
+                }
+            }
+            Step::ShiftLeft(n) => {
+                for _ in 0..n.min(width) {
+                    bmap.delete_col(0);
+                    bmap.insert_col(width)?;
+                }
+            }
+            Step::ShiftRight(n) => {
+                for _ in 0..n.min(width) {
+                    bmap.delete_col(width - 1);
+                    bmap.insert_col(0)?;
+                }
+            }
+            Step::Mirror => bmap = bmap.mirror(),
+            Step::Thin => bmap = bmap.thin(),
+            Step::Thicken => bmap = bmap.thicken(),
+        }
+        Ok(bmap)
+    }
+}
+
+/// Apply a pipeline to a bitmap
+fn apply(steps: &[Step], bmap: Bitmap) -> Result<Bitmap> {
+    steps.iter().try_fold(bmap, |bmap, step| step.apply(bmap))
+}
+
+impl MapCommand {
+    /// Run map
+    pub fn run(&self) -> Result<()> {
+        let steps = parse_expr(&self.expr)?;
+        let buf = read_input(self.font.as_deref())?;
+        let from = self.from.or_else(|| Format::detect(&buf));
+        let to = match self.to {
+            Some(to) if !to.can_write() => bail!("cannot write {to} format"),
+            Some(to) => to,
+            None => from.filter(|f| f.can_write()).unwrap_or(Format::Tfon),
+        };
+        let font = Font::from_props(parse_properties(&buf, from)?.into_iter())?;
+        // fixed width is restored after mapping, padding to the widest glyph
+        let mut props = Vec::new();
+        for prop in font.props() {
+            props.push(match prop {
+                Prop::Bitmap(bmap) => Prop::Bitmap(apply(&steps, bmap)?),
+                Prop::Frame(bmap) => Prop::Frame(apply(&steps, bmap)?),
+                Prop::FontWidth(_) => continue,
+                prop => prop,
+            });
+        }
+        let mut mapped = Font::from_props(props.into_iter())?;
+        if font.width() > 0 {
+            mapped.monospace();
+        }
+        let mut writer = output(None, to)?;
+        let report = to.write_with(
+            &mut writer,
+            mapped.props(),
+            &WriteOptions::default(),
+        )?;
+        print_report(&report);
+        writer.flush()?;
+        Ok(())
+    }
+}
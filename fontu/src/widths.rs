@@ -0,0 +1,79 @@
+// widths.rs
+//
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+use tfon::{Align, WidthTable};
+
+/// Width table sidecar, for converting to a fixed width
+///
+/// ```toml
+/// width = 8
+/// align = "center"
+///
+/// [glyphs]
+/// "i" = "left"
+/// "U+2192" = 2
+/// ```
+///
+/// Placement is `left`, `center`, `right`, or a number of blank columns on
+/// the left.  Glyphs are keyed by character, code point or name.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Sidecar {
+    /// Fixed width (default: widest glyph)
+    width: Option<u8>,
+    /// Placement of glyphs not listed
+    align: Option<Placement>,
+    /// Placement of specific glyphs
+    #[serde(default)]
+    glyphs: BTreeMap<String, Placement>,
+}
+
+/// Placement of a glyph, as a name or column count
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Placement {
+    /// Blank columns on the left
+    Columns(u8),
+    /// `left`, `center` or `right`
+    Name(String),
+}
+
+impl Placement {
+    /// Convert to glyph alignment
+    fn align(&self) -> Result<Align> {
+        Ok(match self {
+            Placement::Columns(n) => Align::Columns(*n),
+            Placement::Name(name) => match name.as_str() {
+                "left" => Align::Left,
+                "center" => Align::Center,
+                "right" => Align::Right,
+                _ => bail!("unknown alignment: {name}"),
+            },
+        })
+    }
+}
+
+/// Read a width table sidecar
+pub fn read_widths(path: &Path) -> Result<WidthTable> {
+    let toml = std::fs::read_to_string(path)
+        .with_context(|| format!("reading {}", path.display()))?;
+    let sidecar: Sidecar = toml::from_str(&toml)
+        .with_context(|| format!("parsing {}", path.display()))?;
+    let mut table = WidthTable {
+        width: sidecar.width,
+        ..Default::default()
+    };
+    if let Some(align) = &sidecar.align {
+        table.align = align.align()?;
+    }
+    for (key, placement) in &sidecar.glyphs {
+        let Some(cp) = tfon::names::resolve(key) else {
+            bail!("unknown character: {key}");
+        };
+        table.glyphs.insert(cp, placement.align()?);
+    }
+    Ok(table)
+}
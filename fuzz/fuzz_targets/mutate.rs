@@ -0,0 +1,29 @@
+#![no_main]
+
+use libfuzzer_sys::arbitrary::{Arbitrary, Result, Unstructured};
+use libfuzzer_sys::fuzz_target;
+use tfon::fuzz_utils::{self, ValidProps};
+use tfon::{Format, WriteOptions};
+
+/// Write mutated props in a format, then exercise mutated source
+fn mutate(u: &mut Unstructured) -> Result<()> {
+    let ValidProps(mut props) = ValidProps::arbitrary(u)?;
+    for _ in 0..u.int_in_range(0..=4)? {
+        fuzz_utils::mutate_props(u, &mut props)?;
+    }
+    let format = Format::arbitrary(u)?;
+    let mut buf = Vec::new();
+    let options = WriteOptions::default();
+    if format
+        .write_with(&mut buf, props.into_iter(), &options)
+        .is_ok()
+    {
+        let src = String::from_utf8_lossy(&buf);
+        fuzz_utils::exercise(&fuzz_utils::mutate_text(u, &src)?);
+    }
+    Ok(())
+}
+
+fuzz_target!(|data: &[u8]| {
+    let _ = mutate(&mut Unstructured::new(data));
+});
@@ -0,0 +1,13 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use tfon::fuzz_utils::{self, ValidProps};
+use tfon::{Font, Format};
+
+fuzz_target!(|props: ValidProps| {
+    let font = Font::from_props(props.0.into_iter()).unwrap();
+    for format in Format::ALL.iter().filter(|f| f.can_read() && f.can_write()) {
+        let parsed = fuzz_utils::round_trip(&font, *format).unwrap();
+        assert!(parsed.glyphs().eq(font.glyphs()), "{format}");
+    }
+});
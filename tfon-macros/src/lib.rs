@@ -0,0 +1,510 @@
+//! Procedural macros for `tfon`
+//!
+//! These are re-exported by `tfon` with the `macros` feature.
+#![forbid(unsafe_code)]
+
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::quote;
+use std::path::PathBuf;
+use syn::punctuated::Punctuated;
+use syn::{parse_macro_input, LitStr, Token};
+
+/// Symbols for all ASCII + Latin 1 characters (as in `tfon::tfon`)
+const SYMBOL: &[&str] = &[
+    "NUL", "SOH", "STX", "ETX", "EOT", "ENQ", "ACK", "BEL", "BS", "HT", "LF",
+    "VT", "FF", "CR", "SO", "SI", "DLE", "DC1", "DC2", "DC3", "DC4", "NAK",
+    "SYN", "ETB", "CAN", "EM", "SUB", "ESC", "FS", "GS", "RS", "US", "SP", "!",
+    "\"", "#", "$", "%", "&", "'", "(", ")", "*", "+", ",", "-", ".", "/", "0",
+    "1", "2", "3", "4", "5", "6", "7", "8", "9", ":", ";", "<", "=", ">", "?",
+    "@", "A", "B", "C", "D", "E", "F", "G", "H", "I", "J", "K", "L", "M", "N",
+    "O", "P", "Q", "R", "S", "T", "U", "V", "W", "X", "Y", "Z", "[", "\\", "]",
+    "^", "_", "`", "a", "b", "c", "d", "e", "f", "g", "h", "i", "j", "k", "l",
+    "m", "n", "o", "p", "q", "r", "s", "t", "u", "v", "w", "x", "y", "z", "{",
+    "|", "}", "~", "DEL", "PAD", "HOP", "BPH", "NBH", "IND", "NEL", "SSA",
+    "ESA", "HTS", "HTJ", "LTS", "PLD", "PLU", "RI", "SS2", "SS3", "DCS", "PU1",
+    "PU2", "STS", "CCH", "MW", "SPA", "EPA", "SOS", "SGCI", "SCI", "CSI", "ST",
+    "OSC", "PM", "APC", "NBSP", "¡", "¢", "£", "¤", "¥", "¦", "§", "¨", "©",
+    "ª", "«", "¬", "SHY", "®", "¯", "°", "±", "²", "³", "´", "µ", "¶", "·",
+    "¸", "¹", "º", "»", "¼", "½", "¾", "¿", "À", "Á", "Â", "Ã", "Ä", "Å", "Æ",
+    "Ç", "È", "É", "Ê", "Ë", "Ì", "Í", "Î", "Ï", "Ð", "Ñ", "Ò", "Ó", "Ô", "Õ",
+    "Ö", "×", "Ø", "Ù", "Ú", "Û", "Ü", "Ý", "Þ", "ß", "à", "á", "â", "ã", "ä",
+    "å", "æ", "ç", "è", "é", "ê", "ë", "ì", "í", "î", "ï", "ð", "ñ", "ò", "ó",
+    "ô", "õ", "ö", "÷", "ø", "ù", "ú", "û", "ü", "ý", "þ", "ÿ",
+];
+
+/// Glyph parsed from a `tfon` source
+struct Glyph {
+    /// Code point
+    cp: u16,
+    /// Pixel rows
+    rows: Vec<String>,
+    /// Pixel rows of alternate frame
+    frame: Option<Vec<String>>,
+    /// Last change (time, author)
+    modified: Option<(String, String)>,
+}
+
+/// Font parsed from a `tfon` source
+#[derive(Default)]
+struct Font {
+    /// Font name
+    name: Option<String>,
+    /// Font number
+    number: Option<u8>,
+    /// Family of related fonts
+    family: Option<String>,
+    /// Style within family
+    style: Option<String>,
+    /// Copyright notice
+    copyright: Option<String>,
+    /// Foundry (or designer)
+    foundry: Option<String>,
+    /// Maximum character number
+    max_char_number: Option<u16>,
+    /// Fixed width
+    width: Option<u8>,
+    /// Pixel spacing between characters
+    char_spacing: Option<u8>,
+    /// Pixel spacing between lines
+    line_spacing: Option<u8>,
+    /// Baseline (rows above)
+    baseline: Option<u8>,
+    /// Ligatures (sequence, code point)
+    ligatures: Vec<(String, u32)>,
+    /// Glyphs, in source order
+    glyphs: Vec<Glyph>,
+}
+
+/// Get the pixels of a row, without any trailing comment
+fn pixel_row(line: &str) -> Option<&str> {
+    let row = line.split_once('#').map_or(line, |(row, _)| row).trim_end();
+    (!row.is_empty() && row.chars().all(|c| c == '.' || c == '@'))
+        .then_some(row)
+}
+
+/// Parse a header value
+fn value<T: std::str::FromStr>(key: &str, val: &str) -> Result<T, String> {
+    val.parse().map_err(|_| format!("invalid {key}: {val}"))
+}
+
+impl Font {
+    /// Parse a `tfon` source
+    ///
+    /// This follows `tfon::tfon::Parser`, which can't be used here since
+    /// `tfon` depends on this crate.  Unlike that parser, malformed blocks
+    /// are errors rather than skipped.
+    fn parse(src: &str) -> Result<Self, String> {
+        let mut font = Font::default();
+        let lines = src
+            .lines()
+            .map(|line| line.trim_end_matches('\r'))
+            .filter(|line| !line.is_empty() && !line.starts_with('#'));
+        for line in lines {
+            if let Some(row) = pixel_row(line) {
+                let glyph = font
+                    .glyphs
+                    .last_mut()
+                    .ok_or_else(|| format!("pixel row without ch: {line}"))?;
+                match &mut glyph.frame {
+                    Some(frame) => frame.push(row.to_string()),
+                    None => glyph.rows.push(row.to_string()),
+                }
+                continue;
+            }
+            let (key, val) = line
+                .split_once(": ")
+                .ok_or_else(|| format!("malformed line: {line}"))?;
+            match key {
+                "font_name" => font.name = Some(val.to_string()),
+                "font_number" => font.number = Some(value(key, val)?),
+                "family" => font.family = Some(val.to_string()),
+                "style" => font.style = Some(val.to_string()),
+                "copyright" => font.copyright = Some(val.to_string()),
+                "foundry" => font.foundry = Some(val.to_string()),
+                "max_char_number" => {
+                    font.max_char_number = Some(value(key, val)?);
+                }
+                "font_width" => font.width = Some(value(key, val)?),
+                "char_spacing" => font.char_spacing = Some(value(key, val)?),
+                "line_spacing" => font.line_spacing = Some(value(key, val)?),
+                "baseline" => font.baseline = Some(value(key, val)?),
+                "ligature" => {
+                    let (cp, seq) = val
+                        .split_once(' ')
+                        .ok_or_else(|| format!("invalid ligature: {val}"))?;
+                    // ligature code points must be 255 or below
+                    let cp: u8 = value(key, cp)?;
+                    font.ligatures.push((seq.to_string(), cp.into()));
+                }
+                "ch" => {
+                    let (cp, symbol) = val
+                        .split_once(' ')
+                        .ok_or_else(|| format!("invalid ch: {val}"))?;
+                    let cp: u16 = value(key, cp)?;
+                    if SYMBOL.get(usize::from(cp)) != Some(&symbol) {
+                        return Err(format!("invalid ch: {val}"));
+                    }
+                    font.glyphs.push(Glyph::new(cp));
+                }
+                "frame" => {
+                    let glyph = font
+                        .glyphs
+                        .last_mut()
+                        .filter(|g| g.frame.is_none() && val == "2")
+                        .ok_or_else(|| format!("misplaced frame: {val}"))?;
+                    glyph.frame = Some(Vec::new());
+                }
+                "modified" => {
+                    let (time, author) = val
+                        .split_once(' ')
+                        .ok_or_else(|| format!("invalid modified: {val}"))?;
+                    let glyph = font
+                        .glyphs
+                        .last_mut()
+                        .filter(|g| g.modified.is_none())
+                        .ok_or_else(|| format!("misplaced modified: {val}"))?;
+                    glyph.modified =
+                        Some((time.to_string(), author.to_string()));
+                }
+                _ => (),
+            }
+        }
+        font.validate()?;
+        Ok(font)
+    }
+
+    /// Check that glyphs are rectangular, and fit the font
+    ///
+    /// These are the checks of `tfon::Font::from_props`, so that loading
+    /// the embedded font can't fail at run time.
+    fn validate(&self) -> Result<(), String> {
+        if self.name.is_none() {
+            return Err("expected property 'font_name'".into());
+        }
+        if self.glyphs.is_empty() {
+            return Err("no glyphs".into());
+        }
+        let height = self.glyphs.first().map_or(0, |g| g.rows.len());
+        for glyph in &self.glyphs {
+            let width = glyph.rows.first().map_or(0, |r| r.len());
+            if glyph.rows.is_empty()
+                || glyph.rows.len() != height
+                || height > 255
+                || width > 255
+                || glyph.rows.iter().any(|r| r.len() != width)
+            {
+                return Err(format!("glyph {} has invalid size", glyph.cp));
+            }
+            if glyph.frame.as_ref().is_some_and(|frame| {
+                frame.len() != height || frame.iter().any(|r| r.len() != width)
+            }) {
+                return Err(format!(
+                    "glyph {} frame has invalid size",
+                    glyph.cp
+                ));
+            }
+            if self.width.is_some_and(|w| w > 0 && usize::from(w) != width) {
+                return Err(format!(
+                    "glyph {} width does not match font width",
+                    glyph.cp
+                ));
+            }
+            if let Some(mx) = self.max_char_number.filter(|mx| glyph.cp > *mx) {
+                return Err(format!(
+                    "code point {} above maximum character number {mx}",
+                    glyph.cp
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Get header bytes of a `tfon::StaticFont` table
+    fn static_header(&self) -> [u8; 6] {
+        let height = self.glyphs[0].rows.len() as u8;
+        [
+            self.number.unwrap_or(1),
+            height,
+            self.width.unwrap_or(0),
+            self.char_spacing.unwrap_or(0),
+            self.line_spacing.unwrap_or(0),
+            self.baseline.unwrap_or(height).min(height),
+        ]
+    }
+}
+
+impl Glyph {
+    /// Make a glyph with no pixel rows
+    fn new(cp: u16) -> Self {
+        Glyph {
+            cp,
+            rows: Vec::new(),
+            frame: None,
+            modified: None,
+        }
+    }
+
+    /// Pack pixels into bits, most significant bit first
+    fn bits(&self) -> Vec<u8> {
+        pack(&self.rows)
+    }
+}
+
+/// Pack pixel rows into bits, most significant bit first
+fn pack(rows: &[String]) -> Vec<u8> {
+    let mut bits = Vec::new();
+    for (pos, pix) in rows.iter().flat_map(|r| r.chars()).enumerate() {
+        if pos & 0b111 == 0 {
+            bits.push(0);
+        }
+        if pix == '@' {
+            bits[pos >> 3] |= 1 << (7 - (pos & 0b111));
+        }
+    }
+    bits
+}
+
+/// Embed a `tfon` font, parsed at compile time
+///
+/// The path is relative to the directory containing the crate's
+/// `Cargo.toml`.  The macro expands to an expression of type `tfon::Font`,
+/// built from static glyph data without any parsing or file I/O.
+///
+/// The glyph data is static, but a `Font` is a heap-allocated collection, so
+/// it is built when the expression is evaluated.  For no allocation at all,
+/// use `include_static_font!`.
+#[proc_macro]
+pub fn include_font(input: TokenStream) -> TokenStream {
+    let lit = parse_macro_input!(input as LitStr);
+    match expand(&lit) {
+        Ok(tokens) => tokens.into(),
+        Err(msg) => syn::Error::new(lit.span(), msg).to_compile_error().into(),
+    }
+}
+
+/// Embed a `tfon` font as a `tfon::StaticFont`, encoded at compile time
+///
+/// The path is relative to the directory containing the crate's
+/// `Cargo.toml`.  The macro expands to a constant expression, so it can
+/// initialize a `static` item.  Ligatures are not included.
+#[proc_macro]
+pub fn include_static_font(input: TokenStream) -> TokenStream {
+    let lit = parse_macro_input!(input as LitStr);
+    match expand_static(&lit) {
+        Ok(tokens) => tokens.into(),
+        Err(msg) => syn::Error::new(lit.span(), msg).to_compile_error().into(),
+    }
+}
+
+/// Make a `tfon::Bitmap` from inline pixel art
+///
+/// Rows are string literals of `.` (off) and `@` (on) pixels, either one
+/// literal per row or lines of one literal.  Row widths are checked at
+/// compile time, and the packed bits are a constant.  The `Bitmap` itself
+/// is made at run time, copying the bits to the heap, so this can't be used
+/// in a `const` or `static` item.
+///
+/// ```
+/// let arrow = tfon::glyph!(
+///     "..@..",
+///     ".@@@.",
+///     "@.@.@",
+/// );
+/// assert_eq!(arrow.width(), 5);
+/// ```
+///
+/// Rows of different widths are a compile error:
+/// ```compile_fail
+/// let arrow = tfon::glyph!(
+///     "..@..",
+///     ".@@@",
+/// );
+/// ```
+#[proc_macro]
+pub fn glyph(input: TokenStream) -> TokenStream {
+    let lits = parse_macro_input!(
+        input with Punctuated::<LitStr, Token![,]>::parse_terminated
+    );
+    match expand_glyph(&lits) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+/// Expand the `glyph!` macro
+fn expand_glyph(
+    lits: &Punctuated<LitStr, Token![,]>,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let mut glyph = Glyph::new(0);
+    let mut width = None;
+    for lit in lits {
+        let value = lit.value();
+        for row in value.lines().map(str::trim).filter(|r| !r.is_empty()) {
+            if row.chars().any(|c| c != '.' && c != '@') {
+                return Err(syn::Error::new(lit.span(), "invalid pixel row"));
+            }
+            if *width.get_or_insert(row.len()) != row.len() {
+                return Err(syn::Error::new(
+                    lit.span(),
+                    "pixel rows must have the same width",
+                ));
+            }
+            glyph.rows.push(row.to_string());
+        }
+    }
+    let width = width.unwrap_or(0);
+    if glyph.rows.len() > 255 || width > 255 {
+        return Err(syn::Error::new(Span::call_site(), "glyph too large"));
+    }
+    let height = glyph.rows.len() as u8;
+    let width = width as u8;
+    let bits = glyph.bits();
+    Ok(quote! {
+        {
+            const BITS: &[u8] = &[#(#bits),*];
+            ::tfon::Bitmap::from_bits(#height, #width, BITS.to_vec())
+                .expect("glyph checked at compile time")
+        }
+    })
+}
+
+/// Read and parse a font file, relative to the manifest directory
+fn read_font(lit: &LitStr) -> Result<(Font, LitStr), String> {
+    let dir = std::env::var("CARGO_MANIFEST_DIR").unwrap_or_default();
+    let path = PathBuf::from(dir).join(lit.value());
+    let src = std::fs::read_to_string(&path)
+        .map_err(|e| format!("{}: {e}", path.display()))?;
+    let font =
+        Font::parse(&src).map_err(|e| format!("{}: {e}", path.display()))?;
+    let path = LitStr::new(&path.to_string_lossy(), Span::call_site());
+    Ok((font, path))
+}
+
+/// Expand the `include_static_font!` macro
+///
+/// The table is built by `tfon::static_font::encode_const`, so the layout
+/// is only defined in one place.
+fn expand_static(lit: &LitStr) -> Result<proc_macro2::TokenStream, String> {
+    let (font, path) = read_font(lit)?;
+    let header = font.static_header();
+    let glyphs: std::collections::BTreeMap<u16, &Glyph> =
+        font.glyphs.iter().map(|glyph| (glyph.cp, glyph)).collect();
+    let glyphs = glyphs.values().map(|glyph| {
+        let (cp, width, bits) =
+            (glyph.cp, glyph.rows[0].len() as u8, glyph.bits());
+        quote! { (#cp, #width, &[#(#bits),*]) }
+    });
+    let name = font.name.unwrap_or_default();
+    Ok(quote! {
+        {
+            // rebuild when the font changes
+            const _: &[u8] = include_bytes!(#path);
+            const GLYPHS: &[::tfon::static_font::GlyphBits] = &[#(#glyphs),*];
+            const HEADER: [u8; 6] = [#(#header),*];
+            const LEN: usize =
+                ::tfon::static_font::encoded_len(HEADER[1], GLYPHS);
+            const DATA: [u8; LEN] =
+                ::tfon::static_font::encode_const(HEADER, GLYPHS);
+            ::tfon::StaticFont::new(#name, &DATA)
+        }
+    })
+}
+
+/// Expand the `include_font!` macro
+fn expand(lit: &LitStr) -> Result<proc_macro2::TokenStream, String> {
+    let (font, path) = read_font(lit)?;
+    let name = font.name.unwrap_or_default();
+    let mut header = vec![quote! { ::tfon::Prop::FontName(#name.into()) }];
+    if let Some(num) = font.number {
+        header.push(quote! { ::tfon::Prop::FontNumber(#num) });
+    }
+    if let Some(family) = &font.family {
+        header.push(quote! { ::tfon::Prop::Family(#family.into()) });
+    }
+    if let Some(style) = &font.style {
+        header.push(quote! { ::tfon::Prop::Style(#style.into()) });
+    }
+    if let Some(copyright) = &font.copyright {
+        header.push(quote! { ::tfon::Prop::Copyright(#copyright.into()) });
+    }
+    if let Some(foundry) = &font.foundry {
+        header.push(quote! { ::tfon::Prop::Foundry(#foundry.into()) });
+    }
+    if let Some(fw) = font.width {
+        header.push(quote! { ::tfon::Prop::FontWidth(#fw) });
+    }
+    if let Some(cs) = font.char_spacing {
+        header.push(quote! { ::tfon::Prop::CharSpacing(#cs) });
+    }
+    if let Some(ls) = font.line_spacing {
+        header.push(quote! { ::tfon::Prop::LineSpacing(#ls) });
+    }
+    if let Some(bl) = font.baseline {
+        header.push(quote! { ::tfon::Prop::Baseline(#bl) });
+    }
+    if let Some(mx) = font.max_char_number {
+        header.push(quote! { ::tfon::Prop::MaxCharNumber(#mx) });
+    }
+    for (seq, cp) in &font.ligatures {
+        header.push(quote! {
+            ::tfon::Prop::Ligature {
+                sequence: #seq.into(),
+                code_point: #cp,
+            }
+        });
+    }
+    let glyphs = font.glyphs.iter().map(|glyph| {
+        let cp = glyph.cp;
+        let height = glyph.rows.len() as u8;
+        let width = glyph.rows[0].len() as u8;
+        let bits = glyph.bits();
+        let frame = match &glyph.frame {
+            Some(frame) => {
+                let bits = pack(frame);
+                quote! { Some(&[#(#bits),*]) }
+            }
+            None => quote! { None },
+        };
+        let modified = match &glyph.modified {
+            Some((time, author)) => quote! { Some((#time, #author)) },
+            None => quote! { None },
+        };
+        quote! { (#cp, #height, #width, &[#(#bits),*], #frame, #modified) }
+    });
+    Ok(quote! {
+        {
+            // rebuild when the font changes
+            const _: &[u8] = include_bytes!(#path);
+            const GLYPHS: &[(
+                u16,
+                u8,
+                u8,
+                &[u8],
+                Option<&[u8]>,
+                Option<(&str, &str)>,
+            )] = &[#(#glyphs),*];
+            let bitmap = |height: u8, width: u8, bits: &[u8]| {
+                ::tfon::Bitmap::from_bits(height, width, bits.to_vec())
+                    .expect("glyph checked at compile time")
+            };
+            let mut props = ::std::vec![#(#header),*];
+            for (cp, height, width, bits, frame, modified) in GLYPHS {
+                props.push(::tfon::Prop::CodePoint(*cp));
+                props.push(::tfon::Prop::Bitmap(bitmap(*height, *width, bits)));
+                if let Some(frame) = frame {
+                    let frame = bitmap(*height, *width, frame);
+                    props.push(::tfon::Prop::Frame(frame));
+                }
+                if let Some((time, author)) = modified {
+                    props.push(::tfon::Prop::Modified {
+                        time: (*time).into(),
+                        author: (*author).into(),
+                    });
+                }
+            }
+            ::tfon::Font::from_props(props.into_iter())
+                .expect("font checked at compile time")
+        }
+    })
+}
@@ -0,0 +1,6 @@
+// paths are relative to the trybuild project, in target/tests/trybuild
+fn main() {
+    let _font = tfon::include_font!(
+        "../../../../tfon-macros/tests/ui/ch_symbol.tfon"
+    );
+}
@@ -0,0 +1,349 @@
+//! Texture atlas for GPU rendering
+//!
+//! All glyphs of a font are packed into a single power-of-two image, using
+//! a shelf packer: glyphs are sorted by height, then placed left to right
+//! on shelves.  The atlas grows (width first) until all glyphs fit.  Each
+//! glyph has a UV rectangle for texture lookup.
+//!
+//! Glyphs can also be stored as signed distance fields, for smooth scaling.
+use crate::common::{Bitmap, Error, Result};
+use crate::font::Font;
+use crate::sdf::Sdf;
+use std::io::Write;
+
+/// Largest atlas size (pixels)
+const MAX_SIZE: u32 = 4096;
+
+/// Glyph location within an atlas
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AtlasGlyph {
+    /// Code point
+    pub code_point: u16,
+    /// Left column
+    pub x: u32,
+    /// Top row
+    pub y: u32,
+    /// Width in pixels
+    pub width: u16,
+    /// Height in pixels
+    pub height: u16,
+    /// Horizontal offset from cursor to left edge
+    pub x_offset: i16,
+    /// Vertical offset from top of line to top edge
+    pub y_offset: i16,
+    /// Cursor advance
+    pub advance: u16,
+    /// Texture coordinates (left, top, right, bottom), from 0 to 1
+    pub uv: [f32; 4],
+}
+
+/// Texture atlas
+#[derive(Clone)]
+pub struct Atlas {
+    /// Font name
+    name: String,
+    /// Font height
+    font_height: u8,
+    /// Baseline (rows above)
+    baseline: u8,
+    /// Line height
+    line_height: u16,
+    /// Padding between glyphs
+    padding: u8,
+    /// Image width
+    width: u32,
+    /// Image height
+    height: u32,
+    /// Pixel values (0 off, 255 on)
+    pixels: Vec<u8>,
+    /// Glyph locations, ordered by code point
+    glyphs: Vec<AtlasGlyph>,
+}
+
+/// Grayscale glyph image, before packing
+struct Tile {
+    /// Code point
+    cp: u16,
+    /// Width in pixels
+    width: u16,
+    /// Height in pixels
+    height: u16,
+    /// Offset (left and top)
+    offset: i16,
+    /// Pixel values
+    pixels: Vec<u8>,
+}
+
+impl Tile {
+    /// Make a tile from a glyph bitmap
+    fn from_bitmap(cp: u16, bmap: &Bitmap) -> Self {
+        Tile {
+            cp,
+            width: u16::from(bmap.width()),
+            height: u16::from(bmap.height()),
+            offset: 0,
+            pixels: bmap.pixels().map(|p| if p { 255 } else { 0 }).collect(),
+        }
+    }
+
+    /// Make a tile from a signed distance field
+    fn from_sdf(cp: u16, sdf: Sdf) -> Self {
+        Tile {
+            cp,
+            width: sdf.width(),
+            height: sdf.height(),
+            offset: -i16::from(sdf.spread()),
+            pixels: sdf.values().to_vec(),
+        }
+    }
+}
+
+/// Place glyphs on shelves within an image
+///
+/// Returns positions for each glyph, in the same order, or `None` if they
+/// do not fit.
+fn pack(
+    sizes: &[(u32, u32)],
+    (width, height): (u32, u32),
+    padding: u32,
+) -> Option<Vec<(u32, u32)>> {
+    let mut order: Vec<usize> = (0..sizes.len()).collect();
+    order.sort_by_key(|i| std::cmp::Reverse(sizes[*i].1));
+    let mut pos = vec![(0, 0); sizes.len()];
+    let (mut x, mut y, mut shelf) = (0, 0, 0);
+    for i in order {
+        let (w, h) = sizes[i];
+        if x + w > width {
+            x = 0;
+            y += shelf + padding;
+            shelf = 0;
+        }
+        if x + w > width || y + h > height {
+            return None;
+        }
+        pos[i] = (x, y);
+        x += w + padding;
+        shelf = shelf.max(h);
+    }
+    Some(pos)
+}
+
+impl Atlas {
+    /// Build a texture atlas from a font
+    ///
+    /// * `padding` Blank pixels between glyphs.
+    pub fn new(font: &Font, padding: u8) -> Result<Self> {
+        let tiles = font
+            .glyphs()
+            .map(|(cp, bmap)| Tile::from_bitmap(cp, bmap))
+            .collect();
+        Self::build(font, tiles, padding)
+    }
+
+    /// Build a texture atlas of signed distance fields
+    ///
+    /// * `padding` Blank pixels between glyphs.
+    /// * `spread` Distance field spread (pixels).
+    pub fn with_sdf(font: &Font, padding: u8, spread: u8) -> Result<Self> {
+        let tiles = font
+            .glyphs()
+            .map(|(cp, bmap)| Tile::from_sdf(cp, Sdf::new(bmap, spread)))
+            .collect();
+        Self::build(font, tiles, padding)
+    }
+
+    /// Pack tiles into an atlas
+    fn build(font: &Font, tiles: Vec<Tile>, padding: u8) -> Result<Self> {
+        let sizes: Vec<(u32, u32)> = tiles
+            .iter()
+            .map(|t| (u32::from(t.width), u32::from(t.height)))
+            .collect();
+        let pad = u32::from(padding);
+        let (mut width, mut height) = (16, 16);
+        let pos = loop {
+            if let Some(pos) = pack(&sizes, (width, height), pad) {
+                break pos;
+            }
+            if width > height {
+                height *= 2;
+            } else {
+                width *= 2;
+            }
+            if height > MAX_SIZE {
+                return Err(Error::TooLarge());
+            }
+        };
+        let mut pixels = vec![0; (width * height) as usize];
+        let mut glyphs = Vec::with_capacity(pos.len());
+        let (sx, sy) = (width as f32, height as f32);
+        for (tile, (x, y)) in tiles.into_iter().zip(pos) {
+            let (w, h) = (tile.width, tile.height);
+            for (row, vals) in tile.pixels.chunks(usize::from(w)).enumerate() {
+                let start = ((y + row as u32) * width + x) as usize;
+                pixels[start..start + vals.len()].copy_from_slice(vals);
+            }
+            let glyph_width = w as i16 + 2 * tile.offset;
+            glyphs.push(AtlasGlyph {
+                code_point: tile.cp,
+                x,
+                y,
+                width: w,
+                height: h,
+                x_offset: tile.offset,
+                y_offset: tile.offset,
+                advance: glyph_width as u16 + u16::from(font.char_spacing()),
+                uv: [
+                    x as f32 / sx,
+                    y as f32 / sy,
+                    (x + u32::from(w)) as f32 / sx,
+                    (y + u32::from(h)) as f32 / sy,
+                ],
+            });
+        }
+        Ok(Atlas {
+            name: font.name().to_string(),
+            font_height: font.height(),
+            baseline: font.baseline(),
+            line_height: u16::from(font.height())
+                + u16::from(font.line_spacing()),
+            padding,
+            width,
+            height,
+            pixels,
+            glyphs,
+        })
+    }
+
+    /// Get image size (width, height)
+    pub fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    /// Get grayscale pixel values
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    /// Get glyph locations, ordered by code point
+    pub fn glyphs(&self) -> &[AtlasGlyph] {
+        &self.glyphs
+    }
+
+    /// Get the location of one glyph
+    pub fn glyph(&self, cp: u16) -> Option<&AtlasGlyph> {
+        self.glyphs
+            .binary_search_by_key(&cp, |g| g.code_point)
+            .ok()
+            .map(|i| &self.glyphs[i])
+    }
+
+    /// Write atlas descriptor as JSON
+    pub fn write_json<W: Write>(&self, mut writer: W) -> Result<()> {
+        writeln!(writer, "{{")?;
+        writeln!(writer, "  \"name\": {},", json_string(&self.name))?;
+        writeln!(writer, "  \"width\": {},", self.width)?;
+        writeln!(writer, "  \"height\": {},", self.height)?;
+        writeln!(writer, "  \"font_height\": {},", self.font_height)?;
+        writeln!(writer, "  \"baseline\": {},", self.baseline)?;
+        writeln!(writer, "  \"line_height\": {},", self.line_height)?;
+        writeln!(writer, "  \"glyphs\": [")?;
+        for (i, g) in self.glyphs.iter().enumerate() {
+            let comma = if i + 1 < self.glyphs.len() { "," } else { "" };
+            writeln!(
+                writer,
+                "    {{\"code_point\": {}, \"x\": {}, \"y\": {}, \
+                 \"width\": {}, \"height\": {}, \"x_offset\": {}, \
+                 \"y_offset\": {}, \"advance\": {}, \
+                 \"uv\": [{}, {}, {}, {}]}}{comma}",
+                g.code_point,
+                g.x,
+                g.y,
+                g.width,
+                g.height,
+                g.x_offset,
+                g.y_offset,
+                g.advance,
+                g.uv[0],
+                g.uv[1],
+                g.uv[2],
+                g.uv[3],
+            )?;
+        }
+        writeln!(writer, "  ]")?;
+        writeln!(writer, "}}")?;
+        Ok(())
+    }
+
+    /// Write atlas descriptor in BMFont text format
+    ///
+    /// * `page` File name of atlas image.
+    pub fn write_bmfont<W: Write>(
+        &self,
+        mut writer: W,
+        page: &str,
+    ) -> Result<()> {
+        let pad = self.padding;
+        writeln!(
+            writer,
+            "info face=\"{}\" size={} bold=0 italic=0 charset=\"\" \
+             unicode=1 stretchH=100 smooth=0 aa=1 padding=0,0,0,0 \
+             spacing={pad},{pad}",
+            self.name.replace('"', "'"),
+            self.font_height,
+        )?;
+        writeln!(
+            writer,
+            "common lineHeight={} base={} scaleW={} scaleH={} pages=1 \
+             packed=0",
+            self.line_height, self.baseline, self.width, self.height,
+        )?;
+        writeln!(writer, "page id=0 file=\"{page}\"")?;
+        writeln!(writer, "chars count={}", self.glyphs.len())?;
+        for g in &self.glyphs {
+            writeln!(
+                writer,
+                "char id={} x={} y={} width={} height={} xoffset={} \
+                 yoffset={} xadvance={} page=0 chnl=15",
+                g.code_point,
+                g.x,
+                g.y,
+                g.width,
+                g.height,
+                g.x_offset,
+                g.y_offset,
+                g.advance,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Write atlas image in PNG format
+    #[cfg(feature = "png")]
+    pub fn write_png<W: Write>(&self, writer: W) -> Result<()> {
+        crate::preview::write_png(
+            writer,
+            self.width,
+            self.height,
+            png::ColorType::Grayscale,
+            &self.pixels,
+        )
+    }
+}
+
+/// Quote a string for JSON
+fn json_string(val: &str) -> String {
+    let mut quoted = String::with_capacity(val.len() + 2);
+    quoted.push('"');
+    for c in val.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            c if c.is_control() => {
+                quoted.push_str(&format!("\\u{:04x}", u32::from(c)))
+            }
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
@@ -1,14 +1,34 @@
-//! Parse fonts in `bdf` format
+//! Parse and write fonts in `bdf` format
 //!
-use crate::common::{Bitmap, Prop};
-use std::str::{FromStr, Lines};
+use crate::common::{
+    Bitmap, Error, ParseContext, Prop, Result, WriteOptions, WriteReport,
+};
+use crate::cursor::{LineCursor, LinePolicy};
+use crate::format::PropParser;
+use std::borrow::Cow;
+use std::io::Write;
+use std::str::FromStr;
 
 /// Parser for `bdf` format
 pub struct Parser<'p> {
     /// Lines to parse
-    lines: Lines<'p>,
-    /// Pushed back line
-    line: Option<&'p str>,
+    lines: LineCursor<'p>,
+    /// Line number where the last property started
+    line: usize,
+    /// Font ascent (pixels above baseline)
+    ascent: Option<u8>,
+    /// Font descent (pixels below baseline)
+    descent: Option<u8>,
+    /// Font bounding box width
+    bbox_width: Option<u8>,
+    /// Pending bitmap of a parsed glyph
+    bitmap: Option<Bitmap>,
+    /// Number of glyphs declared by `CHARS` (with its line and number)
+    chars: Option<(u32, &'p str, usize)>,
+    /// Number of glyph blocks parsed
+    parsed: u32,
+    /// End of font reached
+    ended: bool,
 }
 
 impl<'p> Iterator for Parser<'p> {
@@ -19,85 +39,263 @@ impl<'p> Iterator for Parser<'p> {
     }
 }
 
+impl<'p> PropParser<'p> for Parser<'p> {
+    fn line_number(&self) -> Option<usize> {
+        Some(self.line)
+    }
+}
+
 impl<'p> Parser<'p> {
     /// Create a new `bdf` parser
     pub fn new(buf: &'p str) -> Self {
-        let lines = buf.lines();
-        Parser { lines, line: None }
-    }
-
-    /// Get the next line
-    fn next_line(&mut self) -> Option<&'p str> {
-        if self.line.is_some() {
-            self.line.take()
-        } else {
-            for line in self.lines.by_ref() {
-                if !line.is_empty() {
-                    return Some(line);
-                }
-            }
-            None
+        Parser {
+            lines: LineCursor::new(buf, LinePolicy::default()),
+            line: 0,
+            ascent: None,
+            descent: None,
+            bbox_width: None,
+            bitmap: None,
+            chars: None,
+            parsed: 0,
+            ended: false,
         }
     }
 
     /// Parse one property
     fn prop(&mut self) -> Option<Prop<'p>> {
-        let mut line = self.next_line()?;
-        let mut tok = line.split(' ');
-        if let Some(key) = tok.next() {
-            if key == "ENDFONT" {
-                return None;
-            } else if key == "ENDPROPERTIES" {
-                line = self.next_line()?;
+        if let Some(bmap) = self.bitmap.take() {
+            return Some(Prop::Bitmap(bmap));
+        }
+        if self.ended {
+            return None;
+        }
+        let Some(mut line) = self.lines.next_line() else {
+            return self.check_chars();
+        };
+        // skip structural lines, which are not font properties
+        while let Some(key) = line.split(' ').next() {
+            match key {
+                "ENDFONT" => return self.check_chars(),
+                "FONTBOUNDINGBOX" => {
+                    self.bbox_width = line
+                        .split(' ')
+                        .nth(1)
+                        .and_then(|v| u8::from_str(v).ok());
+                    line = self.lines.next_line()?
+                }
+                "STARTFONT" | "SIZE" | "STARTPROPERTIES" | "ENDPROPERTIES" => {
+                    let Some(next) = self.lines.next_line() else {
+                        return self.check_chars();
+                    };
+                    line = next;
+                }
+                _ => break,
             }
         }
+        self.line = self.lines.line_number();
         let mut tok = line.split(' ');
         match tok.next() {
-            Some("FONT") => tok.next().map(Prop::FontName),
-            Some("SIZE") => tok
-                .next()
-                .and_then(|sz| u8::from_str(sz).ok().map(Prop::FontHeight)),
-            Some("FONT_ASCENT") => tok
-                .next()
-                .and_then(|sz| u8::from_str(sz).ok().map(Prop::Baseline)),
-            Some("ENCODING") => tok
-                .next()
-                .and_then(|sz| u16::from_str(sz).ok().map(Prop::CodePoint)),
-            Some("DWIDTH") => tok.next().and_then(|w| match u8::from_str(w) {
-                Ok(width) => self.character(width),
-                _ => None,
-            }),
+            Some("FONT") => line
+                .strip_prefix("FONT ")
+                .map(|nm| Prop::FontName(nm.into())),
+            Some("CHARS") => match tok.next().map(u32::from_str) {
+                Some(Ok(count)) => {
+                    self.chars = Some((count, line, self.line));
+                    Some(Prop::CharCount(count))
+                }
+                _ => Some(Prop::Unknown(line)),
+            },
+            Some("COPYRIGHT") => Some(
+                quoted(line, "COPYRIGHT ")
+                    .map_or(Prop::Unknown(line), Prop::Copyright),
+            ),
+            Some("FOUNDRY") => Some(
+                quoted(line, "FOUNDRY ")
+                    .map_or(Prop::Unknown(line), Prop::Foundry),
+            ),
+            Some("FONT_ASCENT") => {
+                self.ascent = tok.next().and_then(|v| u8::from_str(v).ok());
+                self.ascent.map(Prop::Baseline)
+            }
+            Some("FONT_DESCENT") => {
+                self.descent = tok.next().and_then(|v| u8::from_str(v).ok());
+                match (self.ascent, self.descent) {
+                    (Some(ascent), Some(descent)) => ascent
+                        .checked_add(descent)
+                        .map(Prop::FontHeight)
+                        .or(Some(Prop::Unknown(line))),
+                    _ => Some(Prop::Unknown(line)),
+                }
+            }
+            Some("FAMILY_NAME") => Some(
+                quoted(line, "FAMILY_NAME ")
+                    .map_or(Prop::Unknown(line), Prop::Family),
+            ),
+            Some("WEIGHT_NAME") => Some(
+                quoted(line, "WEIGHT_NAME ")
+                    .map_or(Prop::Unknown(line), Prop::Style),
+            ),
+            Some("SPACING") => match (tok.next(), self.bbox_width) {
+                // character cell fonts have a fixed width
+                (Some("\"C\""), Some(width)) => Some(Prop::FontWidth(width)),
+                _ => Some(Prop::Unknown(line)),
+            },
+            Some("STARTCHAR") => {
+                self.parsed = self.parsed.saturating_add(1);
+                Some(self.glyph(line))
+            }
+            Some(
+                "ENCODING" | "SWIDTH" | "DWIDTH" | "BBX" | "BITMAP" | "ENDCHAR",
+            ) => Some(self.skip(line)),
+            Some(key) if is_pixel_row(key) => Some(self.skip(line)),
             _ => Some(Prop::Unknown(line)),
         }
     }
 
-    /// Parse a bitmap character property
-    fn character(&mut self, width: u8) -> Option<Prop<'p>> {
-        if width == 0 {
-            return None;
+    /// Parse a glyph block, from `STARTCHAR` to `ENDCHAR`
+    ///
+    /// If the block is malformed, it is skipped.
+    fn glyph(&mut self, start: &'p str) -> Prop<'p> {
+        let mut cp = None;
+        let mut width = None;
+        let mut bbx = None;
+        let mut rows: Option<Bitmap> = None;
+        let mut valid = true;
+        while let Some(line) = self.lines.next_line() {
+            let mut tok = line.split(' ');
+            match (tok.next(), &mut rows) {
+                (Some("ENDCHAR"), _) => break,
+                (Some("STARTCHAR" | "ENDFONT"), _) => {
+                    // missing ENDCHAR
+                    self.lines.push_back(line);
+                    valid = false;
+                    break;
+                }
+                (_, Some(rows)) => {
+                    valid &= is_pixel_row(line)
+                        && rows.try_push_row(HexBitIter::new(line)).is_ok();
+                }
+                (Some("ENCODING"), None) => {
+                    cp = tok.next().and_then(|v| u16::from_str(v).ok());
+                }
+                (Some("DWIDTH"), None) => {
+                    width = tok.next().and_then(|v| u8::from_str(v).ok());
+                }
+                (Some("BBX"), None) => {
+                    let v: Vec<i16> =
+                        tok.filter_map(|v| i16::from_str(v).ok()).collect();
+                    bbx = match v[..] {
+                        [w, h, x, y] => Some((w, h, x, y)),
+                        _ => None,
+                    };
+                }
+                (Some("BITMAP"), None) => {
+                    match bbx.and_then(|(w, ..)| u8::try_from(w).ok()) {
+                        Some(w) => rows = Some(Bitmap::new(w)),
+                        None => valid = false,
+                    }
+                }
+                _ => (),
+            }
         }
-        let mut line = self.next_line()?;
-        if !line.starts_with("BBX") {
-            return None;
+        let bitmap = match (cp, width, bbx, rows) {
+            (Some(_), Some(width), Some(bbx), Some(rows)) if valid => {
+                self.character(width, bbx, &rows)
+            }
+            _ => None,
+        };
+        match (cp, bitmap) {
+            (Some(cp), Some(bmap)) => {
+                self.bitmap = Some(bmap);
+                Prop::CodePoint(cp)
+            }
+            _ => Prop::skipped(start),
+        }
+    }
+
+    /// Skip a malformed block, up to the next `STARTCHAR`
+    fn skip(&mut self, line: &'p str) -> Prop<'p> {
+        while let Some(next) = self.lines.next_line() {
+            if next.starts_with("STARTCHAR") || next == "ENDFONT" {
+                self.lines.push_back(next);
+                break;
+            }
         }
-        line = self.next_line()?;
-        if line != "BITMAP" {
+        Prop::skipped(line)
+    }
+
+    /// Check the number of glyph blocks parsed against `CHARS`, at the end
+    /// of the font
+    ///
+    /// On a mismatch, the `CHARS` line is returned as a skipped block, since
+    /// the glyphs are still usable.
+    fn check_chars(&mut self) -> Option<Prop<'p>> {
+        self.ended = true;
+        let (chars, line, number) = self.chars.take()?;
+        (chars != self.parsed).then(|| {
+            self.line = number;
+            #[cfg(feature = "tracing")]
+            tracing::warn!(
+                chars,
+                parsed = self.parsed,
+                "CHARS does not match number of glyphs"
+            );
+            Prop::skipped(line)
+        })
+    }
+
+    /// Place bounding box rows within a character cell
+    fn character(
+        &self,
+        width: u8,
+        (_bbx_width, bbx_height, bbx_x, bbx_y): (i16, i16, i16, i16),
+        rows: &Bitmap,
+    ) -> Option<Bitmap> {
+        if width == 0 {
             return None;
         }
-        let mut bitmap = Bitmap::new(width);
-        while let Some(line) = self.next_line() {
-            if line == "ENDCHAR" {
-                break;
-            } else if is_pixel_row(line) {
-                bitmap.push_row(HexBitIter::new(line));
-            } else {
-                return None;
+        let (Some(ascent), Some(descent)) = (self.ascent, self.descent) else {
+            // no font metrics; bounding box is the character cell
+            let mut bitmap = Bitmap::new(width);
+            for y in 0..rows.height() {
+                bitmap.push_row((0..width).map(|x| rows.pixel(x, y)));
+            }
+            return Some(bitmap);
+        };
+        let height = ascent.checked_add(descent)?;
+        let top = i16::from(ascent) - (bbx_height + bbx_y);
+        let mut bitmap = Bitmap::new_blank(height, width);
+        for y in 0..rows.height() {
+            for x in 0..rows.width() {
+                let px = u8::try_from(bbx_x + i16::from(x));
+                let py = u8::try_from(top + i16::from(y));
+                if let (Ok(px), Ok(py)) = (px, py) {
+                    if px < width && py < height && rows.pixel(x, y) {
+                        bitmap.set_pixel(px, py, true);
+                    }
+                }
             }
         }
-        Some(Prop::Bitmap(bitmap))
+        Some(bitmap)
+    }
+}
+
+/// Get a quoted string property value, with doubled quotes unescaped
+fn quoted<'a>(line: &'a str, key: &str) -> Option<Cow<'a, str>> {
+    let val = line.strip_prefix(key)?.trim();
+    let val = val.strip_prefix('"')?.strip_suffix('"')?;
+    if val.contains("\"\"") {
+        Some(val.replace("\"\"", "\"").into())
+    } else {
+        Some(val.into())
     }
 }
 
+/// Escape a property string, doubling any quotes
+fn escape(val: &str) -> String {
+    val.replace('"', "\"\"")
+}
+
 /// Check if a line is a pixel row
 fn is_pixel_row(line: &str) -> bool {
     line.chars().all(|c| "1234567890ABCDEF".contains(c))
@@ -117,13 +315,12 @@ impl<'a> Iterator for HexBitIter<'a> {
         if self.bit > 0b0001 {
             self.bit >>= 1;
             Some((self.nybble & self.bit) != 0)
-        } else if !self.line.is_empty() {
+        } else {
+            let (first, rest) = self.line.split_first()?;
             self.bit = 0b1000;
-            self.nybble = hex_nybble(self.line[0]);
-            self.line = &self.line[1..];
+            self.nybble = hex_nybble(*first);
+            self.line = rest;
             Some((self.nybble & self.bit) != 0)
-        } else {
-            None
         }
     }
 }
@@ -152,3 +349,154 @@ fn hex_nybble(v: u8) -> u8 {
         0
     }
 }
+
+/// Write a font in `bdf` format
+pub fn write<'a, W: Write>(
+    writer: W,
+    props: impl Iterator<Item = Prop<'a>>,
+) -> Result<()> {
+    write_with(writer, props, &WriteOptions::default())?;
+    Ok(())
+}
+
+/// Write a font in `bdf` format, with options
+///
+/// Unknown properties are always dropped, since `bdf` properties must be
+/// counted and typed.  Ligatures are dropped too, and listed in the report.
+#[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+pub fn write_with<'a, W: Write>(
+    mut writer: W,
+    props: impl Iterator<Item = Prop<'a>>,
+    _options: &WriteOptions,
+) -> Result<WriteReport> {
+    let ctx = ParseContext::write("bdf");
+    let mut report = WriteReport::default();
+    let props: Vec<_> = props.collect();
+    let font_name = props
+        .iter()
+        .find_map(|v| v.font_name())
+        .ok_or_else(|| ctx.wrap(Error::Expected("FONT")))?;
+    let font_height = props
+        .iter()
+        .find_map(|v| v.font_height())
+        .ok_or_else(|| ctx.wrap(Error::Expected("SIZE")))?;
+    let ascent = props
+        .iter()
+        .find_map(|v| v.baseline())
+        .unwrap_or(font_height)
+        .min(font_height);
+    let descent = font_height - ascent;
+    let font_width = props.iter().find_map(|v| v.font_width()).unwrap_or(0);
+    // count glyphs which will be written (any CharCount is ignored)
+    let chars = props
+        .windows(2)
+        .filter(|v| matches!(v, [Prop::CodePoint(_), Prop::Bitmap(_)]))
+        .count();
+    let max_width = props
+        .iter()
+        .filter_map(|v| match v {
+            Prop::Bitmap(bmap) => Some(bmap.width()),
+            _ => None,
+        })
+        .max()
+        .unwrap_or(0);
+    writeln!(writer, "STARTFONT 2.1")?;
+    writeln!(writer, "FONT {font_name}")?;
+    writeln!(writer, "SIZE {font_height} 75 75")?;
+    writeln!(
+        writer,
+        "FONTBOUNDINGBOX {max_width} {font_height} 0 {}",
+        -i16::from(descent)
+    )?;
+    let family = props.iter().find_map(|v| v.family());
+    let style = props.iter().find_map(|v| v.style());
+    let copyright = props.iter().find_map(|v| v.copyright());
+    let foundry = props.iter().find_map(|v| v.foundry());
+    let count = 2
+        + usize::from(font_width > 0)
+        + usize::from(family.is_some())
+        + usize::from(style.is_some())
+        + usize::from(copyright.is_some())
+        + usize::from(foundry.is_some());
+    writeln!(writer, "STARTPROPERTIES {count}")?;
+    if font_width > 0 {
+        writeln!(writer, "SPACING \"C\"")?;
+    }
+    if let Some(family) = family {
+        writeln!(writer, "FAMILY_NAME \"{}\"", escape(family))?;
+    }
+    if let Some(style) = style {
+        writeln!(writer, "WEIGHT_NAME \"{}\"", escape(style))?;
+    }
+    if let Some(copyright) = copyright {
+        writeln!(writer, "COPYRIGHT \"{}\"", escape(copyright))?;
+    }
+    if let Some(foundry) = foundry {
+        writeln!(writer, "FOUNDRY \"{}\"", escape(foundry))?;
+    }
+    writeln!(writer, "FONT_ASCENT {ascent}")?;
+    writeln!(writer, "FONT_DESCENT {descent}")?;
+    writeln!(writer, "ENDPROPERTIES")?;
+    writeln!(writer, "CHARS {chars}")?;
+    let mut code_point = None;
+    let mut last = None;
+    for prop in props {
+        let at = ctx.at(prop.kind(), code_point.or(last));
+        match prop {
+            Prop::CodePoint(cp) => code_point = Some(cp),
+            Prop::Bitmap(bmap) => {
+                let cp = code_point
+                    .take()
+                    .ok_or_else(|| at.misplaced("ENCODING"))?;
+                last = Some(cp);
+                bmap.check_width(font_width, cp).map_err(|e| at.wrap(e))?;
+                let width = bmap.width();
+                let swidth = (u32::from(width) * 1000)
+                    .checked_div(u32::from(font_height))
+                    .unwrap_or(0);
+                writeln!(writer, "STARTCHAR U+{cp:04X}")?;
+                writeln!(writer, "ENCODING {cp}")?;
+                writeln!(writer, "SWIDTH {swidth} 0")?;
+                writeln!(writer, "DWIDTH {width} 0")?;
+                let bounds = bmap.ink_bounds().unwrap_or_default();
+                let ink = bmap.crop(bounds);
+                let bbx_y = if bounds.height > 0 {
+                    i16::from(ascent)
+                        - (i16::from(bounds.y) + i16::from(bounds.height))
+                } else {
+                    0
+                };
+                writeln!(
+                    writer,
+                    "BBX {} {} {} {bbx_y}",
+                    bounds.width, bounds.height, bounds.x
+                )?;
+                writeln!(writer, "BITMAP")?;
+                for y in 0..ink.height() {
+                    let mut byte = 0;
+                    for x in 0..ink.width() {
+                        if ink.pixel(x, y) {
+                            byte |= 0x80 >> (x & 0b111);
+                        }
+                        if x & 0b111 == 0b111 {
+                            write!(writer, "{byte:02X}")?;
+                            byte = 0;
+                        }
+                    }
+                    if ink.width() & 0b111 != 0 {
+                        write!(writer, "{byte:02X}")?;
+                    }
+                    writeln!(writer)?;
+                }
+                writeln!(writer, "ENDCHAR")?;
+            }
+            Prop::Unknown(_) => report.drop_unknown(&prop),
+            Prop::Ligature { sequence, .. } => {
+                report.ligatures.push(sequence.to_string())
+            }
+            _ => (),
+        }
+    }
+    writeln!(writer, "ENDFONT")?;
+    Ok(report)
+}
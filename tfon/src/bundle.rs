@@ -0,0 +1,148 @@
+//! Font bundles
+//!
+//! A bundle is a zip archive containing multiple fonts, plus a manifest
+//! listing each font file with its name, number and SHA-256 checksum:
+//! ```text
+//! file: font1.tfon
+//! font_name: tfon example
+//! font_number: 1
+//! sha256: 9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08
+//! ```
+use crate::checksum::{sha256_hex, verify};
+use crate::common::{Error, Result};
+use crate::font::Font;
+use crate::tfon;
+use std::io::{Read, Seek, Write};
+use std::str::FromStr;
+use zip::write::SimpleFileOptions;
+use zip::{ZipArchive, ZipWriter};
+
+/// Name of manifest file within a bundle
+pub const MANIFEST: &str = "manifest.txt";
+
+/// Manifest entry for one font
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Entry {
+    /// File name within bundle
+    pub file: String,
+    /// Font name
+    pub font_name: String,
+    /// Font number
+    pub font_number: u8,
+    /// SHA-256 checksum of file (hex)
+    pub sha256: String,
+}
+
+/// Bundle of fonts
+#[derive(Clone, Default)]
+pub struct Bundle {
+    /// Fonts, with file names
+    fonts: Vec<(String, Font)>,
+}
+
+/// Parse manifest entries
+fn parse_manifest(buf: &str) -> Result<Vec<Entry>> {
+    let mut entries: Vec<Entry> = Vec::new();
+    for line in buf.lines() {
+        let Some((key, val)) = line.split_once(": ") else {
+            continue;
+        };
+        if key == "file" {
+            entries.push(Entry {
+                file: val.to_string(),
+                ..Default::default()
+            });
+            continue;
+        }
+        let entry = entries.last_mut().ok_or(Error::Expected("file"))?;
+        match key {
+            "font_name" => entry.font_name = val.to_string(),
+            "font_number" => {
+                entry.font_number = u8::from_str(val)
+                    .map_err(|_| Error::Expected("font_number"))?;
+            }
+            "sha256" => entry.sha256 = val.to_string(),
+            _ => (),
+        }
+    }
+    Ok(entries)
+}
+
+impl Bundle {
+    /// Add a font to the bundle
+    ///
+    /// The font will be stored in `tfon` format, as `file`.
+    pub fn push(&mut self, file: &str, font: Font) {
+        self.fonts.push((file.to_string(), font));
+    }
+
+    /// Get all fonts, with file names
+    pub fn fonts(&self) -> impl Iterator<Item = (&str, &Font)> {
+        self.fonts.iter().map(|(file, font)| (file.as_str(), font))
+    }
+
+    /// Get a font by number
+    pub fn font(&self, number: u8) -> Option<&Font> {
+        self.fonts()
+            .find(|(_file, font)| font.number() == number)
+            .map(|(_file, font)| font)
+    }
+
+    /// Read a bundle from a zip archive
+    ///
+    /// Each file listed in the manifest is checked against its checksum.
+    pub fn read<R: Read + Seek>(reader: R) -> Result<Self> {
+        let mut archive =
+            ZipArchive::new(reader).map_err(std::io::Error::from)?;
+        let mut manifest = String::new();
+        archive
+            .by_name(MANIFEST)
+            .map_err(std::io::Error::from)?
+            .read_to_string(&mut manifest)?;
+        let mut bundle = Bundle::default();
+        for entry in parse_manifest(&manifest)? {
+            let mut src = String::new();
+            archive
+                .by_name(&entry.file)
+                .map_err(std::io::Error::from)?
+                .read_to_string(&mut src)?;
+            verify(src.as_bytes(), &entry.sha256, &entry.file)?;
+            let font = Font::from_file_source(&entry.file, &src)?;
+            bundle.fonts.push((entry.file, font));
+        }
+        Ok(bundle)
+    }
+
+    /// Write a bundle as a zip archive
+    ///
+    /// Returns the manifest entries.
+    pub fn write<W: Write + Seek>(&self, writer: W) -> Result<Vec<Entry>> {
+        let mut zip = ZipWriter::new(writer);
+        let options = SimpleFileOptions::default();
+        let mut entries = Vec::with_capacity(self.fonts.len());
+        for (file, font) in &self.fonts {
+            let mut data = Vec::new();
+            tfon::write(&mut data, font.props())?;
+            zip.start_file(file.as_str(), options)
+                .map_err(std::io::Error::from)?;
+            zip.write_all(&data)?;
+            entries.push(Entry {
+                file: file.clone(),
+                font_name: font.name().to_string(),
+                font_number: font.number(),
+                sha256: sha256_hex(&data),
+            });
+        }
+        zip.start_file(MANIFEST, options)
+            .map_err(std::io::Error::from)?;
+        for entry in &entries {
+            writeln!(zip, "file: {}", entry.file)?;
+            writeln!(zip, "font_name: {}", entry.font_name)?;
+            writeln!(zip, "font_number: {}", entry.font_number)?;
+            writeln!(zip, "sha256: {}", entry.sha256)?;
+            writeln!(zip)?;
+        }
+        zip.finish().map_err(std::io::Error::from)?;
+        Ok(entries)
+    }
+}
@@ -0,0 +1,62 @@
+//! Checksum verification
+//!
+//! A font file can have a sidecar file with its SHA-256 checksum, named by
+//! appending `.sha256` to the font file name.  The sidecar is in the format
+//! written by `sha256sum`:
+//! ```text
+//! 9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08  f12.tfon
+//! ```
+use crate::common::{Error, Result};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+
+/// Calculate SHA-256 checksum (hex)
+pub fn sha256_hex(data: &[u8]) -> String {
+    Sha256::digest(data)
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+/// Get the path of a sidecar checksum file
+pub fn sidecar(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(".sha256");
+    PathBuf::from(name)
+}
+
+/// Parse the checksum from the contents of a sidecar file
+pub fn parse_sidecar(text: &str) -> Option<&str> {
+    let sum = text.split_whitespace().next()?;
+    (sum.len() == 64 && sum.bytes().all(|b| b.is_ascii_hexdigit()))
+        .then_some(sum)
+}
+
+/// Verify data against a checksum
+///
+/// * `name` File name, for the mismatch error.
+pub fn verify(data: &[u8], sha256: &str, name: &str) -> Result<()> {
+    if sha256_hex(data).eq_ignore_ascii_case(sha256) {
+        Ok(())
+    } else {
+        Err(Error::ChecksumMismatch(name.to_string()))
+    }
+}
+
+/// Verify file data against its sidecar checksum, if present
+///
+/// Returns `true` if verified, or `false` if there is no sidecar file.
+pub fn verify_file(path: &Path, data: &[u8]) -> Result<bool> {
+    let text = match std::fs::read_to_string(sidecar(path)) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Ok(false);
+        }
+        Err(e) => return Err(e.into()),
+    };
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let sha256 = parse_sidecar(&text)
+        .ok_or_else(|| Error::ChecksumMismatch(name.to_string()))?;
+    verify(data, sha256, &name)?;
+    Ok(true)
+}
@@ -0,0 +1,110 @@
+// class.rs
+//
+use crate::common::{Error, Result};
+use std::fmt;
+use std::str::FromStr;
+
+/// Character class of a glyph, derived from its code point
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CharClass {
+    /// Numeric digit
+    Digit,
+    /// Uppercase letter
+    Uppercase,
+    /// Lowercase letter
+    Lowercase,
+    /// Letter without case
+    ///
+    /// As a subsetting preset, this includes upper and lowercase letters.
+    Letter,
+    /// White space
+    Space,
+    /// Pictogram, such as an arrow or private use glyph
+    Pictogram,
+    /// Punctuation or other symbol
+    Symbol,
+}
+
+/// Code point ranges of pictograms
+const PICTOGRAMS: &[(u16, u16)] = &[
+    (0x2190, 0x21FF), // arrows
+    (0x2300, 0x23FF), // miscellaneous technical
+    (0x2460, 0x24FF), // enclosed alphanumerics
+    (0x2500, 0x27BF), // box drawing, shapes, dingbats
+    (0x2B00, 0x2BFF), // miscellaneous symbols and arrows
+    (0xE000, 0xF8FF), // private use area
+];
+
+impl fmt::Display for CharClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for CharClass {
+    type Err = Error;
+
+    fn from_str(name: &str) -> Result<Self> {
+        CharClass::ALL
+            .iter()
+            .find(|class| class.name() == name)
+            .copied()
+            .ok_or_else(|| Error::UnknownClass(name.to_string()))
+    }
+}
+
+impl CharClass {
+    /// All character classes
+    pub const ALL: &'static [CharClass] = &[
+        CharClass::Digit,
+        CharClass::Uppercase,
+        CharClass::Lowercase,
+        CharClass::Letter,
+        CharClass::Space,
+        CharClass::Pictogram,
+        CharClass::Symbol,
+    ];
+
+    /// Get class preset name
+    pub fn name(self) -> &'static str {
+        match self {
+            CharClass::Digit => "digits",
+            CharClass::Uppercase => "uppercase",
+            CharClass::Lowercase => "lowercase",
+            CharClass::Letter => "letters",
+            CharClass::Space => "spaces",
+            CharClass::Pictogram => "pictograms",
+            CharClass::Symbol => "symbols",
+        }
+    }
+
+    /// Classify a code point
+    pub fn of(cp: u16) -> Self {
+        if PICTOGRAMS.iter().any(|(lo, hi)| (*lo..=*hi).contains(&cp)) {
+            return CharClass::Pictogram;
+        }
+        let Some(c) = char::from_u32(cp.into()) else {
+            return CharClass::Symbol;
+        };
+        if c.is_numeric() {
+            CharClass::Digit
+        } else if c.is_uppercase() {
+            CharClass::Uppercase
+        } else if c.is_lowercase() {
+            CharClass::Lowercase
+        } else if c.is_alphabetic() {
+            CharClass::Letter
+        } else if c.is_whitespace() {
+            CharClass::Space
+        } else {
+            CharClass::Symbol
+        }
+    }
+
+    /// Check if a class is included in this one, as a subsetting preset
+    pub fn includes(self, class: CharClass) -> bool {
+        self == class
+            || (self == CharClass::Letter
+                && matches!(class, CharClass::Uppercase | CharClass::Lowercase))
+    }
+}
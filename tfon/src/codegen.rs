@@ -0,0 +1,36 @@
+//! Helpers for generating C source code
+use std::fmt::Write;
+
+/// Make a C identifier from a name
+///
+/// Characters which are not allowed are replaced with `_`.
+pub(crate) fn ident(name: &str) -> String {
+    let mut id: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    if !id.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+        id.insert(0, '_');
+    }
+    id
+}
+
+/// Format bytes as a comma-separated list of hex literals
+pub(crate) fn hex_list(bytes: &[u8]) -> String {
+    let mut list = String::with_capacity(bytes.len() * 6);
+    for (i, b) in bytes.iter().enumerate() {
+        if i > 0 {
+            list.push_str(", ");
+        }
+        let _ = write!(list, "0x{b:02X}");
+    }
+    list
+}
+
+/// Format a code point as a C comment
+pub(crate) fn char_comment(cp: u16) -> String {
+    match char::from_u32(u32::from(cp)) {
+        Some(c) if c.is_ascii_graphic() && c != '\\' => format!("'{c}'"),
+        _ => format!("U+{cp:04X}"),
+    }
+}
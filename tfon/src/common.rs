@@ -1,5 +1,8 @@
 // common.rs
 //
+use crate::static_font::Constraints;
+use std::borrow::Cow;
+use std::fmt;
 use std::iter::repeat;
 
 /// Font parse/write error
@@ -13,12 +16,224 @@ pub enum Error {
 
     #[error("Unknown font format")]
     UnknownFormat(),
+
+    #[error("Missing glyph for '{0}'")]
+    MissingGlyph(char),
+
+    #[error("Bitmap too large")]
+    TooLarge(),
+
+    #[error("Missing font number {0}")]
+    MissingFont(u8),
+
+    #[error("Unsupported tag [{0}]")]
+    UnsupportedTag(String),
+
+    #[error("Glyph {0} width does not match font width")]
+    WidthMismatch(u16),
+
+    #[error("Glyph {0} does not fit character cell")]
+    GlyphTooLarge(u16),
+
+    #[error("Checksum mismatch: {0}")]
+    ChecksumMismatch(String),
+
+    #[error("Unknown character class: {0}")]
+    UnknownClass(String),
+
+    #[error("Invalid rotation: {0} degrees")]
+    InvalidRotation(u16),
+
+    #[error("Character cell too small: {0}x{1}")]
+    CellTooSmall(u8, u8),
+
+    #[error("Glyph {0} frame size does not match")]
+    FrameMismatch(u16),
+
+    #[error("Appeared before any '{0}' header")]
+    Misplaced(&'static str),
+
+    #[error("Input exceeds limit: {0} (max {1})")]
+    LimitExceeded(&'static str, usize),
+
+    #[error("Code point {0} above maximum character number {1}")]
+    OutOfRange(u16, u16),
+
+    #[error("Invalid pixel: {0}")]
+    InvalidPixel(char),
+
+    #[error("Pixel rows must have the same width")]
+    RaggedRows(),
+
+    #[error("Ligature code point {0} above 255")]
+    LigatureOutOfRange(u32),
+
+    #[error("Source contains {0} fonts; read them separately")]
+    MultipleFonts(usize),
+
+    #[error("{0}: {1}")]
+    Context(Box<ParseContext>, Box<Error>),
+}
+
+impl Error {
+    /// Get the context of an error, if any
+    pub fn context(&self) -> Option<&ParseContext> {
+        match self {
+            Error::Context(ctx, _err) => Some(ctx),
+            _ => None,
+        }
+    }
+
+    /// Get the underlying error, without context
+    pub fn root(&self) -> &Error {
+        match self {
+            Error::Context(_ctx, err) => err.root(),
+            err => err,
+        }
+    }
+}
+
+/// Context of an error: format, operation, line, property and code point
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ParseContext {
+    /// Format name
+    pub format: Option<&'static str>,
+    /// Operation, such as `write`
+    pub operation: &'static str,
+    /// Line number of source (from 1)
+    pub line: Option<usize>,
+    /// Property kind, such as `Bitmap`
+    pub property: Option<&'static str>,
+    /// Code point of glyph
+    pub code_point: Option<u16>,
+    /// Code point of previous glyph
+    pub previous: Option<u16>,
+}
+
+impl fmt::Display for ParseContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(format) = self.format {
+            write!(f, "{format} ")?;
+        }
+        write!(f, "{}", self.operation)?;
+        if let Some(line) = self.line {
+            write!(f, " at line {line}")?;
+        }
+        if let Some(property) = self.property {
+            write!(f, ": {property}")?;
+        }
+        if let Some(cp) = self.code_point {
+            write!(f, " for U+{cp:04X}")?;
+        }
+        if let Some(cp) = self.previous {
+            write!(f, " after U+{cp:04X}")?;
+        }
+        Ok(())
+    }
+}
+
+impl ParseContext {
+    /// Make a context for writing a format
+    pub(crate) fn write(format: &'static str) -> Self {
+        ParseContext {
+            format: Some(format),
+            operation: "write",
+            ..Default::default()
+        }
+    }
+
+    /// Make a context for parsing a format
+    pub(crate) fn parse(format: &'static str) -> Self {
+        ParseContext {
+            format: Some(format),
+            operation: "parse",
+            ..Default::default()
+        }
+    }
+
+    /// Make a context for loading a font from properties
+    pub(crate) fn load() -> Self {
+        ParseContext {
+            operation: "load",
+            ..Default::default()
+        }
+    }
+
+    /// Make a context for checking a source
+    pub(crate) fn check() -> Self {
+        ParseContext {
+            operation: "check",
+            ..Default::default()
+        }
+    }
+
+    /// Add source line number
+    pub(crate) fn line(&self, line: usize) -> Self {
+        ParseContext {
+            line: Some(line),
+            ..*self
+        }
+    }
+
+    /// Add property kind and code point
+    pub(crate) fn at(
+        &self,
+        kind: &'static str,
+        code_point: Option<u16>,
+    ) -> Self {
+        ParseContext {
+            property: Some(kind),
+            code_point,
+            ..*self
+        }
+    }
+
+    /// Make an error for a property before a required header
+    ///
+    /// The code point is that of the previous glyph.
+    pub(crate) fn misplaced(&self, header: &'static str) -> Error {
+        let ctx = ParseContext {
+            code_point: None,
+            previous: self.code_point,
+            ..*self
+        };
+        ctx.wrap(Error::Misplaced(header))
+    }
+
+    /// Attach context to an error (unless it already has context)
+    pub(crate) fn wrap(&self, err: Error) -> Error {
+        match err {
+            Error::Context(..) => err,
+            err => Error::Context(Box::new(*self), Box::new(err)),
+        }
+    }
+
+    /// Attach context to an error, including one without a format
+    ///
+    /// Errors from loading or checking properties get the format and
+    /// operation of this context, keeping their line, property and code
+    /// point.
+    pub(crate) fn within(&self, err: Error) -> Error {
+        match err {
+            Error::Context(ctx, err) if ctx.format.is_none() => {
+                let ctx = ParseContext {
+                    format: self.format,
+                    operation: self.operation,
+                    line: ctx.line.or(self.line),
+                    ..*ctx
+                };
+                Error::Context(Box::new(ctx), err)
+            }
+            err => self.wrap(err),
+        }
+    }
 }
 
 /// Result type
 pub(crate) type Result<T> = std::result::Result<T, Error>;
 
 /// Bitmap of pixels
+#[derive(Clone, PartialEq, Eq, Hash)]
 pub struct Bitmap {
     /// Height in pixels
     pub(crate) height: u8,
@@ -28,6 +243,144 @@ pub struct Bitmap {
     bmap: Vec<u8>,
 }
 
+/// Input size limits, for parsing untrusted fonts
+///
+/// The defaults allow any valid font, but still bound memory use.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Limits {
+    /// Maximum number of glyphs (including frames)
+    pub max_glyphs: usize,
+    /// Maximum total pixels of all glyphs
+    pub max_pixels: usize,
+    /// Maximum line length (bytes)
+    pub max_line: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            max_glyphs: 2 * 65_536,
+            max_pixels: 2 * 65_536 * 255 * 255,
+            max_line: 1 << 20,
+        }
+    }
+}
+
+impl Limits {
+    /// Check line lengths of a source
+    ///
+    /// The error context has the number of the first line which is too
+    /// long.
+    pub fn check_lines(&self, buf: &str) -> Result<()> {
+        if let Some(n) = buf.lines().position(|ln| ln.len() > self.max_line) {
+            let ctx = ParseContext::check().line(n + 1);
+            return Err(
+                ctx.wrap(Error::LimitExceeded("line length", self.max_line))
+            );
+        }
+        Ok(())
+    }
+
+    /// Collect properties, checking glyph and pixel limits
+    ///
+    /// Collection stops at the first property exceeding a limit.
+    pub fn collect<'a>(
+        &self,
+        props: impl Iterator<Item = Prop<'a>>,
+    ) -> Result<Vec<Prop<'a>>> {
+        let mut glyphs = 0;
+        let mut pixels = 0;
+        let mut collected = Vec::new();
+        for prop in props {
+            if let Prop::Bitmap(bmap) | Prop::Frame(bmap) = &prop {
+                glyphs += 1;
+                if glyphs > self.max_glyphs {
+                    return Err(Error::LimitExceeded(
+                        "glyphs",
+                        self.max_glyphs,
+                    ));
+                }
+                pixels += usize::from(bmap.height) * usize::from(bmap.width);
+                if pixels > self.max_pixels {
+                    return Err(Error::LimitExceeded(
+                        "pixels",
+                        self.max_pixels,
+                    ));
+                }
+            }
+            collected.push(prop);
+        }
+        Ok(collected)
+    }
+}
+
+/// Line ending for writing text formats
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LineEnding {
+    /// Line feed (`\n`)
+    #[default]
+    Lf,
+    /// Carriage return, line feed (`\r\n`), for legacy Windows tools
+    Crlf,
+}
+
+impl LineEnding {
+    /// Get the line ending of the current platform
+    pub const fn native() -> Self {
+        if cfg!(windows) {
+            LineEnding::Crlf
+        } else {
+            LineEnding::Lf
+        }
+    }
+
+    /// Get the line ending as a string
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::Crlf => "\r\n",
+        }
+    }
+}
+
+/// Options for writing fonts
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct WriteOptions {
+    /// Re-emit unknown properties, for formats which allow them
+    pub keep_unknown: bool,
+    /// Add ruler and row number comments to pixel grids (`tfon` only)
+    pub guides: bool,
+    /// Code point range to add blank scaffold glyphs for, where missing
+    /// (`tfon` only)
+    pub scaffold: Option<(u16, u16)>,
+    /// Target constraints to assert at compile time (`ctable` only)
+    pub constraints: Option<Constraints>,
+    /// Line ending (`ifnt` only)
+    pub line_ending: LineEnding,
+}
+
+/// Report from writing a font
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct WriteReport {
+    /// Keys of unknown properties which were dropped
+    pub dropped: Vec<String>,
+    /// Sequences of ligatures which were dropped
+    pub ligatures: Vec<String>,
+}
+
+/// Rectangle within a bitmap
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Rect {
+    /// Left column
+    pub x: u8,
+    /// Top row
+    pub y: u8,
+    /// Width in pixels
+    pub width: u8,
+    /// Height in pixels
+    pub height: u8,
+}
+
 /// Pixel iterator for bitmaps
 pub(crate) struct PixIter<'a> {
     bmap: &'a Bitmap,
@@ -35,16 +388,25 @@ pub(crate) struct PixIter<'a> {
 }
 
 /// Font property
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum Prop<'a> {
-    /// Unknown property
+    /// Unknown property (entire line)
     Unknown(&'a str),
     /// Font name
-    FontName(&'a str),
+    FontName(Cow<'a, str>),
+    /// Family of related fonts
+    Family(Cow<'a, str>),
+    /// Style within a family (such as `Bold`)
+    Style(Cow<'a, str>),
+    /// Copyright notice
+    Copyright(Cow<'a, str>),
+    /// Foundry (or designer) of the font
+    Foundry(Cow<'a, str>),
     /// Font number
     FontNumber(u8),
     /// Font height (pixels)
     FontHeight(u8),
-    /// Font width (pixels)
+    /// Font width (pixels; 0 for proportional)
     FontWidth(u8),
     /// Pixel spacing between characters
     CharSpacing(u8),
@@ -52,12 +414,49 @@ pub enum Prop<'a> {
     LineSpacing(u8),
     /// Baseline of characters
     Baseline(u8),
-    /// Maximum character number
+    /// Maximum character number (highest reserved code point)
     MaxCharNumber(u16),
+    /// Number of characters (glyphs)
+    CharCount(u32),
     /// Character code point
     CodePoint(u16),
     /// Character bitmap
     Bitmap(Bitmap),
+    /// Alternate frame of preceding character bitmap (for blinking)
+    Frame(Bitmap),
+    /// Last change to preceding character (for blame)
+    Modified {
+        /// Timestamp of change
+        time: Cow<'a, str>,
+        /// Author of change
+        author: Cow<'a, str>,
+    },
+    /// Character sequence rendered with one glyph
+    Ligature {
+        /// Characters replaced by the ligature
+        sequence: Cow<'a, str>,
+        /// Code point of ligature glyph
+        code_point: u32,
+    },
+    /// Malformed block skipped by parser (first line)
+    Skipped(&'a str),
+}
+
+impl fmt::Debug for Bitmap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let rows: Vec<String> = (0..self.height)
+            .map(|y| {
+                (0..self.width)
+                    .map(|x| if self.pixel(x, y) { '@' } else { '.' })
+                    .collect()
+            })
+            .collect();
+        f.debug_struct("Bitmap")
+            .field("height", &self.height)
+            .field("width", &self.width)
+            .field("rows", &rows)
+            .finish()
+    }
 }
 
 impl<'a> Iterator for PixIter<'a> {
@@ -87,10 +486,39 @@ impl Bitmap {
         }
     }
 
+    /// Create a blank bitmap with all pixels off
+    pub(crate) fn new_blank(height: u8, width: u8) -> Self {
+        let len = usize::from(height) * usize::from(width);
+        Bitmap {
+            height,
+            width,
+            bmap: vec![0; len.div_ceil(8)],
+        }
+    }
+
+    /// Create a bitmap from a pixel function
+    pub(crate) fn from_fn(
+        height: u8,
+        width: u8,
+        f: impl Fn(u8, u8) -> bool,
+    ) -> Self {
+        let mut bitmap = Bitmap::new(width);
+        for y in 0..height {
+            bitmap.push_row((0..width).map(|x| f(x, y)));
+        }
+        bitmap
+    }
+
     /// Create a bitmap from bits
-    pub fn from_bits(height: u8, width: u8, bmap: Vec<u8>) -> Option<Self> {
+    pub fn from_bits(height: u8, width: u8, mut bmap: Vec<u8>) -> Option<Self> {
         let len = usize::from(height) * usize::from(width);
-        if bmap.len() == (len + 7) / 8 {
+        if bmap.len() == len.div_ceil(8) {
+            // clear padding bits, so equal bitmaps have equal bits
+            if let Some(last) = bmap.last_mut() {
+                if len & 0b111 != 0 {
+                    *last &= 0xFF << (8 - (len & 0b111));
+                }
+            }
             Some(Bitmap {
                 height,
                 width,
@@ -101,6 +529,48 @@ impl Bitmap {
         }
     }
 
+    /// Create a bitmap from pixel art
+    ///
+    /// Each line is one row, with `.` for off and `@` for on pixels.
+    /// Leading and trailing whitespace, blank lines and `#` comment lines
+    /// are ignored.
+    pub fn from_art(art: &str) -> Result<Self> {
+        let rows: Vec<&str> = art
+            .lines()
+            .map(str::trim)
+            .filter(|row| !row.is_empty() && !row.starts_with('#'))
+            .collect();
+        let width = rows.first().map_or(0, |row| row.chars().count());
+        let width = u8::try_from(width).map_err(|_| Error::TooLarge())?;
+        let mut bitmap = Bitmap::new(width);
+        for row in rows {
+            if row.chars().count() != usize::from(width) {
+                return Err(Error::RaggedRows());
+            }
+            if let Some(pix) = row.chars().find(|c| *c != '.' && *c != '@') {
+                return Err(Error::InvalidPixel(pix));
+            }
+            bitmap.try_push_row(row.chars().map(|c| c == '@'))?;
+        }
+        Ok(bitmap)
+    }
+
+    /// Get pixel art, with one line per row (`.` off, `@` on)
+    pub fn to_art(&self) -> String {
+        let mut art = String::new();
+        for y in 0..self.height {
+            art.extend((0..self.width).map(|x| {
+                if self.pixel(x, y) {
+                    '@'
+                } else {
+                    '.'
+                }
+            }));
+            art.push('\n');
+        }
+        art
+    }
+
     /// Get pixel height
     pub fn height(&self) -> u8 {
         self.height
@@ -111,11 +581,59 @@ impl Bitmap {
         self.width
     }
 
+    /// Check width against a font width (0 for proportional)
+    pub(crate) fn check_width(&self, font_width: u8, cp: u16) -> Result<()> {
+        if font_width == 0 || self.width == font_width {
+            Ok(())
+        } else {
+            Err(Error::WidthMismatch(cp))
+        }
+    }
+
+    /// Get one pixel
+    ///
+    /// Pixels outside the bitmap are off.
+    pub fn pixel(&self, x: u8, y: u8) -> bool {
+        if x >= self.width || y >= self.height {
+            return false;
+        }
+        let pos = usize::from(y) * usize::from(self.width) + usize::from(x);
+        let off = pos >> 3;
+        let bit = 7 - (pos & 0b111);
+        (self.bmap[off] >> bit) & 1 != 0
+    }
+
+    /// Set one pixel
+    pub(crate) fn set_pixel(&mut self, x: u8, y: u8, on: bool) {
+        let pos = usize::from(y) * usize::from(self.width) + usize::from(x);
+        let off = pos >> 3;
+        let bit = 7 - (pos & 0b111);
+        if on {
+            self.bmap[off] |= 1 << bit;
+        } else {
+            self.bmap[off] &= !(1 << bit);
+        }
+    }
+
+    /// Push a row of pixels, failing if the bitmap is already 255 rows high
+    pub(crate) fn try_push_row(
+        &mut self,
+        row: impl Iterator<Item = bool>,
+    ) -> Result<()> {
+        if self.height == u8::MAX {
+            return Err(Error::TooLarge());
+        }
+        self.push_row(row);
+        Ok(())
+    }
+
     /// Push a row of pixels to the bitmap
+    ///
+    /// The bitmap must be less than 255 rows high.
     pub(crate) fn push_row(&mut self, row: impl Iterator<Item = bool>) {
         let width = usize::from(self.width);
-        let mut pos = usize::from(self.height) * width;
-        for pix in row.chain(repeat(false)).take(width) {
+        let start = usize::from(self.height) * width;
+        for (pos, pix) in (start..).zip(row.chain(repeat(false)).take(width)) {
             if pos & 0b111 == 0 {
                 self.bmap.push(0);
             }
@@ -124,7 +642,6 @@ impl Bitmap {
                 let bit = 7 - (pos & 0b111);
                 self.bmap[off] |= 1 << bit;
             }
-            pos += 1;
         }
         self.height += 1;
     }
@@ -138,17 +655,343 @@ impl Bitmap {
     pub fn into_bits(self) -> Vec<u8> {
         self.bmap
     }
+
+    /// Get a stable 64-bit fingerprint
+    ///
+    /// This is an FNV-1a hash of the size and packed bits, so it does not
+    /// change between versions or platforms.
+    pub fn fingerprint(&self) -> u64 {
+        const OFFSET: u64 = 0xCBF2_9CE4_8422_2325;
+        const PRIME: u64 = 0x0100_0000_01B3;
+        [self.height, self.width]
+            .iter()
+            .chain(&self.bmap)
+            .fold(OFFSET, |hash, b| (hash ^ u64::from(*b)).wrapping_mul(PRIME))
+    }
+
+    /// Get bounding box of all "on" pixels
+    ///
+    /// Returns `None` if no pixels are on.
+    pub fn ink_bounds(&self) -> Option<Rect> {
+        let mut bounds: Option<(u8, u8, u8, u8)> = None;
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if self.pixel(x, y) {
+                    let (x0, y0, x1, y1) = bounds.get_or_insert((x, y, x, y));
+                    *x0 = (*x0).min(x);
+                    *y0 = (*y0).min(y);
+                    *x1 = (*x1).max(x);
+                    *y1 = (*y1).max(y);
+                }
+            }
+        }
+        bounds.map(|(x0, y0, x1, y1)| Rect {
+            x: x0,
+            y: y0,
+            width: x1 - x0 + 1,
+            height: y1 - y0 + 1,
+        })
+    }
+
+    /// Crop to a rectangle
+    ///
+    /// Any part of the rectangle outside the bitmap is off.
+    pub fn crop(&self, rect: Rect) -> Bitmap {
+        Bitmap::from_fn(rect.height, rect.width, |x, y| {
+            match (rect.x.checked_add(x), rect.y.checked_add(y)) {
+                (Some(px), Some(py)) => self.pixel(px, py),
+                _ => false,
+            }
+        })
+    }
+
+    /// Insert a blank row before row `y`
+    ///
+    /// If `y` is past the bottom, the row is appended.
+    pub fn insert_row(&mut self, y: u8) -> Result<()> {
+        let height = self.height.checked_add(1).ok_or(Error::TooLarge())?;
+        *self = Bitmap::from_fn(height, self.width, |px, py| match py {
+            py if py < y => self.pixel(px, py),
+            py if py == y => false,
+            py => self.pixel(px, py - 1),
+        });
+        Ok(())
+    }
+
+    /// Delete row `y`
+    ///
+    /// If `y` is past the bottom, the bitmap is unchanged.
+    pub fn delete_row(&mut self, y: u8) {
+        if y < self.height {
+            *self = Bitmap::from_fn(self.height - 1, self.width, |px, py| {
+                if py < y {
+                    self.pixel(px, py)
+                } else {
+                    self.pixel(px, py + 1)
+                }
+            });
+        }
+    }
+
+    /// Insert a blank column before column `x`
+    ///
+    /// If `x` is past the right edge, the column is appended.
+    pub fn insert_col(&mut self, x: u8) -> Result<()> {
+        let width = self.width.checked_add(1).ok_or(Error::TooLarge())?;
+        *self = Bitmap::from_fn(self.height, width, |px, py| match px {
+            px if px < x => self.pixel(px, py),
+            px if px == x => false,
+            px => self.pixel(px - 1, py),
+        });
+        Ok(())
+    }
+
+    /// Delete column `x`
+    ///
+    /// If `x` is past the right edge, the bitmap is unchanged.
+    pub fn delete_col(&mut self, x: u8) {
+        if x < self.width {
+            *self = Bitmap::from_fn(self.height, self.width - 1, |px, py| {
+                if px < x {
+                    self.pixel(px, py)
+                } else {
+                    self.pixel(px + 1, py)
+                }
+            });
+        }
+    }
+
+    /// Crop to the bounding box of all "on" pixels
+    ///
+    /// If no pixels are on, the result is an empty (0x0) bitmap.
+    pub fn crop_to_ink(&self) -> Bitmap {
+        self.crop(self.ink_bounds().unwrap_or_default())
+    }
+
+    /// Rotate clockwise by a number of quarter turns
+    pub fn rotate(&self, quarter_turns: u8) -> Bitmap {
+        let (h, w) = (self.height, self.width);
+        match quarter_turns % 4 {
+            0 => self.clone(),
+            1 => Bitmap::from_fn(w, h, |x, y| self.pixel(y, h - 1 - x)),
+            2 => Bitmap::from_fn(h, w, |x, y| self.pixel(w - 1 - x, h - 1 - y)),
+            _ => Bitmap::from_fn(w, h, |x, y| self.pixel(w - 1 - y, x)),
+        }
+    }
+
+    /// Get one pixel, with signed coordinates
+    fn pixel_at(&self, x: i16, y: i16) -> bool {
+        match (u8::try_from(x), u8::try_from(y)) {
+            (Ok(x), Ok(y)) => self.pixel(x, y),
+            _ => false,
+        }
+    }
+
+    /// Check if a pixel is simple: changing it preserves topology
+    ///
+    /// This holds when the Yokoi 8-connectivity number of its neighborhood
+    /// is 1 -- no strokes are broken or joined, and no counters are opened
+    /// or filled.
+    fn is_simple(&self, x: u8, y: u8) -> bool {
+        let (x, y) = (i16::from(x), i16::from(y));
+        // neighbors: E, NE, N, NW, W, SW, S, SE
+        let n = [
+            (1, 0),
+            (1, -1),
+            (0, -1),
+            (-1, -1),
+            (-1, 0),
+            (-1, 1),
+            (0, 1),
+            (1, 1),
+        ]
+        .map(|(dx, dy)| !self.pixel_at(x + dx, y + dy));
+        let count = [0, 2, 4, 6]
+            .iter()
+            .filter(|&&k| n[k] && !(n[k + 1] && n[(k + 2) % 8]))
+            .count();
+        count == 1
+    }
+
+    /// Count "on" pixels in the 8-neighborhood of a pixel
+    fn neighbors(&self, x: u8, y: u8) -> usize {
+        let (x, y) = (i16::from(x), i16::from(y));
+        (-1..=1)
+            .flat_map(|dy| (-1..=1).map(move |dx| (dx, dy)))
+            .filter(|&(dx, dy)| {
+                (dx, dy) != (0, 0) && self.pixel_at(x + dx, y + dy)
+            })
+            .count()
+    }
+
+    /// Get lengths of the horizontal and vertical runs through a pixel
+    fn runs(&self, x: u8, y: u8) -> (u8, u8) {
+        let run = |dx: i16, dy: i16| {
+            (1..)
+                .take_while(|&i| {
+                    self.pixel_at(i16::from(x) + dx * i, i16::from(y) + dy * i)
+                })
+                .count() as u8
+        };
+        if self.pixel(x, y) {
+            (1 + run(-1, 0) + run(1, 0), 1 + run(0, -1) + run(0, 1))
+        } else {
+            (0, 0)
+        }
+    }
+
+    /// Thin strokes by one pixel, preserving topology
+    ///
+    /// Each stroke is thinned across its narrower direction: vertical
+    /// strokes lose their right edge, and horizontal strokes their bottom
+    /// edge.  Pixels are kept where removal would break a stroke, open a
+    /// counter or remove a stroke end, so one pixel wide strokes are
+    /// unchanged.
+    pub fn thin(&self) -> Bitmap {
+        let mut bmap = self.clone();
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let (h, v) = self.runs(x, y);
+                let (x, y) = (i16::from(x), i16::from(y));
+                let px = |dx, dy| self.pixel_at(x + dx, y + dy);
+                // stroke continues beside pixel, so removal leaves no notch
+                let right = !px(1, 0)
+                    && px(-1, 0)
+                    && (!px(0, -1) || px(-1, -1))
+                    && (!px(0, 1) || px(-1, 1));
+                let bottom = !px(0, 1)
+                    && px(0, -1)
+                    && (!px(-1, 0) || px(-1, -1))
+                    && (!px(1, 0) || px(1, -1));
+                let edge = (right && h <= v) || (bottom && v < h);
+                let (x, y) = (x as u8, y as u8);
+                if edge && bmap.neighbors(x, y) > 1 && bmap.is_simple(x, y) {
+                    bmap.set_pixel(x, y, false);
+                }
+            }
+        }
+        bmap
+    }
+
+    /// Thicken strokes by one pixel, preserving topology
+    ///
+    /// Each stroke is thickened across its narrower direction: vertical
+    /// strokes gain a right edge, and horizontal strokes a bottom edge.
+    /// Pixels are not added where that would join separate strokes or fill
+    /// a counter.  The bitmap size is unchanged.
+    pub fn thicken(&self) -> Bitmap {
+        let mut bmap = self.clone();
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if self.pixel(x, y) {
+                    continue;
+                }
+                let left = x.checked_sub(1).map(|lx| self.runs(lx, y));
+                let above = y.checked_sub(1).map(|ay| self.runs(x, ay));
+                let edge = left.is_some_and(|(h, v)| h > 0 && h <= v)
+                    || above.is_some_and(|(h, v)| v > 0 && v < h);
+                if edge && bmap.is_simple(x, y) {
+                    bmap.set_pixel(x, y, true);
+                }
+            }
+        }
+        bmap
+    }
+
+    /// Mirror horizontally (flip left to right)
+    pub fn mirror(&self) -> Bitmap {
+        let right = self.width.saturating_sub(1);
+        Bitmap::from_fn(self.height, self.width, |x, y| {
+            self.pixel(right - x, y)
+        })
+    }
+}
+
+impl WriteReport {
+    /// Record a dropped unknown property
+    pub(crate) fn drop_unknown(&mut self, prop: &Prop) {
+        if let Some(key) = prop.unknown_key() {
+            if !self.dropped.iter().any(|k| k == key) {
+                self.dropped.push(key.to_string());
+            }
+        }
+    }
 }
 
 impl<'a> Prop<'a> {
+    /// Get property kind, for error messages
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Prop::Unknown(_) => "Unknown",
+            Prop::FontName(_) => "FontName",
+            Prop::Family(_) => "Family",
+            Prop::Style(_) => "Style",
+            Prop::Copyright(_) => "Copyright",
+            Prop::Foundry(_) => "Foundry",
+            Prop::FontNumber(_) => "FontNumber",
+            Prop::FontHeight(_) => "FontHeight",
+            Prop::FontWidth(_) => "FontWidth",
+            Prop::CharSpacing(_) => "CharSpacing",
+            Prop::LineSpacing(_) => "LineSpacing",
+            Prop::Baseline(_) => "Baseline",
+            Prop::MaxCharNumber(_) => "MaxCharNumber",
+            Prop::CharCount(_) => "CharCount",
+            Prop::CodePoint(_) => "CodePoint",
+            Prop::Bitmap(_) => "Bitmap",
+            Prop::Frame(_) => "Frame",
+            Prop::Modified { .. } => "Modified",
+            Prop::Ligature { .. } => "Ligature",
+            Prop::Skipped(_) => "Skipped",
+        }
+    }
+
+    /// Make a skipped block property
+    pub(crate) fn skipped(line: &'a str) -> Self {
+        #[cfg(feature = "tracing")]
+        tracing::warn!(line, "skipped malformed block");
+        Prop::Skipped(line)
+    }
+
     /// Get font name
-    pub fn font_name(&self) -> Option<&'a str> {
+    pub fn font_name(&self) -> Option<&str> {
         match self {
             Prop::FontName(nm) => Some(nm),
             _ => None,
         }
     }
 
+    /// Get font family
+    pub fn family(&self) -> Option<&str> {
+        match self {
+            Prop::Family(family) => Some(family),
+            _ => None,
+        }
+    }
+
+    /// Get font style
+    pub fn style(&self) -> Option<&str> {
+        match self {
+            Prop::Style(style) => Some(style),
+            _ => None,
+        }
+    }
+
+    /// Get copyright notice
+    pub fn copyright(&self) -> Option<&str> {
+        match self {
+            Prop::Copyright(copyright) => Some(copyright),
+            _ => None,
+        }
+    }
+
+    /// Get foundry
+    pub fn foundry(&self) -> Option<&str> {
+        match self {
+            Prop::Foundry(foundry) => Some(foundry),
+            _ => None,
+        }
+    }
+
     /// Get font number
     pub fn font_number(&self) -> Option<u8> {
         match self {
@@ -182,6 +1025,48 @@ impl<'a> Prop<'a> {
         }
     }
 
+    /// Get font width (0 for proportional)
+    pub fn font_width(&self) -> Option<u8> {
+        match self {
+            Prop::FontWidth(fw) => Some(*fw),
+            _ => None,
+        }
+    }
+
+    /// Get maximum character number
+    pub fn max_char_number(&self) -> Option<u16> {
+        match self {
+            Prop::MaxCharNumber(mx) => Some(*mx),
+            _ => None,
+        }
+    }
+
+    /// Get number of characters
+    pub fn char_count(&self) -> Option<u32> {
+        match self {
+            Prop::CharCount(count) => Some(*count),
+            _ => None,
+        }
+    }
+
+    /// Get baseline
+    pub fn baseline(&self) -> Option<u8> {
+        match self {
+            Prop::Baseline(bl) => Some(*bl),
+            _ => None,
+        }
+    }
+
+    /// Get unknown property key
+    pub fn unknown_key(&self) -> Option<&'a str> {
+        match self {
+            Prop::Unknown(line) => {
+                line.split([':', '=', ' ']).next().map(|key| key.trim())
+            }
+            _ => None,
+        }
+    }
+
     /// Get code point
     pub fn code_point(&self) -> Option<u16> {
         match self {
@@ -189,4 +1074,15 @@ impl<'a> Prop<'a> {
             _ => None,
         }
     }
+
+    /// Get ligature sequence and code point
+    pub fn ligature(&self) -> Option<(&str, u32)> {
+        match self {
+            Prop::Ligature {
+                sequence,
+                code_point,
+            } => Some((sequence, *code_point)),
+            _ => None,
+        }
+    }
 }
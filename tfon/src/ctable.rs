@@ -0,0 +1,188 @@
+//! Export fonts as C tables with a sorted glyph index
+//!
+//! Glyph bits are packed row by row (most significant bit first) and
+//! padded to a byte at the end of each glyph.  An index of glyphs, sorted
+//! by code point, gives the width and bit offset of each one.  A range
+//! table groups the index into [segments](crate::segment) of contiguous code
+//! points (like a TrueType `cmap` format 4 table), so sparse fonts (such as
+//! a few pictograms far above ASCII) need no table entries for missing code
+//! points.  Lookup is a binary search of the ranges.
+//!
+//! With [Constraints], the height, widest glyph and glyph count are defined
+//! as macros, and checked with `_Static_assert`, so a font which does not
+//! fit the target fails to compile.
+//!
+//! The generated `tfon_lookup` helper finds a glyph:
+//! ```c
+//! const tfon_glyph_t *gl = tfon_lookup(&my_font, 0x2192);
+//! if (gl) {
+//!     const uint8_t *bits = my_font.bits + gl->offset;
+//! }
+//! ```
+use crate::codegen;
+use crate::common::{Error, ParseContext, Result, WriteOptions};
+use crate::font::Font;
+use crate::segment;
+use crate::static_font::Constraints;
+use std::io::Write;
+
+/// Shared type definitions and lookup helper
+const PRELUDE: &str = "\
+#include <stddef.h>
+#include <stdint.h>
+
+#ifndef TFON_CTABLE
+#define TFON_CTABLE
+/* Glyph index entry */
+typedef struct {
+    uint16_t code_point;
+    uint8_t width;
+    uint32_t offset;
+} tfon_glyph_t;
+
+/* Range of contiguous code points in glyph index */
+typedef struct {
+    uint16_t start;
+    uint16_t end;
+    uint16_t index;
+} tfon_range_t;
+
+/* Font with glyph index sorted by code point */
+typedef struct {
+    const uint8_t *bits;
+    const tfon_glyph_t *index;
+    uint16_t count;
+    const tfon_range_t *ranges;
+    uint16_t range_count;
+    uint8_t height;
+    uint8_t char_spacing;
+    uint8_t line_spacing;
+    uint8_t baseline;
+} tfon_font_t;
+
+/* Find a glyph by code point (binary search of ranges); NULL if missing */
+static inline const tfon_glyph_t *tfon_lookup(const tfon_font_t *font,
+    uint16_t cp)
+{
+    size_t lo = 0;
+    size_t hi = font->range_count;
+    while (lo < hi) {
+        size_t mid = lo + (hi - lo) / 2;
+        const tfon_range_t *r = &font->ranges[mid];
+        if (r->end < cp)
+            lo = mid + 1;
+        else if (r->start > cp)
+            hi = mid;
+        else
+            return &font->index[r->index + (cp - r->start)];
+    }
+    return NULL;
+}
+#endif
+";
+
+/// Write a font as a C table with a sorted glyph index
+pub fn write_c<W: Write>(writer: W, font: &Font, name: &str) -> Result<()> {
+    write_c_with(writer, font, name, &WriteOptions::default())
+}
+
+/// Write a font as a C table, with options
+pub fn write_c_with<W: Write>(
+    mut writer: W,
+    font: &Font,
+    name: &str,
+    options: &WriteOptions,
+) -> Result<()> {
+    let ctx = ParseContext::write("ctable");
+    let id = codegen::ident(name);
+    let count = u16::try_from(font.glyphs().count())
+        .map_err(|_| ctx.wrap(Error::TooLarge()))?;
+    writeln!(writer, "// {}", font.name())?;
+    writeln!(writer, "{PRELUDE}")?;
+    let mut bits = Vec::new();
+    let mut index = Vec::with_capacity(usize::from(count));
+    for (cp, bmap) in font.glyphs() {
+        let at = ctx.at("Bitmap", Some(cp));
+        if bmap.height() != font.height() {
+            return Err(at.wrap(Error::GlyphTooLarge(cp)));
+        }
+        let offset = u32::try_from(bits.len())
+            .map_err(|_| at.wrap(Error::TooLarge()))?;
+        index.push((cp, bmap.width(), offset));
+        bits.extend(bmap.clone().into_bits());
+    }
+    writeln!(writer, "static const uint8_t {id}_bits[] = {{")?;
+    for chunk in bits.chunks(12) {
+        writeln!(writer, "    {},", codegen::hex_list(chunk))?;
+    }
+    writeln!(writer, "}};")?;
+    writeln!(writer)?;
+    writeln!(writer, "static const tfon_glyph_t {id}_index[] = {{")?;
+    for (cp, width, offset) in index {
+        writeln!(
+            writer,
+            "    {{{cp:5}, {width:3}, {offset:6}}}, // {}",
+            codegen::char_comment(cp)
+        )?;
+    }
+    writeln!(writer, "}};")?;
+    writeln!(writer)?;
+    // an index entry is larger than a range, so gaps are never filled
+    let ranges = segment::segments(font.glyphs().map(|(cp, _)| cp), 0);
+    writeln!(writer, "static const tfon_range_t {id}_ranges[] = {{")?;
+    for seg in &ranges {
+        writeln!(
+            writer,
+            "    {{{:5}, {:5}, {:5}}},",
+            seg.start, seg.end, seg.index
+        )?;
+    }
+    writeln!(writer, "}};")?;
+    writeln!(writer)?;
+    writeln!(writer, "static const tfon_font_t {id} = {{")?;
+    writeln!(writer, "    {id}_bits,")?;
+    writeln!(writer, "    {id}_index,")?;
+    writeln!(writer, "    {count},")?;
+    writeln!(writer, "    {id}_ranges,")?;
+    writeln!(writer, "    {},", ranges.len())?;
+    writeln!(
+        writer,
+        "    {}, {}, {}, {}",
+        font.height(),
+        font.char_spacing(),
+        font.line_spacing(),
+        font.baseline()
+    )?;
+    writeln!(writer, "}};")?;
+    if let Some(constraints) = &options.constraints {
+        write_assertions(&mut writer, font, &id, constraints)?;
+    }
+    Ok(())
+}
+
+/// Write target constraints as compile-time assertions
+fn write_assertions<W: Write>(
+    writer: &mut W,
+    font: &Font,
+    id: &str,
+    constraints: &Constraints,
+) -> Result<()> {
+    let prefix = id.to_ascii_uppercase();
+    let max_width = font.glyphs().map(|(_cp, b)| b.width()).max();
+    writeln!(writer)?;
+    writeln!(writer, "#define {prefix}_HEIGHT {}", font.height())?;
+    writeln!(
+        writer,
+        "#define {prefix}_MAX_WIDTH {}",
+        max_width.unwrap_or(0)
+    )?;
+    writeln!(
+        writer,
+        "#define {prefix}_GLYPH_COUNT {}",
+        font.glyphs().count()
+    )?;
+    for (expr, msg) in constraints.assertions(&prefix) {
+        writeln!(writer, "_Static_assert({expr}, \"{msg}\");")?;
+    }
+    Ok(())
+}
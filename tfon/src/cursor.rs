@@ -0,0 +1,185 @@
+// cursor.rs
+//
+use std::str::Lines;
+
+/// Policy for skipping lines
+///
+/// Blank lines are always skipped.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct LinePolicy {
+    /// Trim whitespace from lines, so whitespace-only lines are skipped
+    pub(crate) trim: bool,
+    /// Comment line prefixes
+    pub(crate) comments: &'static [char],
+}
+
+impl LinePolicy {
+    /// Apply policy to a line, returning `None` if it is skipped
+    fn apply<'a>(&self, line: &'a str) -> Option<&'a str> {
+        let line = if self.trim { line.trim() } else { line };
+        (!line.is_empty() && !line.starts_with(self.comments)).then_some(line)
+    }
+}
+
+/// Cursor over lines of a text font
+///
+/// Lines are skipped according to a [LinePolicy].  One line of lookahead
+/// can be pushed back.
+#[derive(Clone, Debug)]
+pub(crate) struct LineCursor<'a> {
+    /// Lines to read
+    lines: Lines<'a>,
+    /// Pushed back line (with its line number)
+    pushed: Option<(&'a str, usize)>,
+    /// Number of lines consumed
+    consumed: usize,
+    /// Line number of last line read
+    number: usize,
+    /// Line number of line read before the last
+    previous: usize,
+    /// Policy for skipping lines
+    policy: LinePolicy,
+}
+
+impl<'a> LineCursor<'a> {
+    /// Create a new line cursor
+    pub(crate) fn new(buf: &'a str, policy: LinePolicy) -> Self {
+        LineCursor {
+            lines: buf.lines(),
+            pushed: None,
+            consumed: 0,
+            number: 0,
+            previous: 0,
+            policy,
+        }
+    }
+
+    /// Get the next line
+    pub(crate) fn next_line(&mut self) -> Option<&'a str> {
+        if let Some((line, number)) = self.pushed.take() {
+            self.previous = self.number;
+            self.number = number;
+            return Some(line);
+        }
+        for line in self.lines.by_ref() {
+            self.consumed += 1;
+            if let Some(line) = self.policy.apply(line) {
+                self.previous = self.number;
+                self.number = self.consumed;
+                return Some(line);
+            }
+        }
+        None
+    }
+
+    /// Push back the last line read, to be read again
+    ///
+    /// The line number goes back to that of the line read before it.
+    pub(crate) fn push_back(&mut self, line: &'a str) {
+        self.pushed = Some((line, self.number));
+        self.number = self.previous;
+    }
+
+    /// Peek at the next line, without consuming it
+    pub(crate) fn peek(&mut self) -> Option<&'a str> {
+        let line = self.next_line()?;
+        self.push_back(line);
+        Some(line)
+    }
+
+    /// Get the line number (from 1) of the last line read
+    ///
+    /// Before any line is read, this is 0.
+    pub(crate) fn line_number(&self) -> usize {
+        self.number
+    }
+
+    /// Get remaining lines, without consuming them
+    pub(crate) fn remaining(&self) -> impl Iterator<Item = &'a str> {
+        let policy = self.policy;
+        self.pushed.map(|(line, _number)| line).into_iter().chain(
+            self.lines
+                .clone()
+                .filter_map(move |line| policy.apply(line)),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SRC: &str = "# comment\na: 1\n\n  \nb: 2\n# c: 3\nd: 4\n";
+
+    /// Policy to trim lines and skip `#` comments
+    const TRIM: LinePolicy = LinePolicy {
+        trim: true,
+        comments: &['#'],
+    };
+
+    #[test]
+    fn blank_lines() {
+        let mut lines = LineCursor::new(SRC, LinePolicy::default());
+        assert_eq!(lines.next_line(), Some("# comment"));
+        assert_eq!(lines.next_line(), Some("a: 1"));
+        assert_eq!(lines.next_line(), Some("  "));
+        assert_eq!(lines.line_number(), 4);
+        let mut lines = LineCursor::new(SRC, TRIM);
+        assert_eq!(lines.next_line(), Some("a: 1"));
+        assert_eq!(lines.next_line(), Some("b: 2"));
+        assert_eq!(lines.next_line(), Some("d: 4"));
+        assert_eq!(lines.next_line(), None);
+    }
+
+    #[test]
+    fn lookahead() {
+        let mut lines = LineCursor::new(
+            SRC,
+            LinePolicy {
+                comments: &['#'],
+                ..Default::default()
+            },
+        );
+        assert_eq!(lines.peek(), Some("a: 1"));
+        assert_eq!(lines.line_number(), 0);
+        assert_eq!(lines.next_line(), Some("a: 1"));
+        assert_eq!(lines.peek(), Some("  "));
+        let remaining: Vec<_> = lines.remaining().collect();
+        assert_eq!(remaining, ["  ", "b: 2", "d: 4"]);
+        assert_eq!(lines.next_line(), Some("  "));
+        assert_eq!(lines.next_line(), Some("b: 2"));
+        assert_eq!(lines.remaining().count(), 1);
+    }
+
+    #[test]
+    fn push_back() {
+        let mut lines = LineCursor::new(SRC, TRIM);
+        assert_eq!(lines.next_line(), Some("a: 1"));
+        let line = lines.next_line().unwrap();
+        assert_eq!(line, "b: 2");
+        lines.push_back(line);
+        assert_eq!(lines.next_line(), Some("b: 2"));
+        assert_eq!(lines.next_line(), Some("d: 4"));
+        assert_eq!(lines.next_line(), None);
+    }
+
+    #[test]
+    fn line_numbers() {
+        let mut lines = LineCursor::new(SRC, TRIM);
+        assert_eq!(lines.line_number(), 0);
+        lines.next_line();
+        assert_eq!(lines.line_number(), 2);
+        let line = lines.next_line().unwrap();
+        assert_eq!(lines.line_number(), 5);
+        lines.push_back(line);
+        assert_eq!(lines.line_number(), 2);
+        assert_eq!(lines.peek(), Some("b: 2"));
+        assert_eq!(lines.line_number(), 2);
+        lines.next_line();
+        assert_eq!(lines.line_number(), 5);
+        lines.next_line();
+        assert_eq!(lines.line_number(), 7);
+        assert_eq!(lines.next_line(), None);
+        assert_eq!(lines.line_number(), 7);
+    }
+}
@@ -0,0 +1,105 @@
+//! Per-glyph differences between fonts
+//!
+//! Each added, removed or changed glyph is compared pixel by pixel, so a
+//! report can be sorted to show materially changed glyphs first.  Glyphs
+//! of different sizes are aligned at the top left; added and removed glyphs
+//! are compared with a blank glyph.
+use crate::common::Bitmap;
+use crate::font::Font;
+use std::fmt;
+
+/// Kind of glyph change
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Change {
+    /// Glyph added
+    Added,
+    /// Glyph removed
+    Removed,
+    /// Glyph changed
+    Changed,
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Change::Added => write!(f, "added"),
+            Change::Removed => write!(f, "removed"),
+            Change::Changed => write!(f, "changed"),
+        }
+    }
+}
+
+/// Difference of one glyph
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GlyphDiff {
+    /// Code point
+    pub code_point: u16,
+    /// Kind of change
+    pub change: Change,
+    /// Number of changed pixels
+    pub pixels: u32,
+    /// Number of pixels compared
+    pub total: u32,
+}
+
+impl GlyphDiff {
+    /// Get percentage of changed pixels
+    pub fn percent(&self) -> f32 {
+        if self.total > 0 {
+            self.pixels as f32 * 100.0 / self.total as f32
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Compare glyphs of two fonts
+///
+/// Differences are sorted by number of changed pixels (most first), then
+/// by code point.  Glyphs with identical pixels but a different size are
+/// included, with zero changed pixels.
+pub fn diff(old: &Font, new: &Font) -> Vec<GlyphDiff> {
+    let mut diffs = Vec::new();
+    for (cp, bmap) in new.glyphs() {
+        match old.glyph(cp) {
+            Some(prev) if prev == bmap => (),
+            Some(prev) => diffs.push(compare(cp, Change::Changed, prev, bmap)),
+            None => diffs.push(compare(cp, Change::Added, &blank(), bmap)),
+        }
+    }
+    for (cp, bmap) in old.glyphs() {
+        if new.glyph(cp).is_none() {
+            diffs.push(compare(cp, Change::Removed, bmap, &blank()));
+        }
+    }
+    diffs.sort_by_key(|d| (std::cmp::Reverse(d.pixels), d.code_point));
+    diffs
+}
+
+/// Make an empty bitmap
+fn blank() -> Bitmap {
+    Bitmap::new_blank(0, 0)
+}
+
+/// Compare two bitmaps, aligned at the top left
+fn compare(cp: u16, change: Change, a: &Bitmap, b: &Bitmap) -> GlyphDiff {
+    let height = a.height().max(b.height());
+    let width = a.width().max(b.width());
+    let lit = |bmap: &Bitmap, x: u8, y: u8| {
+        x < bmap.width() && y < bmap.height() && bmap.pixel(x, y)
+    };
+    let mut pixels = 0;
+    for y in 0..height {
+        for x in 0..width {
+            if lit(a, x, y) != lit(b, x, y) {
+                pixels += 1;
+            }
+        }
+    }
+    GlyphDiff {
+        code_point: cp,
+        change,
+        pixels,
+        total: u32::from(height) * u32::from(width),
+    }
+}
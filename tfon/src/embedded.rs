@@ -0,0 +1,167 @@
+//! Render text with `embedded-graphics`
+//!
+//! [TextStyle] implements `TextRenderer`, so fonts can be drawn on any
+//! `DrawTarget` using `embedded_graphics::text::Text`.
+use crate::common::Bitmap;
+use crate::font::Font;
+use embedded_graphics::prelude::*;
+use embedded_graphics::primitives::Rectangle;
+use embedded_graphics::text::renderer::{
+    CharacterStyle, TextMetrics, TextRenderer,
+};
+use embedded_graphics::text::Baseline;
+use embedded_graphics::Pixel;
+
+/// Text style for drawing with a font
+#[derive(Clone, Copy)]
+pub struct TextStyle<'a, C> {
+    /// Font
+    pub font: &'a Font,
+    /// Text color
+    pub text_color: Option<C>,
+    /// Background color
+    pub background_color: Option<C>,
+}
+
+impl<'a, C: PixelColor> TextStyle<'a, C> {
+    /// Create a new text style
+    pub fn new(font: &'a Font, text_color: C) -> Self {
+        TextStyle {
+            font,
+            text_color: Some(text_color),
+            background_color: None,
+        }
+    }
+
+    /// Get glyph for a character
+    ///
+    /// Missing characters are replaced with `?`, if available.
+    fn glyph(&self, c: char) -> Option<&'a Bitmap> {
+        self.font
+            .glyph_char(c)
+            .or_else(|| self.font.glyph_char('?'))
+    }
+
+    /// Get vertical offset from baseline to top of glyphs
+    fn baseline_offset(&self, baseline: Baseline) -> i32 {
+        let height = i32::from(self.font.height());
+        match baseline {
+            Baseline::Top => 0,
+            Baseline::Bottom => height - 1,
+            Baseline::Middle => (height - 1) / 2,
+            Baseline::Alphabetic => i32::from(self.font.baseline()) - 1,
+        }
+    }
+
+    /// Get top left point of text
+    fn top_left(&self, position: Point, baseline: Baseline) -> Point {
+        position - Point::new(0, self.baseline_offset(baseline))
+    }
+
+    /// Get advance width of a character
+    fn advance(&self, c: char) -> u32 {
+        self.glyph(c).map_or(0, |g| {
+            u32::from(g.width()) + u32::from(self.font.char_spacing())
+        })
+    }
+
+    /// Fill background, if a color is set
+    fn fill_background<D>(
+        &self,
+        top_left: Point,
+        width: u32,
+        target: &mut D,
+    ) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = C>,
+    {
+        if let Some(color) = self.background_color {
+            let size = Size::new(width, u32::from(self.font.height()));
+            target.fill_solid(&Rectangle::new(top_left, size), color)?;
+        }
+        Ok(())
+    }
+}
+
+impl<C: PixelColor> CharacterStyle for TextStyle<'_, C> {
+    type Color = C;
+
+    fn set_text_color(&mut self, text_color: Option<C>) {
+        self.text_color = text_color;
+    }
+
+    fn set_background_color(&mut self, background_color: Option<C>) {
+        self.background_color = background_color;
+    }
+}
+
+impl<C: PixelColor> TextRenderer for TextStyle<'_, C> {
+    type Color = C;
+
+    fn draw_string<D>(
+        &self,
+        text: &str,
+        position: Point,
+        baseline: Baseline,
+        target: &mut D,
+    ) -> Result<Point, D::Error>
+    where
+        D: DrawTarget<Color = C>,
+    {
+        let mut top_left = self.top_left(position, baseline);
+        for c in text.chars() {
+            let Some(glyph) = self.glyph(c) else {
+                continue;
+            };
+            let advance = self.advance(c);
+            self.fill_background(top_left, advance, target)?;
+            if let Some(color) = self.text_color {
+                let pixels = (0..glyph.height()).flat_map(|y| {
+                    (0..glyph.width()).filter(move |x| glyph.pixel(*x, y)).map(
+                        move |x| {
+                            let pt = Point::new(i32::from(x), i32::from(y));
+                            Pixel(top_left + pt, color)
+                        },
+                    )
+                });
+                target.draw_iter(pixels)?;
+            }
+            top_left.x += advance as i32;
+        }
+        Ok(Point::new(top_left.x, position.y))
+    }
+
+    fn draw_whitespace<D>(
+        &self,
+        width: u32,
+        position: Point,
+        baseline: Baseline,
+        target: &mut D,
+    ) -> Result<Point, D::Error>
+    where
+        D: DrawTarget<Color = C>,
+    {
+        let top_left = self.top_left(position, baseline);
+        self.fill_background(top_left, width, target)?;
+        Ok(position + Point::new(width as i32, 0))
+    }
+
+    fn measure_string(
+        &self,
+        text: &str,
+        position: Point,
+        baseline: Baseline,
+    ) -> TextMetrics {
+        let width: u32 = text.chars().map(|c| self.advance(c)).sum();
+        let top_left = self.top_left(position, baseline);
+        let size = Size::new(width, u32::from(self.font.height()));
+        TextMetrics {
+            bounding_box: Rectangle::new(top_left, size),
+            next_position: position + Point::new(width as i32, 0),
+        }
+    }
+
+    fn line_height(&self) -> u32 {
+        u32::from(self.font.height()) + u32::from(self.font.line_spacing())
+    }
+}
@@ -0,0 +1,82 @@
+//! Export fonts as ESC/POS user-defined characters
+//!
+//! Receipt printers accept downloaded characters with the `ESC &` command,
+//! as column-packed bitmaps: each column is `y` bytes, with the most
+//! significant bit at the top.  Only code points 32 to 126 can be defined.
+use crate::common::{Bitmap, Error, ParseContext, Prop, Result};
+use std::io::Write;
+
+/// Escape byte
+const ESC: u8 = 0x1B;
+
+/// Maximum character height (pixels)
+const MAX_HEIGHT: u8 = 24;
+
+/// Write a font as ESC/POS user-defined characters
+///
+/// A command to select the user-defined character set follows the
+/// character definitions.  Glyphs outside the printable ASCII range are
+/// skipped.
+pub fn write<'a, W: Write>(
+    mut writer: W,
+    props: impl Iterator<Item = Prop<'a>>,
+) -> Result<()> {
+    let ctx = ParseContext::write("escpos");
+    let mut glyphs: Vec<(u8, Bitmap)> = Vec::new();
+    let mut code_point = None;
+    for prop in props {
+        let at = ctx.at(prop.kind(), code_point);
+        match prop {
+            Prop::CodePoint(cp) => code_point = Some(cp),
+            Prop::Bitmap(bmap) => {
+                let cp = code_point.take().ok_or_else(|| at.misplaced("ch"))?;
+                if let Ok(c @ 32..=126) = u8::try_from(cp) {
+                    glyphs.push((c, bmap));
+                }
+            }
+            _ => (),
+        }
+    }
+    glyphs.sort_by_key(|(c, _bmap)| *c);
+    let height = glyphs.iter().map(|(_c, b)| b.height()).max().unwrap_or(0);
+    if height > MAX_HEIGHT {
+        return Err(ctx.wrap(Error::TooLarge()));
+    }
+    let y = height.div_ceil(8).max(1);
+    // one command per run of consecutive characters
+    let mut start = 0;
+    while start < glyphs.len() {
+        let mut end = start + 1;
+        while end < glyphs.len() && glyphs[end].0 == glyphs[end - 1].0 + 1 {
+            end += 1;
+        }
+        let run = &glyphs[start..end];
+        writer.write_all(&[ESC, b'&', y, run[0].0, run[run.len() - 1].0])?;
+        for (_c, bmap) in run {
+            writer.write_all(&[bmap.width()])?;
+            writer.write_all(&columns(bmap, y))?;
+        }
+        start = end;
+    }
+    // select user-defined character set
+    writer.write_all(&[ESC, b'%', 1])?;
+    Ok(())
+}
+
+/// Pack bitmap columns, `y` bytes per column
+fn columns(bmap: &Bitmap, y: u8) -> Vec<u8> {
+    let mut data =
+        Vec::with_capacity(usize::from(bmap.width()) * usize::from(y));
+    for x in 0..bmap.width() {
+        for b in 0..y {
+            let mut byte = 0;
+            for bit in 0..8 {
+                if bmap.pixel(x, b * 8 + bit) {
+                    byte |= 0x80 >> bit;
+                }
+            }
+            data.push(byte);
+        }
+    }
+    data
+}
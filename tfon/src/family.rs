@@ -0,0 +1,185 @@
+//! Font families
+//!
+//! Related fonts, such as regular and bold variants, are linked by their
+//! `family` property, with a `style` distinguishing each member.  Fonts
+//! without a family each stand alone, in a family named after the font.
+//!
+//! Styles are tagged with weight and width [Axes], parsed from style words
+//! such as `Bold` or `Semi Condensed`, so the closest member to a request
+//! can be selected with [Family::select].
+use crate::font::Font;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+/// Style weights, from thin to black
+const WEIGHTS: &[(&str, u16)] = &[
+    ("thin", 100),
+    ("hairline", 100),
+    ("extralight", 200),
+    ("ultralight", 200),
+    ("light", 300),
+    ("regular", 400),
+    ("normal", 400),
+    ("book", 400),
+    ("medium", 500),
+    ("semibold", 600),
+    ("demibold", 600),
+    ("bold", 700),
+    ("extrabold", 800),
+    ("ultrabold", 800),
+    ("black", 900),
+    ("heavy", 900),
+];
+
+/// Style widths, from ultra-condensed to ultra-expanded
+const WIDTHS: &[(&str, u8)] = &[
+    ("ultracondensed", 1),
+    ("extracondensed", 2),
+    ("condensed", 3),
+    ("narrow", 3),
+    ("semicondensed", 4),
+    ("semiexpanded", 6),
+    ("expanded", 7),
+    ("wide", 7),
+    ("extraexpanded", 8),
+    ("ultraexpanded", 9),
+];
+
+/// Weight and width axes of a style
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Axes {
+    /// Weight, from 100 (thin) to 900 (black); 400 is regular
+    pub weight: u16,
+    /// Width, from 1 (ultra-condensed) to 9 (ultra-expanded); 5 is normal
+    pub width: u8,
+}
+
+impl Default for Axes {
+    fn default() -> Self {
+        Axes {
+            weight: 400,
+            width: 5,
+        }
+    }
+}
+
+impl Axes {
+    /// Parse axes from style words, such as `Extra Bold Condensed`
+    ///
+    /// Unrecognized words are ignored.
+    pub fn from_style(style: &str) -> Self {
+        let mut axes = Axes::default();
+        let words: Vec<String> = style
+            .split([' ', '-', '_'])
+            .filter(|w| !w.is_empty())
+            .map(str::to_ascii_lowercase)
+            .collect();
+        let mut i = 0;
+        while i < words.len() {
+            let mut word = words[i].clone();
+            // join modifiers with the next word, such as "semi bold"
+            if matches!(word.as_str(), "extra" | "ultra" | "semi" | "demi") {
+                if let Some(next) = words.get(i + 1) {
+                    word.push_str(next);
+                    i += 1;
+                }
+            }
+            if let Some((_, weight)) = WEIGHTS.iter().find(|(w, _)| *w == word)
+            {
+                axes.weight = *weight;
+            }
+            if let Some((_, width)) = WIDTHS.iter().find(|(w, _)| *w == word) {
+                axes.width = *width;
+            }
+            i += 1;
+        }
+        axes
+    }
+}
+
+/// Family of related fonts
+#[derive(Clone, Debug)]
+pub struct Family {
+    /// Family name
+    name: String,
+    /// Member fonts, ordered by style
+    members: Vec<Arc<Font>>,
+}
+
+/// Get the family name of a font
+fn family_name(font: &Font) -> &str {
+    font.family().unwrap_or(font.name())
+}
+
+/// Get the sort key of a style (`Regular` first)
+fn style_key(style: &str) -> (bool, String) {
+    (
+        !style.eq_ignore_ascii_case("regular"),
+        style.to_ascii_lowercase(),
+    )
+}
+
+impl Family {
+    /// Group fonts into families, ordered by name
+    pub fn group(fonts: impl IntoIterator<Item = Arc<Font>>) -> Vec<Family> {
+        let mut families: BTreeMap<String, Vec<Arc<Font>>> = BTreeMap::new();
+        for font in fonts {
+            families
+                .entry(family_name(&font).to_string())
+                .or_default()
+                .push(font);
+        }
+        families
+            .into_iter()
+            .map(|(name, mut members)| {
+                members.sort_by_key(|font| style_key(font.style()));
+                Family { name, members }
+            })
+            .collect()
+    }
+
+    /// Get family name
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Get member fonts, ordered by style (`Regular` first)
+    pub fn members(&self) -> &[Arc<Font>] {
+        &self.members
+    }
+
+    /// Get the member with a style (ignoring case)
+    pub fn style(&self, style: &str) -> Option<&Arc<Font>> {
+        self.members
+            .iter()
+            .find(|font| font.style().eq_ignore_ascii_case(style))
+    }
+
+    /// Select the member closest to a weight, width and height
+    ///
+    /// Height is matched first (preferring smaller fonts on a tie), since
+    /// bitmap fonts can't be scaled smoothly, then weight, then width.  Any
+    /// remaining tie goes to the earliest member, so the choice is
+    /// deterministic.
+    pub fn select(
+        &self,
+        weight: u16,
+        width: u8,
+        height: u8,
+    ) -> Option<&Arc<Font>> {
+        self.members.iter().min_by_key(|font| {
+            let axes = font.axes();
+            (
+                font.height().abs_diff(height),
+                font.height() > height,
+                axes.weight.abs_diff(weight),
+                axes.width.abs_diff(width),
+            )
+        })
+    }
+
+    /// Get the regular member, or the first one
+    pub fn regular(&self) -> Option<&Arc<Font>> {
+        self.style("regular").or(self.members.first())
+    }
+}
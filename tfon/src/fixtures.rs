@@ -0,0 +1,63 @@
+//! Font fixtures for testing
+//!
+//! Small fonts are included in each supported format, so that tests can be
+//! written against realistic data.  Fixtures are named by file, such as
+//! `digits3x5.bdf`.
+//!
+//! The `misc-fixed-5x7` fixtures are a real-world font: the public domain
+//! X11 `5x7` misc-fixed font, limited to code points 32-255.
+use crate::common::Result;
+use crate::font::Font;
+
+/// All font fixtures (file name, source)
+pub const FIXTURES: &[(&str, &str)] = &[
+    ("ascii5x7.bdf", include_str!("../fixtures/ascii5x7.bdf")),
+    ("ascii5x7.ifnt", include_str!("../fixtures/ascii5x7.ifnt")),
+    ("ascii5x7.tfon", include_str!("../fixtures/ascii5x7.tfon")),
+    ("blink7x7.tfon", include_str!("../fixtures/blink7x7.tfon")),
+    ("digits3x5.bdf", include_str!("../fixtures/digits3x5.bdf")),
+    ("digits3x5.ifnt", include_str!("../fixtures/digits3x5.ifnt")),
+    (
+        "digits3x5.ifntx",
+        include_str!("../fixtures/digits3x5.ifntx"),
+    ),
+    ("digits3x5.tfon", include_str!("../fixtures/digits3x5.tfon")),
+    (
+        "misc-fixed-5x7.bdf",
+        include_str!("../fixtures/misc-fixed-5x7.bdf"),
+    ),
+    (
+        "misc-fixed-5x7.ifnt",
+        include_str!("../fixtures/misc-fixed-5x7.ifnt"),
+    ),
+    (
+        "misc-fixed-5x7.ifntx",
+        include_str!("../fixtures/misc-fixed-5x7.ifntx"),
+    ),
+    (
+        "misc-fixed-5x7.tfon",
+        include_str!("../fixtures/misc-fixed-5x7.tfon"),
+    ),
+    (
+        "misc-fixed-5x7.xml",
+        include_str!("../fixtures/misc-fixed-5x7.xml"),
+    ),
+];
+
+/// Get the source of a fixture
+pub fn source(name: &str) -> Option<&'static str> {
+    FIXTURES
+        .iter()
+        .find(|(nm, _src)| *nm == name)
+        .map(|(_nm, src)| *src)
+}
+
+/// Load a fixture font
+///
+/// The format is determined by the file extension.
+pub fn font(name: &str) -> Result<Font> {
+    let src = source(name).ok_or_else(|| {
+        std::io::Error::new(std::io::ErrorKind::NotFound, name.to_string())
+    })?;
+    Font::from_file_source(name, src)
+}
@@ -0,0 +1,1233 @@
+// font.rs
+//
+use crate::class::CharClass;
+use crate::common::{Bitmap, Error, ParseContext, Prop, Rect, Result};
+use crate::family::Axes;
+use crate::footprint::{self, Footprint, Packing};
+use crate::graphic::{Animation, Frame};
+use crate::heatmap::Heatmap;
+use crate::patch::Patch;
+use std::collections::BTreeMap;
+use std::ops::{RangeBounds, RangeInclusive};
+
+/// Options for converting fonts
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ConvertOptions {
+    /// Rows above the baseline
+    pub baseline: Option<u8>,
+    /// Rows below the baseline
+    pub descent: Option<u8>,
+    /// Width table, for converting to a fixed width
+    pub widths: Option<WidthTable>,
+}
+
+/// Placement of a glyph padded to a fixed width
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Align {
+    /// Glyph at left, padded on the right
+    Left,
+    /// Glyph centered (extra column on the right)
+    #[default]
+    Center,
+    /// Glyph at right, padded on the left
+    Right,
+    /// Number of blank columns on the left
+    Columns(u8),
+}
+
+/// Width table for converting a proportional font to a fixed width
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct WidthTable {
+    /// Fixed width (default: widest glyph)
+    pub width: Option<u8>,
+    /// Placement of glyphs not in the table
+    pub align: Align,
+    /// Placement of specific glyphs, by code point
+    pub glyphs: BTreeMap<u16, Align>,
+}
+
+/// Fill for glyphs added by [Font::ensure_charset]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum FillPolicy {
+    /// Blank glyph, as wide as the font (or its space glyph)
+    Blank,
+    /// Copy of the glyph at a code point, such as `?` or U+FFFD
+    Copy(u16),
+}
+
+/// Accented Latin-1 letters (composed, base, accent)
+const ACCENTED: &[(u16, char, u16)] = &[
+    (0xC0, 'A', 0x60),
+    (0xC1, 'A', 0xB4),
+    (0xC2, 'A', 0x5E),
+    (0xC3, 'A', 0x7E),
+    (0xC4, 'A', 0xA8),
+    (0xC5, 'A', 0xB0),
+    (0xC7, 'C', 0xB8),
+    (0xC8, 'E', 0x60),
+    (0xC9, 'E', 0xB4),
+    (0xCA, 'E', 0x5E),
+    (0xCB, 'E', 0xA8),
+    (0xCC, 'I', 0x60),
+    (0xCD, 'I', 0xB4),
+    (0xCE, 'I', 0x5E),
+    (0xCF, 'I', 0xA8),
+    (0xD1, 'N', 0x7E),
+    (0xD2, 'O', 0x60),
+    (0xD3, 'O', 0xB4),
+    (0xD4, 'O', 0x5E),
+    (0xD5, 'O', 0x7E),
+    (0xD6, 'O', 0xA8),
+    (0xD9, 'U', 0x60),
+    (0xDA, 'U', 0xB4),
+    (0xDB, 'U', 0x5E),
+    (0xDC, 'U', 0xA8),
+    (0xDD, 'Y', 0xB4),
+    (0xE0, 'a', 0x60),
+    (0xE1, 'a', 0xB4),
+    (0xE2, 'a', 0x5E),
+    (0xE3, 'a', 0x7E),
+    (0xE4, 'a', 0xA8),
+    (0xE5, 'a', 0xB0),
+    (0xE7, 'c', 0xB8),
+    (0xE8, 'e', 0x60),
+    (0xE9, 'e', 0xB4),
+    (0xEA, 'e', 0x5E),
+    (0xEB, 'e', 0xA8),
+    (0xEC, 'ı', 0x60),
+    (0xED, 'ı', 0xB4),
+    (0xEE, 'ı', 0x5E),
+    (0xEF, 'ı', 0xA8),
+    (0xF1, 'n', 0x7E),
+    (0xF2, 'o', 0x60),
+    (0xF3, 'o', 0xB4),
+    (0xF4, 'o', 0x5E),
+    (0xF5, 'o', 0x7E),
+    (0xF6, 'o', 0xA8),
+    (0xF9, 'u', 0x60),
+    (0xFA, 'u', 0xB4),
+    (0xFB, 'u', 0x5E),
+    (0xFC, 'u', 0xA8),
+    (0xFD, 'y', 0xB4),
+    (0xFF, 'y', 0xA8),
+];
+
+/// Cedilla code point (placed below base glyph)
+const CEDILLA: u16 = 0xB8;
+
+/// Record of the last change to a glyph
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Modified {
+    /// Timestamp, such as `2024-05-01T12:00:00Z`
+    pub time: String,
+    /// Author of the change
+    pub author: String,
+}
+
+/// Bitmap font
+///
+/// A font collected from a property iterator, with glyphs keyed by code
+/// point.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Font {
+    /// Font name
+    name: String,
+    /// Family of related fonts
+    family: Option<String>,
+    /// Style within family
+    style: Option<String>,
+    /// Copyright notice
+    copyright: Option<String>,
+    /// Foundry (or designer)
+    foundry: Option<String>,
+    /// Font number
+    number: u8,
+    /// Font height (pixels)
+    height: u8,
+    /// Font width (pixels; 0 for proportional)
+    width: u8,
+    /// Pixel spacing between characters
+    char_spacing: u8,
+    /// Pixel spacing between lines
+    line_spacing: u8,
+    /// Baseline (rows above)
+    baseline: Option<u8>,
+    /// Maximum character number (highest reserved code point)
+    max_char_number: Option<u16>,
+    /// Character glyphs
+    glyphs: BTreeMap<u16, Bitmap>,
+    /// Alternate frames of blinking glyphs
+    frames: BTreeMap<u16, Bitmap>,
+    /// Last changes to glyphs
+    modified: BTreeMap<u16, Modified>,
+    /// Ligature code points, keyed by character sequence
+    ligatures: BTreeMap<String, u32>,
+}
+
+impl Font {
+    /// Printable ASCII, required by many sign controllers
+    pub const PRINTABLE_ASCII: &'static [RangeInclusive<u16>] = &[0x20..=0x7E];
+
+    /// Code point pairs which are horizontal mirror images
+    pub const MIRRORED: &'static [(u16, u16)] = &[
+        (0x28, 0x29),     // ( )
+        (0x2F, 0x5C),     // / \
+        (0x3C, 0x3E),     // < >
+        (0x5B, 0x5D),     // [ ]
+        (0x7B, 0x7D),     // { }
+        (0xAB, 0xBB),     // « »
+        (0x2039, 0x203A), // ‹ ›
+        (0x2190, 0x2192), // ← →
+        (0x2196, 0x2197), // ↖ ↗
+        (0x2199, 0x2198), // ↙ ↘
+        (0x21D0, 0x21D2), // ⇐ ⇒
+        (0x21E6, 0x21E8), // ⇦ ⇨
+        (0x25B6, 0x25C0), // ▶ ◀
+        (0x25B7, 0x25C1), // ▷ ◁
+        (0x25BA, 0x25C4), // ► ◄
+        (0x276E, 0x276F), // ❮ ❯
+        (0x27E8, 0x27E9), // ⟨ ⟩
+        (0x2B05, 0x27A1), // ⬅ ➡
+    ];
+
+    /// Create a font from font properties
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub fn from_props<'a>(
+        props: impl Iterator<Item = Prop<'a>>,
+    ) -> Result<Self> {
+        let mut name = None;
+        let mut family = None;
+        let mut style = None;
+        let mut copyright = None;
+        let mut foundry = None;
+        let mut number = 1;
+        let mut height = None;
+        let mut width = 0;
+        let mut char_spacing = 0;
+        let mut line_spacing = 0;
+        let mut baseline = None;
+        let mut max_char_number = None;
+        let mut glyphs = BTreeMap::new();
+        let mut frames = BTreeMap::new();
+        let mut modified = BTreeMap::new();
+        let mut ligatures = BTreeMap::new();
+        let mut cp = None;
+        let mut last = None;
+        let mut stamp = None;
+        let ctx = ParseContext::load();
+        for prop in props {
+            let at = ctx.at(prop.kind(), cp.or(last));
+            match prop {
+                Prop::FontName(nm) => name = Some(nm.to_string()),
+                Prop::Family(fam) => family = Some(fam.to_string()),
+                Prop::Style(st) => style = Some(st.to_string()),
+                Prop::Copyright(c) => copyright = Some(c.to_string()),
+                Prop::Foundry(f) => foundry = Some(f.to_string()),
+                Prop::FontNumber(num) => number = num,
+                Prop::FontHeight(fh) => height = Some(fh),
+                Prop::FontWidth(fw) => width = fw,
+                Prop::CharSpacing(cs) => char_spacing = cs,
+                Prop::LineSpacing(ls) => line_spacing = ls,
+                Prop::Baseline(bl) => baseline = Some(bl),
+                Prop::MaxCharNumber(mx) => max_char_number = Some(mx),
+                Prop::CodePoint(c) => cp = Some(c),
+                Prop::Bitmap(bmap) => {
+                    let c = cp.take().ok_or_else(|| at.misplaced("ch"))?;
+                    height.get_or_insert(bmap.height);
+                    glyphs.insert(c, bmap);
+                    last = Some(c);
+                    stamp = Some(c);
+                }
+                Prop::Frame(bmap) => {
+                    let c = last.take().ok_or_else(|| at.misplaced("ch"))?;
+                    frames.insert(c, bmap);
+                }
+                Prop::Modified { time, author } => {
+                    let c = stamp.take().ok_or_else(|| at.misplaced("ch"))?;
+                    let time = time.to_string();
+                    let author = author.to_string();
+                    modified.insert(c, Modified { time, author });
+                }
+                Prop::Ligature {
+                    sequence,
+                    code_point,
+                } => {
+                    ligatures.insert(sequence.to_string(), code_point);
+                }
+                _ => (),
+            }
+        }
+        for (cp, bmap) in &glyphs {
+            bmap.check_width(width, *cp)
+                .map_err(|e| ctx.at("Bitmap", Some(*cp)).wrap(e))?;
+        }
+        if let (Some(mx), Some(cp)) = (max_char_number, glyphs.keys().last()) {
+            if *cp > mx {
+                let at = ctx.at("Bitmap", Some(*cp));
+                return Err(at.wrap(Error::OutOfRange(*cp, mx)));
+            }
+        }
+        for (cp, frame) in &frames {
+            if !glyphs.get(cp).is_some_and(|b| same_size(b, frame)) {
+                let at = ctx.at("Frame", Some(*cp));
+                return Err(at.wrap(Error::FrameMismatch(*cp)));
+            }
+        }
+        #[cfg(feature = "tracing")]
+        tracing::debug!(glyphs = glyphs.len(), "font loaded");
+        Ok(Font {
+            name: name.ok_or_else(|| ctx.wrap(Error::Expected("font_name")))?,
+            family,
+            style,
+            copyright,
+            foundry,
+            number,
+            height: height
+                .ok_or_else(|| ctx.wrap(Error::Expected("font_height")))?,
+            width,
+            char_spacing,
+            line_spacing,
+            baseline,
+            max_char_number,
+            glyphs,
+            frames,
+            modified,
+            ligatures,
+        })
+    }
+
+    /// Parse a font from source, with format determined by file extension
+    #[cfg(any(feature = "bundle", feature = "fixtures"))]
+    pub(crate) fn from_file_source(name: &str, src: &str) -> Result<Self> {
+        let format = name
+            .rsplit_once('.')
+            .and_then(|(_nm, ext)| crate::Format::from_extension(ext))
+            .ok_or(Error::UnknownFormat())?;
+        format.load(src)
+    }
+
+    /// Get font properties
+    pub fn props(&self) -> impl Iterator<Item = Prop<'_>> {
+        [
+            Prop::FontName(self.name.as_str().into()),
+            Prop::FontNumber(self.number),
+            Prop::FontHeight(self.height),
+            Prop::CharSpacing(self.char_spacing),
+            Prop::LineSpacing(self.line_spacing),
+        ]
+        .into_iter()
+        .chain((self.width > 0).then_some(Prop::FontWidth(self.width)))
+        .chain(self.baseline.map(Prop::Baseline))
+        .chain(self.max_char_number.map(Prop::MaxCharNumber))
+        .chain(self.family.as_deref().map(|f| Prop::Family(f.into())))
+        .chain(self.style.as_deref().map(|s| Prop::Style(s.into())))
+        .chain(self.copyright.as_deref().map(|c| Prop::Copyright(c.into())))
+        .chain(self.foundry.as_deref().map(|f| Prop::Foundry(f.into())))
+        .chain(self.ligatures.iter().map(|(seq, cp)| Prop::Ligature {
+            sequence: seq.into(),
+            code_point: *cp,
+        }))
+        .chain(self.glyphs.iter().flat_map(|(cp, bmap)| {
+            [Prop::CodePoint(*cp), Prop::Bitmap(bmap.clone())]
+                .into_iter()
+                .chain(self.frames.get(cp).cloned().map(Prop::Frame))
+                .chain(self.modified.get(cp).map(|m| Prop::Modified {
+                    time: m.time.as_str().into(),
+                    author: m.author.as_str().into(),
+                }))
+        }))
+    }
+
+    /// Get font name
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Get family of related fonts
+    pub fn family(&self) -> Option<&str> {
+        self.family.as_deref()
+    }
+
+    /// Set family of related fonts
+    pub fn set_family(&mut self, family: Option<&str>) {
+        self.family = family.map(str::to_string);
+    }
+
+    /// Get style within family
+    ///
+    /// If not specified, the style is `Regular`.
+    pub fn style(&self) -> &str {
+        self.style.as_deref().unwrap_or("Regular")
+    }
+
+    /// Set style within family, such as `Bold` or `Condensed`
+    pub fn set_style(&mut self, style: Option<&str>) {
+        self.style = style.map(str::to_string);
+    }
+
+    /// Get copyright notice
+    pub fn copyright(&self) -> Option<&str> {
+        self.copyright.as_deref()
+    }
+
+    /// Set copyright notice
+    pub fn set_copyright(&mut self, copyright: Option<&str>) {
+        self.copyright = copyright.map(str::to_string);
+    }
+
+    /// Get foundry (or designer)
+    pub fn foundry(&self) -> Option<&str> {
+        self.foundry.as_deref()
+    }
+
+    /// Set foundry (or designer)
+    pub fn set_foundry(&mut self, foundry: Option<&str>) {
+        self.foundry = foundry.map(str::to_string);
+    }
+
+    /// Get weight and width axes of style
+    pub fn axes(&self) -> Axes {
+        Axes::from_style(self.style())
+    }
+
+    /// Get full name, with family and style
+    ///
+    /// This is the family followed by the style (unless `Regular`), such as
+    /// "Highways 12 Bold", or the font name if there is no family.
+    pub fn full_name(&self) -> String {
+        match &self.family {
+            Some(family) if self.style().eq_ignore_ascii_case("regular") => {
+                family.clone()
+            }
+            Some(family) => format!("{family} {}", self.style()),
+            None => self.name.clone(),
+        }
+    }
+
+    /// Get font number
+    pub fn number(&self) -> u8 {
+        self.number
+    }
+
+    /// Get font height
+    pub fn height(&self) -> u8 {
+        self.height
+    }
+
+    /// Get font width
+    ///
+    /// A width of 0 means the font is proportional; otherwise all glyphs
+    /// have the same fixed width.
+    pub fn width(&self) -> u8 {
+        self.width
+    }
+
+    /// Get character spacing
+    pub fn char_spacing(&self) -> u8 {
+        self.char_spacing
+    }
+
+    /// Get line spacing
+    pub fn line_spacing(&self) -> u8 {
+        self.line_spacing
+    }
+
+    /// Get baseline (rows above)
+    ///
+    /// If not specified, the baseline is the bottom of the font.
+    pub fn baseline(&self) -> u8 {
+        self.baseline.unwrap_or(self.height).min(self.height)
+    }
+
+    /// Get maximum character number (highest reserved code point)
+    ///
+    /// Sign controllers reserve a character table up to this code point, so
+    /// no glyph may be above it.  If not specified, it is the highest glyph
+    /// code point.
+    pub fn max_char_number(&self) -> u16 {
+        self.max_char_number
+            .or_else(|| self.glyphs.keys().next_back().copied())
+            .unwrap_or(0)
+    }
+
+    /// Set (or clear) maximum character number
+    ///
+    /// An error is returned if any glyph is above it.
+    pub fn set_max_char_number(&mut self, max: Option<u16>) -> Result<()> {
+        if let Some(mx) = max {
+            if let Some(cp) =
+                self.glyphs.keys().next_back().filter(|cp| **cp > mx)
+            {
+                return Err(Error::OutOfRange(*cp, mx));
+            }
+        }
+        self.max_char_number = max;
+        Ok(())
+    }
+
+    /// Get glyph bitmap for a code point
+    pub fn glyph(&self, cp: u16) -> Option<&Bitmap> {
+        self.glyphs.get(&cp)
+    }
+
+    /// Get glyph bitmap for a character
+    pub fn glyph_char(&self, c: char) -> Option<&Bitmap> {
+        u16::try_from(u32::from(c))
+            .ok()
+            .and_then(|cp| self.glyph(cp))
+    }
+
+    /// Set (or remove) the glyph for a code point
+    ///
+    /// The glyph must match the font height, and fixed width (if any).  Its
+    /// last change is recorded as `modified`; if `None`, any previous record
+    /// is cleared.  A frame which no longer matches the glyph is removed.
+    pub fn set_glyph(
+        &mut self,
+        cp: u16,
+        bmap: Option<Bitmap>,
+        modified: Option<Modified>,
+    ) -> Result<()> {
+        match bmap {
+            Some(bmap) => {
+                bmap.check_width(self.width, cp)?;
+                if bmap.height() != self.height {
+                    return Err(Error::GlyphTooLarge(cp));
+                }
+                if let Some(mx) = self.max_char_number.filter(|mx| cp > *mx) {
+                    return Err(Error::OutOfRange(cp, mx));
+                }
+                self.glyphs.insert(cp, bmap);
+                match modified {
+                    Some(modified) => self.modified.insert(cp, modified),
+                    None => self.modified.remove(&cp),
+                };
+            }
+            None => {
+                self.glyphs.remove(&cp);
+            }
+        }
+        self.prune();
+        Ok(())
+    }
+
+    /// Get the last change to a glyph, if recorded
+    pub fn modified(&self, cp: u16) -> Option<&Modified> {
+        self.modified.get(&cp)
+    }
+
+    /// Get the alternate frame of a blinking glyph
+    pub fn frame(&self, cp: u16) -> Option<&Bitmap> {
+        self.frames.get(&cp)
+    }
+
+    /// Set (or clear) the alternate frame of a glyph, for blinking
+    ///
+    /// The frame must be the same size as the glyph.
+    pub fn set_frame(&mut self, cp: u16, frame: Option<Bitmap>) -> Result<()> {
+        match frame {
+            Some(frame) => {
+                if !self.glyph(cp).is_some_and(|b| same_size(b, &frame)) {
+                    return Err(Error::FrameMismatch(cp));
+                }
+                self.frames.insert(cp, frame);
+            }
+            None => {
+                self.frames.remove(&cp);
+            }
+        }
+        Ok(())
+    }
+
+    /// Get an animation of a glyph, alternating between its frames
+    ///
+    /// * `delay` Display time of each frame (ms).
+    ///
+    /// Controllers without blinking glyphs can display the animation as
+    /// graphics (see [Animation::ntcip_multi]).
+    pub fn animation(&self, cp: u16, delay: u32) -> Option<Animation> {
+        let bmap = self.glyph(cp)?;
+        let frames = std::iter::once(bmap)
+            .chain(self.frame(cp))
+            .map(|bitmap| Frame {
+                bitmap: bitmap.clone(),
+                delay,
+            })
+            .collect();
+        Some(Animation { frames })
+    }
+
+    /// Get an iterator of ligatures, ordered by character sequence
+    pub fn ligatures(&self) -> impl Iterator<Item = (&str, u32)> {
+        self.ligatures.iter().map(|(seq, cp)| (seq.as_str(), *cp))
+    }
+
+    /// Add a ligature, rendering a character sequence with one glyph
+    pub fn add_ligature(&mut self, sequence: &str, cp: u32) {
+        self.ligatures.insert(sequence.to_string(), cp);
+    }
+
+    /// Find the longest ligature at the start of some text
+    ///
+    /// Only ligatures with a glyph are considered.  Returns the length of
+    /// the sequence (in bytes) and the ligature glyph.
+    pub fn ligature_at(&self, text: &str) -> Option<(usize, &Bitmap)> {
+        self.ligatures
+            .iter()
+            .filter(|(seq, _cp)| !seq.is_empty() && text.starts_with(*seq))
+            .filter_map(|(seq, cp)| {
+                let glyph =
+                    u16::try_from(*cp).ok().and_then(|cp| self.glyph(cp))?;
+                Some((seq.len(), glyph))
+            })
+            .max_by_key(|(len, _glyph)| *len)
+    }
+
+    /// Get character class of a glyph
+    ///
+    /// Returns `None` if the font has no glyph for the code point.
+    pub fn class(&self, cp: u16) -> Option<CharClass> {
+        self.glyphs.contains_key(&cp).then(|| CharClass::of(cp))
+    }
+
+    /// Find glyphs visually similar to a bitmap
+    ///
+    /// Similarity is the normalized overlap of lit pixels (see
+    /// [similarity](crate::quality::similarity)), from 0 to 1.  Glyphs at or
+    /// above `threshold` are returned, most similar first.
+    pub fn find_similar(
+        &self,
+        bmap: &Bitmap,
+        threshold: f32,
+    ) -> Vec<(u16, f32)> {
+        let mut found: Vec<(u16, f32)> = self
+            .glyphs
+            .iter()
+            .map(|(cp, glyph)| (*cp, crate::quality::similarity(bmap, glyph)))
+            .filter(|(_cp, sim)| *sim >= threshold)
+            .collect();
+        found.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+        found
+    }
+
+    /// Apply an edit to every glyph, ordered by code point
+    ///
+    /// The font height is updated to the tallest glyph afterwards.  A fixed
+    /// width is kept only if all glyphs still have the same width.
+    pub fn map_glyphs<F>(&mut self, mut f: F) -> Result<()>
+    where
+        F: FnMut(u16, &mut Bitmap) -> Result<()>,
+    {
+        let result = self
+            .glyphs
+            .iter_mut()
+            .chain(self.frames.iter_mut())
+            .try_for_each(|(cp, bmap)| f(*cp, bmap));
+        self.prune();
+        if let Some(height) = self.glyphs.values().map(|b| b.height()).max() {
+            self.height = height;
+        }
+        self.update_width();
+        result
+    }
+
+    /// Remove frames and changes which no longer match their glyphs
+    fn prune(&mut self) {
+        let glyphs = &self.glyphs;
+        self.frames.retain(|cp, frame| {
+            glyphs.get(cp).is_some_and(|b| same_size(b, frame))
+        });
+        self.modified.retain(|cp, _m| glyphs.contains_key(cp));
+    }
+
+    /// Get mutable iterator of all glyph and frame bitmaps
+    fn bitmaps_mut(&mut self) -> impl Iterator<Item = &mut Bitmap> {
+        self.glyphs.values_mut().chain(self.frames.values_mut())
+    }
+
+    /// Update fixed width after glyphs have changed
+    fn update_width(&mut self) {
+        let mut widths = self.glyphs.values().map(|b| b.width());
+        let first = widths.next().unwrap_or(0);
+        if self.width > 0 && widths.all(|w| w == first) {
+            self.width = first;
+        } else {
+            self.width = 0;
+        }
+    }
+
+    /// Convert to a fixed width (monospace) font
+    ///
+    /// Narrower glyphs are padded to the widest glyph, centered.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub fn monospace(&mut self) {
+        let width = self.glyphs.values().map(|b| b.width()).max().unwrap_or(0);
+        for bmap in self.bitmaps_mut() {
+            let left = (width - bmap.width()) / 2;
+            *bmap = Bitmap::from_fn(bmap.height(), width, |x, y| {
+                x >= left && bmap.pixel(x - left, y)
+            });
+        }
+        self.width = width;
+    }
+
+    /// Convert to a fixed width font, with a width table
+    ///
+    /// Each glyph is padded to the width, placed as listed in the table.
+    /// An error is returned if a glyph (with its padding) does not fit.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub fn monospace_with(&mut self, table: &WidthTable) -> Result<()> {
+        let widest = self.glyphs.values().map(|b| b.width()).max();
+        let width = table.width.or(widest).unwrap_or(0);
+        let mut lefts = BTreeMap::new();
+        for (cp, bmap) in &self.glyphs {
+            let extra = width
+                .checked_sub(bmap.width())
+                .ok_or(Error::GlyphTooLarge(*cp))?;
+            let left = match table.glyphs.get(cp).unwrap_or(&table.align) {
+                Align::Left => 0,
+                Align::Center => extra / 2,
+                Align::Right => extra,
+                Align::Columns(n) if *n <= extra => *n,
+                Align::Columns(_) => return Err(Error::GlyphTooLarge(*cp)),
+            };
+            lefts.insert(*cp, left);
+        }
+        for (cp, bmap) in self.glyphs.iter_mut().chain(self.frames.iter_mut()) {
+            let left = lefts.get(cp).copied().unwrap_or(0);
+            *bmap = Bitmap::from_fn(bmap.height(), width, |x, y| {
+                x >= left && bmap.pixel(x - left, y)
+            });
+        }
+        self.width = width;
+        Ok(())
+    }
+
+    /// Adjust stroke width of all glyphs
+    ///
+    /// * `pixels` Number of pixels to thicken strokes, or thin them if
+    ///   negative.
+    ///
+    /// Topology is preserved (see [Bitmap::thin] and [Bitmap::thicken]), for
+    /// adapting a font between low and high brightness displays.
+    pub fn adjust_stroke(&mut self, pixels: i8) {
+        for bmap in self.bitmaps_mut() {
+            for _ in 0..pixels.unsigned_abs() {
+                *bmap = if pixels < 0 {
+                    bmap.thin()
+                } else {
+                    bmap.thicken()
+                };
+            }
+        }
+    }
+
+    /// Estimate memory footprint with a glyph packing
+    pub fn footprint(&self, packing: Packing) -> Result<Footprint> {
+        footprint::measure(self, packing)
+    }
+
+    /// Sum ink at each position across all glyphs
+    pub fn pixel_heatmap(&self) -> Heatmap {
+        Heatmap::new(self)
+    }
+
+    /// Rotate font clockwise, by 90, 180 or 270 degrees
+    ///
+    /// For portrait-mounted displays.  All glyphs are rotated; for quarter
+    /// turns, the font height and width are swapped, as are character and
+    /// line spacing.  Proportional glyphs are first padded to the widest
+    /// glyph (as with [Font::monospace]), so the rotated font has one
+    /// height.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub fn rotate(&self, degrees: u16) -> Result<Font> {
+        let mut font = self.clone();
+        match degrees % 360 {
+            0 => (),
+            180 => {
+                font.baseline = Some(self.height - self.baseline());
+                for bmap in font.bitmaps_mut() {
+                    *bmap = bmap.rotate(2);
+                }
+            }
+            d @ (90 | 270) => {
+                font.monospace();
+                font.height = font.width;
+                font.width = self.height;
+                font.char_spacing = self.line_spacing;
+                font.line_spacing = self.char_spacing;
+                font.baseline = None;
+                let turns = if d == 90 { 1 } else { 3 };
+                for bmap in font.bitmaps_mut() {
+                    *bmap = bmap.rotate(turns);
+                }
+            }
+            _ => return Err(Error::InvalidRotation(degrees)),
+        }
+        Ok(font)
+    }
+
+    /// Split into upper and lower fonts
+    ///
+    /// * `rows_top` Number of rows in the upper font.
+    ///
+    /// Some controllers compose tall characters from two stacked fonts.
+    pub fn split_height(&self, rows_top: u8) -> Result<(Font, Font)> {
+        if rows_top == 0 || rows_top >= self.height {
+            return Err(Error::TooLarge());
+        }
+        let rows_bottom = self.height - rows_top;
+        let mut upper = self.clone();
+        let mut lower = self.clone();
+        upper.height = rows_top;
+        upper.baseline = self.baseline.map(|bl| bl.min(rows_top));
+        lower.height = rows_bottom;
+        lower.baseline = self.baseline.map(|bl| bl.saturating_sub(rows_top));
+        for (cp, bmap) in self.glyphs() {
+            let width = bmap.width();
+            let top = Rect {
+                x: 0,
+                y: 0,
+                width,
+                height: rows_top,
+            };
+            let bottom = Rect {
+                x: 0,
+                y: rows_top,
+                width,
+                height: rows_bottom,
+            };
+            upper.glyphs.insert(cp, bmap.crop(top));
+            lower.glyphs.insert(cp, bmap.crop(bottom));
+            if let Some(frame) = self.frame(cp) {
+                upper.frames.insert(cp, frame.crop(top));
+                lower.frames.insert(cp, frame.crop(bottom));
+            }
+        }
+        Ok((upper, lower))
+    }
+
+    /// Stack upper and lower fonts into one taller font
+    ///
+    /// This is the inverse of [split_height].  Header properties are taken
+    /// from the upper font.  If a code point is missing from either font,
+    /// that half of the glyph is blank.
+    ///
+    /// [split_height]: Font::split_height
+    pub fn stack(upper: &Font, lower: &Font) -> Result<Font> {
+        let height = upper
+            .height
+            .checked_add(lower.height)
+            .ok_or(Error::TooLarge())?;
+        let mut font = upper.clone();
+        font.height = height;
+        font.baseline = match (upper.baseline, lower.baseline) {
+            (_, Some(bl)) => Some(upper.height.saturating_add(bl)),
+            (Some(bl), None) if bl < upper.height => Some(bl),
+            _ => None,
+        };
+        font.glyphs.clear();
+        font.frames.clear();
+        font.modified.clear();
+        let cps = upper.glyphs.keys().chain(lower.glyphs.keys());
+        for cp in cps.copied() {
+            if font.glyphs.contains_key(&cp) {
+                continue;
+            }
+            let top = upper.glyph(cp);
+            let bottom = lower.glyph(cp);
+            let width = top
+                .map(|b| b.width())
+                .max(bottom.map(|b| b.width()))
+                .unwrap_or(0);
+            let glyph = Bitmap::from_fn(height, width, |x, y| {
+                if y < upper.height {
+                    top.is_some_and(|b| b.pixel(x, y))
+                } else {
+                    bottom.is_some_and(|b| b.pixel(x, y - upper.height))
+                }
+            });
+            font.glyphs.insert(cp, glyph);
+        }
+        font.update_width();
+        Ok(font)
+    }
+
+    /// Convert font, normalizing glyphs to a common baseline
+    ///
+    /// Glyphs are shifted vertically so that the baseline lands on the
+    /// requested row, then padded or cropped to the new height.  Options
+    /// which are not set keep the font's own metrics.  With a width table,
+    /// glyphs are then padded to a fixed width.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub fn convert(&self, options: &ConvertOptions) -> Result<Font> {
+        let baseline = self.baseline();
+        let ascent = options.baseline.unwrap_or(baseline);
+        let descent = options.descent.unwrap_or(self.height - baseline);
+        let height = ascent.checked_add(descent).ok_or(Error::TooLarge())?;
+        let shift = i16::from(ascent) - i16::from(baseline);
+        let mut font = self.clone();
+        font.height = height;
+        font.baseline = if options.baseline.is_some() || ascent < height {
+            Some(ascent)
+        } else {
+            None
+        };
+        for bmap in font.bitmaps_mut() {
+            *bmap = Bitmap::from_fn(height, bmap.width(), |x, y| {
+                u8::try_from(i16::from(y) - shift)
+                    .is_ok_and(|sy| bmap.pixel(x, sy))
+            });
+        }
+        if let Some(table) = &options.widths {
+            font.monospace_with(table)?;
+        }
+        Ok(font)
+    }
+
+    /// Get vertical offset of ink from the center of the character cell
+    ///
+    /// This is half the difference between blank rows above and below the
+    /// ink of all glyphs: positive if glyphs sit low, negative if they sit
+    /// high (common after a naive BDF import).  A font without ink has no
+    /// offset.
+    pub fn vertical_offset(&self) -> i16 {
+        let mut margins: Option<(u8, u8)> = None;
+        for bmap in self.glyphs.values() {
+            if let Some(ink) = bmap.ink_bounds() {
+                let below = bmap.height().saturating_sub(ink.y + ink.height);
+                let (top, bottom) = margins.get_or_insert((ink.y, below));
+                *top = (*top).min(ink.y);
+                *bottom = (*bottom).min(below);
+            }
+        }
+        margins
+            .map_or(0, |(top, bottom)| (i16::from(top) - i16::from(bottom)) / 2)
+    }
+
+    /// Shift all glyphs vertically to center their ink in the cell
+    ///
+    /// The baseline moves with the glyphs.  Returns the number of glyphs
+    /// moved (blank glyphs are not counted).
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub fn recenter(&mut self) -> usize {
+        let shift = self.vertical_offset();
+        if shift == 0 {
+            return 0;
+        }
+        let baseline = i16::from(self.baseline()) - shift;
+        self.baseline =
+            u8::try_from(baseline.clamp(0, self.height.into())).ok();
+        let mut moved = 0;
+        for bmap in self.bitmaps_mut() {
+            *bmap = Bitmap::from_fn(bmap.height(), bmap.width(), |x, y| {
+                u8::try_from(i16::from(y) + shift)
+                    .is_ok_and(|sy| bmap.pixel(x, sy))
+            });
+        }
+        for bmap in self.glyphs.values() {
+            if bmap.ink_bounds().is_some() {
+                moved += 1;
+            }
+        }
+        moved
+    }
+
+    /// Scale font by an integer factor
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub fn scale(&self, factor: u8) -> Result<Font> {
+        let factor = factor.max(1);
+        let scale = |v: u8| v.checked_mul(factor).ok_or(Error::TooLarge());
+        let mut font = self.clone();
+        font.height = scale(self.height)?;
+        font.width = scale(self.width)?;
+        font.char_spacing = scale(self.char_spacing)?;
+        font.line_spacing = scale(self.line_spacing)?;
+        font.baseline = self.baseline.map(scale).transpose()?;
+        for bmap in font.bitmaps_mut() {
+            let height = scale(bmap.height())?;
+            let width = scale(bmap.width())?;
+            *bmap = Bitmap::from_fn(height, width, |x, y| {
+                bmap.pixel(x / factor, y / factor)
+            });
+        }
+        Ok(font)
+    }
+
+    /// Keep only glyphs matching a predicate
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(u16) -> bool,
+    {
+        self.glyphs.retain(|cp, _bmap| f(*cp));
+        self.prune();
+    }
+
+    /// Keep only glyphs in any of a list of character class presets
+    pub fn retain_classes(&mut self, presets: &[CharClass]) {
+        self.retain(|cp| {
+            let class = CharClass::of(cp);
+            presets.iter().any(|preset| preset.includes(class))
+        });
+    }
+
+    /// Compose missing accented Latin-1 letters
+    ///
+    /// Each letter is made from its base glyph and an accent glyph (such as
+    /// `´` or `¨`), centered above the base ink, or below for a cedilla.
+    /// Letters without room in the character cell are skipped.  Returns the
+    /// number of glyphs added.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub fn compose_accents(&mut self) -> usize {
+        let mut count = 0;
+        for &(cp, base, accent) in ACCENTED {
+            if self.glyphs.contains_key(&cp) {
+                continue;
+            }
+            let (Some(bmap), Some(amap)) =
+                (self.glyph_char(base), self.glyph(accent))
+            else {
+                continue;
+            };
+            if let Some(bmap) = compose(bmap, amap, accent == CEDILLA) {
+                self.glyphs.insert(cp, bmap);
+                count += 1;
+            }
+        }
+        #[cfg(feature = "tracing")]
+        tracing::debug!(count, "accents composed");
+        count
+    }
+
+    /// Add glyphs for any missing code points in a character set
+    ///
+    /// Missing glyphs are filled according to a policy, so exported fonts
+    /// have every code point a controller requires.  See
+    /// [Font::PRINTABLE_ASCII] for a preset.  Returns the number of glyphs
+    /// added.
+    ///
+    /// An error is returned if the glyph to copy is missing, or a code point
+    /// is above the maximum character number.
+    pub fn ensure_charset(
+        &mut self,
+        charset: &[RangeInclusive<u16>],
+        policy: FillPolicy,
+    ) -> Result<usize> {
+        let fill = match policy {
+            FillPolicy::Blank => {
+                let width = match (self.width, self.glyph(0x20)) {
+                    (0, Some(space)) => space.width(),
+                    (0, None) => (self.height / 2).max(1),
+                    (width, _) => width,
+                };
+                Bitmap::new_blank(self.height, width)
+            }
+            FillPolicy::Copy(cp) => {
+                self.glyph(cp).cloned().ok_or_else(|| {
+                    let c = char::from_u32(cp.into()).unwrap_or('\u{FFFD}');
+                    Error::MissingGlyph(c)
+                })?
+            }
+        };
+        let missing: Vec<u16> = charset
+            .iter()
+            .cloned()
+            .flatten()
+            .filter(|cp| !self.glyphs.contains_key(cp))
+            .collect();
+        if let (Some(mx), Some(cp)) =
+            (self.max_char_number, missing.iter().max())
+        {
+            if *cp > mx {
+                return Err(Error::OutOfRange(*cp, mx));
+            }
+        }
+        for cp in &missing {
+            self.glyphs.insert(*cp, fill.clone());
+        }
+        Ok(missing.len())
+    }
+
+    /// Add missing glyphs by mirroring their counterparts
+    ///
+    /// For each pair of code points, a missing glyph is made by flipping the
+    /// other one horizontally, so only one direction of arrows or chevrons
+    /// needs to be drawn.  Existing glyphs are not replaced.  See
+    /// [Font::MIRRORED] for a preset table.  Returns the number of glyphs
+    /// added.
+    pub fn mirror_glyphs(&mut self, pairs: &[(u16, u16)]) -> usize {
+        let mut count = 0;
+        for &(a, b) in pairs {
+            for (from, to) in [(a, b), (b, a)] {
+                if self.glyphs.contains_key(&to) {
+                    continue;
+                }
+                if let Some(bmap) = self.glyphs.get(&from) {
+                    let bmap = bmap.mirror();
+                    self.glyphs.insert(to, bmap);
+                    count += 1;
+                }
+            }
+        }
+        count
+    }
+
+    /// Add missing lowercase letters as small capitals
+    ///
+    /// Each letter `a` to `z` is made from its capital, scaled down
+    /// vertically to two thirds of the ink height and resting on the same
+    /// bottom row.  Widths are kept, so strokes stay sharp.  Existing glyphs
+    /// are not replaced.  Returns the number of glyphs added.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub fn synthesize_small_caps(&mut self) -> usize {
+        let mut count = 0;
+        for (upper, lower) in ('A'..='Z').zip('a'..='z') {
+            let cp = u16::from(lower as u8);
+            if self.glyphs.contains_key(&cp) {
+                continue;
+            }
+            if let Some(bmap) = self.glyph_char(upper).and_then(small_cap) {
+                self.glyphs.insert(cp, bmap);
+                count += 1;
+            }
+        }
+        #[cfg(feature = "tracing")]
+        tracing::debug!(count, "small caps synthesized");
+        count
+    }
+
+    /// Make a patch with changes from this font to another
+    pub fn diff_patch(&self, other: &Font) -> Patch {
+        let mut glyphs = BTreeMap::new();
+        for (cp, bmap) in &other.glyphs {
+            if self.glyphs.get(cp) != Some(bmap) {
+                glyphs.insert(*cp, Some(bmap.clone()));
+            }
+        }
+        for cp in self.glyphs.keys() {
+            if !other.glyphs.contains_key(cp) {
+                glyphs.insert(*cp, None);
+            }
+        }
+        Patch {
+            name: other.name.clone(),
+            number: other.number,
+            width: other.width,
+            char_spacing: other.char_spacing,
+            line_spacing: other.line_spacing,
+            baseline: other.baseline,
+            glyphs,
+        }
+    }
+
+    /// Apply a patch made by [Font::diff_patch]
+    pub fn apply_patch(&mut self, patch: &Patch) -> Result<()> {
+        for (cp, bmap) in &patch.glyphs {
+            if let Some(bmap) = bmap {
+                bmap.check_width(patch.width, *cp)?;
+            }
+        }
+        self.name.clone_from(&patch.name);
+        self.number = patch.number;
+        self.width = patch.width;
+        self.char_spacing = patch.char_spacing;
+        self.line_spacing = patch.line_spacing;
+        self.baseline = patch.baseline;
+        for (cp, bmap) in &patch.glyphs {
+            match bmap {
+                Some(bmap) => {
+                    self.height = bmap.height();
+                    self.glyphs.insert(*cp, bmap.clone());
+                }
+                None => {
+                    self.glyphs.remove(cp);
+                }
+            }
+        }
+        self.prune();
+        Ok(())
+    }
+
+    /// Get an iterator of all glyphs, ordered by code point
+    pub fn glyphs(&self) -> impl Iterator<Item = (u16, &Bitmap)> {
+        self.glyphs.iter().map(|(cp, bmap)| (*cp, bmap))
+    }
+
+    /// Get glyphs in a range of code points, ordered by code point
+    ///
+    /// Such as `font.glyphs_in(0x20..0x7F)` for printable ASCII.
+    pub fn glyphs_in(
+        &self,
+        range: impl RangeBounds<u16>,
+    ) -> impl DoubleEndedIterator<Item = (u16, &Bitmap)> {
+        self.glyphs.range(range).map(|(cp, bmap)| (*cp, bmap))
+    }
+
+    /// Split glyphs into pages, for listing
+    ///
+    /// Each page is a range of code points with up to `per_page` glyphs
+    /// (at least one), which can be passed to [Font::glyphs_in].
+    pub fn glyph_pages(&self, per_page: usize) -> Vec<RangeInclusive<u16>> {
+        let cps: Vec<u16> = self.glyphs.keys().copied().collect();
+        cps.chunks(per_page.max(1))
+            .filter_map(|page| Some(*page.first()?..=*page.last()?))
+            .collect()
+    }
+}
+
+/// Compose a glyph from base and accent glyphs
+///
+/// The accent is placed one row above the base ink (or touching it, if
+/// there is no room), or directly below it when `below` is set.
+fn compose(base: &Bitmap, accent: &Bitmap, below: bool) -> Option<Bitmap> {
+    let b = base.ink_bounds()?;
+    let a = accent.ink_bounds()?;
+    let max_x = base.width().checked_sub(a.width)?;
+    let x = (i16::from(b.x) + (i16::from(b.width) - i16::from(a.width)) / 2)
+        .clamp(0, i16::from(max_x));
+    let y = if below {
+        let y = i16::from(b.y) + i16::from(b.height);
+        (y + i16::from(a.height) <= i16::from(base.height())).then_some(y)?
+    } else {
+        let top = i16::from(b.y) - i16::from(a.height);
+        if top > 0 {
+            top - 1
+        } else if top == 0 {
+            0
+        } else {
+            return None;
+        }
+    };
+    Some(Bitmap::from_fn(base.height(), base.width(), |px, py| {
+        let ax = i16::from(px) - x;
+        let ay = i16::from(py) - y;
+        let accent_pix = (0..i16::from(a.width)).contains(&ax)
+            && (0..i16::from(a.height)).contains(&ay)
+            && accent.pixel(a.x + ax as u8, a.y + ay as u8);
+        base.pixel(px, py) || accent_pix
+    }))
+}
+
+/// Make a small capital from a capital glyph
+///
+/// Rows are sampled nearest-neighbor, which keeps horizontal bars one pixel
+/// thick.
+fn small_cap(upper: &Bitmap) -> Option<Bitmap> {
+    let ink = upper.ink_bounds()?;
+    let h = u16::from(ink.height);
+    let th = ((h * 2 + 1) / 3).max(1);
+    let top = u16::from(ink.y) + h - th;
+    Some(Bitmap::from_fn(upper.height(), upper.width(), |x, y| {
+        let y = u16::from(y);
+        if y < top || y >= top + th {
+            return false;
+        }
+        let sy = u16::from(ink.y) + ((y - top) * 2 + 1) * h / (th * 2);
+        upper.pixel(x, sy as u8)
+    }))
+}
+
+/// Check if two bitmaps are the same size
+fn same_size(a: &Bitmap, b: &Bitmap) -> bool {
+    a.height() == b.height() && a.width() == b.width()
+}
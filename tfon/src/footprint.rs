@@ -0,0 +1,130 @@
+//! Memory footprint estimation
+//!
+//! Embedded targets store fonts in flash, packed in various ways.  A
+//! [Footprint] reports the bytes needed for each glyph, plus any fixed
+//! overhead (headers and tables), so a font can be checked against a
+//! flash budget before committing to it.
+use crate::common::{Bitmap, Result};
+use crate::font::Font;
+use crate::static_font::{ENTRY_LEN, HEADER_LEN};
+#[cfg(feature = "fmt-u8g2")]
+use crate::u8g2;
+use std::fmt;
+
+/// Size of a `GFXglyph` struct
+const GFX_GLYPH: usize = 7;
+
+/// Size of a `GFXfont` struct (with 32-bit pointers)
+const GFX_FONT: usize = 13;
+
+/// Glyph packing for export
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Packing {
+    /// Pixels packed into bits, padded to a byte at the end of each glyph
+    Bits,
+    /// Pixels packed into bits, with each row padded to a byte
+    RowBytes,
+    /// [StaticFont](crate::StaticFont) table
+    Static,
+    /// Adafruit GFX `GFXfont`, cropped to ink bounds
+    Gfx,
+    /// u8g2 run-length encoded font
+    U8g2,
+}
+
+/// Memory footprint of a font
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Footprint {
+    /// Glyph packing
+    pub packing: Packing,
+    /// Bytes for each glyph, ordered by code point
+    pub glyphs: Vec<(u16, usize)>,
+    /// Bytes of fixed overhead
+    pub overhead: usize,
+}
+
+impl fmt::Display for Packing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl Packing {
+    /// All packings
+    pub const ALL: &'static [Packing] = &[
+        Packing::Bits,
+        Packing::RowBytes,
+        Packing::Static,
+        Packing::Gfx,
+        Packing::U8g2,
+    ];
+
+    /// Get packing name
+    pub fn name(self) -> &'static str {
+        match self {
+            Packing::Bits => "bits",
+            Packing::RowBytes => "row-bytes",
+            Packing::Static => "static",
+            Packing::Gfx => "gfx",
+            Packing::U8g2 => "u8g2",
+        }
+    }
+}
+
+impl Footprint {
+    /// Get total bytes
+    pub fn total(&self) -> usize {
+        self.overhead + self.glyphs.iter().map(|(_cp, n)| n).sum::<usize>()
+    }
+}
+
+/// Get bytes of packed bits for a bitmap
+fn bits_len(bmap: &Bitmap) -> usize {
+    (usize::from(bmap.height()) * usize::from(bmap.width())).div_ceil(8)
+}
+
+/// Measure the footprint of a font
+pub(crate) fn measure(font: &Font, packing: Packing) -> Result<Footprint> {
+    let sized = |f: fn(&Bitmap) -> usize| {
+        font.glyphs().map(|(cp, bmap)| (cp, f(bmap))).collect()
+    };
+    let (glyphs, overhead) = match packing {
+        Packing::Bits => (sized(bits_len), 0),
+        Packing::RowBytes => (
+            sized(|bmap| {
+                usize::from(bmap.height())
+                    * usize::from(bmap.width()).div_ceil(8)
+            }),
+            0,
+        ),
+        Packing::Static => {
+            (sized(|bmap| bits_len(bmap) + ENTRY_LEN), HEADER_LEN)
+        }
+        Packing::Gfx => {
+            let glyphs: Vec<_> =
+                sized(|bmap| bits_len(&bmap.crop_to_ink()) + GFX_GLYPH);
+            // empty entries for missing code points in range
+            let span = match (glyphs.first(), glyphs.last()) {
+                (Some((first, _)), Some((last, _))) => {
+                    usize::from(last - first) + 1
+                }
+                _ => 0,
+            };
+            let missing = span - glyphs.len();
+            (glyphs, GFX_FONT + missing * GFX_GLYPH)
+        }
+        #[cfg(feature = "fmt-u8g2")]
+        Packing::U8g2 => {
+            let (data, glyphs) = u8g2::encode_sized(font)?;
+            let sum: usize = glyphs.iter().map(|(_cp, n)| n).sum();
+            (glyphs, data.len() - sum)
+        }
+        #[cfg(not(feature = "fmt-u8g2"))]
+        Packing::U8g2 => return Err(crate::Error::UnknownFormat()),
+    };
+    Ok(Footprint {
+        packing,
+        glyphs,
+        overhead,
+    })
+}
@@ -0,0 +1,419 @@
+//! Registry of font formats
+//!
+//! Formats can be detected, parsed and written by name, so that tools do
+//! not need separate code for each one.
+//!
+//! Each format other than `tfon` is enabled by a cargo feature, such as
+//! `fmt-bdf`; the default features enable all of them.  Disabled formats
+//! are left out of [Format::ALL], so they are never detected, and can't be
+//! parsed or written.
+#[cfg(feature = "fmt-bdf")]
+use crate::bdf;
+use crate::common::{
+    Error, Limits, ParseContext, Prop, Result, WriteOptions, WriteReport,
+};
+#[cfg(feature = "fmt-ctable")]
+use crate::ctable;
+#[cfg(feature = "fmt-escpos")]
+use crate::escpos;
+use crate::font::Font;
+#[cfg(feature = "fmt-gfx")]
+use crate::gfx;
+#[cfg(feature = "fmt-ifnt")]
+use crate::ifnt;
+#[cfg(feature = "fmt-ifntx")]
+use crate::ifntx;
+#[cfg(feature = "fmt-iris")]
+use crate::iris;
+use crate::tfon;
+#[cfg(feature = "fmt-u8g2")]
+use crate::u8g2;
+use std::fmt;
+use std::io::Write;
+use std::str::FromStr;
+
+/// Font format
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Format {
+    /// Glyph Bitmap Distribution Format
+    Bdf,
+    /// C table with sorted glyph index (write only)
+    Ctable,
+    /// ESC/POS user-defined characters (write only)
+    Escpos,
+    /// Adafruit GFX `GFXfont` C struct (write only)
+    Gfx,
+    /// `ifnt` format
+    Ifnt,
+    /// Obsolete `ifnt` (X) format (read only)
+    Ifntx,
+    /// IRIS sign configuration XML
+    Iris,
+    /// `tfon` format
+    Tfon,
+    /// u8g2 C array (write only)
+    U8g2,
+}
+
+/// Parser of font properties
+pub trait PropParser<'a>: Iterator<Item = Prop<'a>> {
+    /// Get the line number (from 1) where the last property started
+    ///
+    /// Formats which are not line-based have no line numbers.
+    fn line_number(&self) -> Option<usize> {
+        None
+    }
+}
+
+/// Property iterator from a parser
+pub type Props<'a> = Box<dyn PropParser<'a> + 'a>;
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for Format {
+    type Err = Error;
+
+    fn from_str(name: &str) -> Result<Self> {
+        Format::ALL
+            .iter()
+            .find(|fmt| fmt.name() == name)
+            .copied()
+            .ok_or(Error::UnknownFormat())
+    }
+}
+
+/// Number of properties parsed when scoring format detection
+const SAMPLE_PROPS: usize = 256;
+
+/// Make a report of ligatures dropped by a writer
+#[cfg(any(
+    feature = "fmt-ctable",
+    feature = "fmt-escpos",
+    feature = "fmt-gfx",
+    feature = "fmt-u8g2"
+))]
+fn ligature_report<'a>(
+    ligatures: impl Iterator<Item = (&'a str, u32)>,
+) -> WriteReport {
+    let mut report = WriteReport::default();
+    for (sequence, _cp) in ligatures {
+        report.ligatures.push(sequence.to_string());
+    }
+    report
+}
+
+/// Split a source containing concatenated fonts into one source per font
+///
+/// A new font begins at a line starting a font in any readable format, such
+/// as `STARTFONT` or `font_name: `, so fonts of different formats can be
+/// mixed.  Anything before the first font (such as comments) is included
+/// with it.  A source with one font (or none) is returned whole.
+pub fn split(buf: &str) -> Vec<&str> {
+    let mut starts = Vec::new();
+    let mut pos = 0;
+    for line in buf.split_inclusive('\n') {
+        let head = line.trim_start();
+        if Format::ALL
+            .iter()
+            .filter_map(|fmt| fmt.font_start())
+            .any(|start| head.starts_with(start))
+        {
+            starts.push(pos);
+        }
+        pos += line.len();
+    }
+    let mut fonts = Vec::new();
+    let mut begin = 0;
+    for start in starts.into_iter().skip(1) {
+        fonts.push(&buf[begin..start]);
+        begin = start;
+    }
+    fonts.push(&buf[begin..]);
+    fonts
+}
+
+impl Format {
+    /// All supported formats (enabled by cargo features)
+    pub const ALL: &'static [Format] = &[
+        #[cfg(feature = "fmt-bdf")]
+        Format::Bdf,
+        #[cfg(feature = "fmt-ctable")]
+        Format::Ctable,
+        #[cfg(feature = "fmt-escpos")]
+        Format::Escpos,
+        #[cfg(feature = "fmt-gfx")]
+        Format::Gfx,
+        #[cfg(feature = "fmt-ifnt")]
+        Format::Ifnt,
+        #[cfg(feature = "fmt-ifntx")]
+        Format::Ifntx,
+        #[cfg(feature = "fmt-iris")]
+        Format::Iris,
+        Format::Tfon,
+        #[cfg(feature = "fmt-u8g2")]
+        Format::U8g2,
+    ];
+
+    /// Get format name
+    pub fn name(self) -> &'static str {
+        match self {
+            Format::Bdf => "bdf",
+            Format::Ctable => "ctable",
+            Format::Escpos => "escpos",
+            Format::Gfx => "gfx",
+            Format::Ifnt => "ifnt",
+            Format::Ifntx => "ifntx",
+            Format::Iris => "iris",
+            Format::Tfon => "tfon",
+            Format::U8g2 => "u8g2",
+        }
+    }
+
+    /// Check if format is enabled by cargo features
+    pub fn is_enabled(self) -> bool {
+        Format::ALL.contains(&self)
+    }
+
+    /// Check if format can be parsed
+    pub fn can_read(self) -> bool {
+        self.is_enabled()
+            && matches!(
+                self,
+                Format::Bdf
+                    | Format::Ifnt
+                    | Format::Ifntx
+                    | Format::Iris
+                    | Format::Tfon
+            )
+    }
+
+    /// Check if format can be written
+    pub fn can_write(self) -> bool {
+        self.is_enabled() && self != Format::Ifntx
+    }
+
+    /// Check if format is binary (not text)
+    pub fn is_binary(self) -> bool {
+        self == Format::Escpos
+    }
+
+    /// Get default file extension
+    pub fn extension(self) -> &'static str {
+        match self {
+            Format::Bdf => "bdf",
+            Format::Ctable => "c",
+            Format::Escpos => "bin",
+            Format::Gfx => "h",
+            Format::Ifnt => "ifnt",
+            Format::Ifntx => "ifntx",
+            Format::Iris => "xml",
+            Format::Tfon => "tfon",
+            Format::U8g2 => "c",
+        }
+    }
+
+    /// Get MIME type, for HTTP responses and manifests
+    pub fn mime_type(self) -> &'static str {
+        match self {
+            Format::Bdf => "application/x-font-bdf",
+            Format::Ctable | Format::Gfx | Format::U8g2 => {
+                "text/x-c; charset=utf-8"
+            }
+            Format::Escpos => "application/octet-stream",
+            Format::Ifnt | Format::Ifntx => "text/plain; charset=utf-8",
+            Format::Iris => "application/xml",
+            Format::Tfon => "application/x-tfon",
+        }
+    }
+
+    /// Get a readable format from a file extension (or format name)
+    ///
+    /// IRIS fonts use the `xml` extension.
+    pub fn from_extension(ext: &str) -> Option<Self> {
+        Format::ALL
+            .iter()
+            .find(|fmt| fmt.extension() == ext)
+            .copied()
+            .or_else(|| Format::from_str(ext).ok())
+            .filter(|fmt| fmt.can_read())
+    }
+
+    /// Get a writable format from an output file extension
+    ///
+    /// Either a format name (such as `u8g2`) or a default extension (such
+    /// as `h`) is accepted.  Since C tables and u8g2 arrays share the `c`
+    /// extension, it picks C tables.
+    pub fn from_output_extension(ext: &str) -> Option<Self> {
+        Format::from_str(ext)
+            .ok()
+            .or_else(|| {
+                Format::ALL
+                    .iter()
+                    .find(|fmt| fmt.extension() == ext)
+                    .copied()
+            })
+            .filter(|fmt| fmt.can_write())
+    }
+
+    /// Get the text which starts a font source
+    fn font_start(self) -> Option<&'static str> {
+        match self {
+            Format::Bdf => Some("STARTFONT"),
+            Format::Ifnt => Some("[FontInfo]"),
+            Format::Ifntx => Some("name: "),
+            Format::Iris => Some("<font "),
+            Format::Tfon => Some("font_name: "),
+            _ => None,
+        }
+    }
+
+    /// Detect the format of a font source
+    ///
+    /// Each readable format parses a sample of the source, scoring one
+    /// point for each recognized property and losing one for each unknown
+    /// or skipped property.  The best positive score wins (the earliest
+    /// format on a tie), so leading comments, blank lines or additional
+    /// concatenated fonts don't prevent detection.
+    pub fn detect(buf: &str) -> Option<Self> {
+        Format::ALL
+            .iter()
+            .rev()
+            .filter_map(|fmt| Some((*fmt, fmt.score(buf)?)))
+            .filter(|(_fmt, score)| *score > 0)
+            .max_by_key(|(_fmt, score)| *score)
+            .map(|(fmt, _score)| fmt)
+    }
+
+    /// Score how well a readable format parses a sample of a source
+    fn score(self, buf: &str) -> Option<i32> {
+        let props = self.parser(buf).ok()?;
+        Some(
+            props
+                .take(SAMPLE_PROPS)
+                .map(|prop| match prop {
+                    Prop::Unknown(_) | Prop::Skipped(_) => -1,
+                    _ => 1,
+                })
+                .sum(),
+        )
+    }
+
+    /// Parse a font source
+    ///
+    /// Sources with more than one font are rejected; use [split] first.
+    pub fn parse(self, buf: &str) -> Result<Props<'_>> {
+        #[cfg(feature = "fmt-iris")]
+        if self == Format::Iris {
+            let count = iris::font_count(buf);
+            if count > 1 {
+                let ctx = ParseContext::parse(self.name());
+                return Err(ctx.wrap(Error::MultipleFonts(count)));
+            }
+        }
+        self.parser(buf)
+    }
+
+    /// Make a parser for a font source
+    fn parser(self, buf: &str) -> Result<Props<'_>> {
+        match self {
+            #[cfg(feature = "fmt-bdf")]
+            Format::Bdf => Ok(Box::new(bdf::Parser::new(buf))),
+            #[cfg(feature = "fmt-ifnt")]
+            Format::Ifnt => Ok(Box::new(ifnt::Parser::new(buf))),
+            #[cfg(feature = "fmt-ifntx")]
+            Format::Ifntx => Ok(Box::new(ifntx::Parser::new(buf))),
+            #[cfg(feature = "fmt-iris")]
+            Format::Iris => Ok(Box::new(iris::Parser::new(buf))),
+            Format::Tfon => Ok(Box::new(tfon::Parser::new(buf))),
+            _ => Err(Error::UnknownFormat()),
+        }
+    }
+
+    /// Parse a font source, enforcing input size limits
+    ///
+    /// Line lengths are checked before parsing, and glyph and pixel counts
+    /// as properties are parsed, so memory use is bounded for untrusted
+    /// input.
+    pub fn parse_limited<'a>(
+        self,
+        buf: &'a str,
+        limits: &Limits,
+    ) -> Result<Vec<Prop<'a>>> {
+        let ctx = ParseContext::parse(self.name());
+        limits.check_lines(buf).map_err(|e| ctx.within(e))?;
+        let mut props = self.parse(buf)?;
+        limits.collect(props.by_ref()).map_err(|e| {
+            match props.line_number() {
+                Some(n) => ctx.line(n),
+                None => ctx,
+            }
+            .wrap(e)
+        })
+    }
+
+    /// Parse a font source and load it as a font
+    ///
+    /// Errors have the format as context, along with the property and code
+    /// point.
+    pub fn load(self, buf: &str) -> Result<Font> {
+        let ctx = ParseContext::parse(self.name());
+        Font::from_props(self.parse(buf)?).map_err(|e| ctx.within(e))
+    }
+
+    /// Write a font, with options
+    ///
+    /// Code generation formats use the font name for identifiers.
+    pub fn write_with<'a, W: Write>(
+        self,
+        writer: W,
+        props: impl Iterator<Item = Prop<'a>>,
+        options: &WriteOptions,
+    ) -> Result<WriteReport> {
+        match self {
+            #[cfg(feature = "fmt-bdf")]
+            Format::Bdf => bdf::write_with(writer, props, options),
+            #[cfg(feature = "fmt-ifnt")]
+            Format::Ifnt => ifnt::write_with(writer, props, options),
+            #[cfg(feature = "fmt-iris")]
+            Format::Iris => iris::write_with(writer, props, options),
+            Format::Tfon => tfon::write_with(writer, props, options),
+            #[cfg(feature = "fmt-escpos")]
+            Format::Escpos => {
+                let props: Vec<_> = props.collect();
+                let report =
+                    ligature_report(props.iter().filter_map(|p| p.ligature()));
+                escpos::write(writer, props.into_iter())?;
+                Ok(report)
+            }
+            #[cfg(feature = "fmt-ctable")]
+            Format::Ctable => {
+                let ctx = ParseContext::write("ctable");
+                let font =
+                    Font::from_props(props).map_err(|e| ctx.within(e))?;
+                ctable::write_c_with(writer, &font, font.name(), options)?;
+                Ok(ligature_report(font.ligatures()))
+            }
+            #[cfg(feature = "fmt-gfx")]
+            Format::Gfx => {
+                let ctx = ParseContext::write("gfx");
+                let font =
+                    Font::from_props(props).map_err(|e| ctx.within(e))?;
+                gfx::write_c(writer, &font, font.name())?;
+                Ok(ligature_report(font.ligatures()))
+            }
+            #[cfg(feature = "fmt-u8g2")]
+            Format::U8g2 => {
+                let ctx = ParseContext::write("u8g2");
+                let font =
+                    Font::from_props(props).map_err(|e| ctx.within(e))?;
+                u8g2::write_c(writer, &font, font.name())?;
+                Ok(ligature_report(font.ligatures()))
+            }
+            _ => Err(Error::UnknownFormat()),
+        }
+    }
+}
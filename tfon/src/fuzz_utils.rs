@@ -0,0 +1,188 @@
+//! Fuzzing utilities
+//!
+//! Fuzzer input ([Unstructured] data) is turned into property streams of
+//! valid fonts, which can then be mutated (as props, or as written source)
+//! to reach the parsers' skip and recovery paths.  [Font], [Format] and
+//! [ValidProps] implement [Arbitrary], so fuzz targets can take them
+//! directly, or compose them into other `Arbitrary` types.  These drive the
+//! targets in the `fuzz` directory, and can be reused for fuzzing other
+//! formats.
+use crate::common::{Bitmap, Prop, Result, WriteOptions};
+use crate::font::Font;
+use crate::format::Format;
+pub use arbitrary;
+use arbitrary::{Arbitrary, Unstructured};
+
+/// Characters which are meaningful to at least one parser
+pub const INTERESTING: &[u8] = b"@.X0 9-:=<>\"'\n#[]/_";
+
+/// Font names for generated fonts
+const NAMES: &[&str] = &["fuzz", "Fuzz Bold", "x", "a=b", "[x]", "#1"];
+
+/// Unknown lines for generated fonts
+const UNKNOWN: &[&str] = &["x: y", "Key=Value", "COMMENT x", "[Other]", ""];
+
+/// Properties of a valid font, generated by [props]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ValidProps(pub Vec<Prop<'static>>);
+
+impl<'a> Arbitrary<'a> for ValidProps {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        props(u).map(ValidProps)
+    }
+}
+
+impl<'a> Arbitrary<'a> for Font {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        Font::from_props(props(u)?.into_iter())
+            .map_err(|_| arbitrary::Error::IncorrectFormat)
+    }
+}
+
+impl<'a> Arbitrary<'a> for Format {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        u.choose(Format::ALL).copied()
+    }
+}
+
+/// Generate a bitmap
+pub fn bitmap(u: &mut Unstructured, height: u8) -> arbitrary::Result<Bitmap> {
+    let width = u.int_in_range(1..=16)?;
+    let mut bmap = Bitmap::new(width);
+    for _ in 0..height {
+        let row = (0..width)
+            .map(|_| bool::arbitrary(u))
+            .collect::<arbitrary::Result<Vec<_>>>()?;
+        bmap.push_row(row.into_iter());
+    }
+    Ok(bmap)
+}
+
+/// Generate properties of a valid font
+///
+/// Glyphs all have the font height, with increasing code points from 32 to
+/// 255 (which every format can write).  Once the data is used up, every
+/// value is the smallest allowed, so generation always finishes with a
+/// small font.
+pub fn props(u: &mut Unstructured) -> arbitrary::Result<Vec<Prop<'static>>> {
+    let height = u.int_in_range(1..=16)?;
+    let mut props = vec![
+        Prop::FontName((*u.choose(NAMES)?).into()),
+        Prop::FontHeight(height),
+        Prop::CharSpacing(u.int_in_range(0..=4)?),
+        Prop::LineSpacing(u.int_in_range(0..=4)?),
+    ];
+    let mut cp: u16 = 32;
+    for _ in 0..u.int_in_range(1u8..=32)? {
+        props.push(Prop::CodePoint(cp));
+        props.push(Prop::Bitmap(bitmap(u, height)?));
+        cp += u.int_in_range(1..=8)?;
+        if u.is_empty() || cp > 255 {
+            break;
+        }
+    }
+    Ok(props)
+}
+
+/// Mutate properties, usually making an invalid font
+pub fn mutate_props(
+    u: &mut Unstructured,
+    props: &mut Vec<Prop<'static>>,
+) -> arbitrary::Result<()> {
+    if props.is_empty() {
+        return Ok(());
+    }
+    let pos = u.choose_index(props.len())?;
+    match u.int_in_range(0..=5)? {
+        0 => {
+            props.remove(pos);
+        }
+        1 => props.insert(pos, props[pos].clone()),
+        2 => {
+            let other = u.choose_index(props.len())?;
+            props.swap(pos, other);
+        }
+        3 => {
+            let height = u.int_in_range(0..=20)?;
+            props.insert(pos, Prop::Bitmap(bitmap(u, height)?));
+        }
+        4 => props.insert(pos, Prop::CodePoint(u16::arbitrary(u)?)),
+        _ => props.insert(pos, Prop::Unknown(u.choose(UNKNOWN)?)),
+    }
+    Ok(())
+}
+
+/// Mutate a font source
+///
+/// Bytes are replaced with [INTERESTING] characters, and lines are
+/// dropped, repeated or truncated.  Invalid UTF-8 is replaced.
+pub fn mutate_text(
+    u: &mut Unstructured,
+    src: &str,
+) -> arbitrary::Result<String> {
+    let mut lines: Vec<Vec<u8>> =
+        src.lines().map(|line| line.as_bytes().to_vec()).collect();
+    while !u.is_empty() && !lines.is_empty() {
+        let pos = u.choose_index(lines.len())?;
+        match u.int_in_range(0..=4)? {
+            0 => {
+                lines.remove(pos);
+            }
+            1 => lines.insert(pos, lines[pos].clone()),
+            2 => {
+                let len = u.int_in_range(0..=lines[pos].len())?;
+                lines[pos].truncate(len);
+            }
+            _ if lines[pos].is_empty() => lines[pos].push(b'\r'),
+            _ => {
+                let col = u.choose_index(lines[pos].len())?;
+                lines[pos][col] = *u.choose(INTERESTING)?;
+            }
+        }
+    }
+    Ok(String::from_utf8_lossy(&lines.join(&b'\n')).into_owned())
+}
+
+/// Write a font in a format, then parse it back
+pub fn round_trip(font: &Font, format: Format) -> Result<Font> {
+    let mut buf = Vec::new();
+    format.write_with(&mut buf, font.props(), &WriteOptions::default())?;
+    let src = String::from_utf8_lossy(&buf);
+    let props = format.parse(&src)?;
+    Font::from_props(props)
+}
+
+/// Feed a source through every parser and writer
+///
+/// Errors are fine; panics are not.
+pub fn exercise(src: &str) {
+    let _ = Format::detect(src);
+    let _ = crate::format::split(src);
+    let _ = crate::tfon::write_canonical(std::io::sink(), src);
+    #[cfg(feature = "fmt-iris")]
+    let _ = crate::iris::read_fonts(src);
+    for fmt in Format::ALL.iter().filter(|f| f.can_read()) {
+        let Ok(props) = fmt.parse(src) else {
+            continue;
+        };
+        let props: Vec<Prop> = props.collect();
+        let _ = fmt.parse_limited(src, &Default::default());
+        let font = Font::from_props(props.clone().into_iter());
+        for to in Format::ALL.iter().filter(|f| f.can_write()) {
+            let options = WriteOptions::default();
+            let _ = to.write_with(
+                std::io::sink(),
+                props.clone().into_iter(),
+                &options,
+            );
+            if let Ok(font) = &font {
+                let _ = to.write_with(std::io::sink(), font.props(), &options);
+            }
+        }
+        let _ = crate::tfon::write_exact(
+            std::io::sink(),
+            src,
+            props.clone().into_iter(),
+        );
+    }
+}
@@ -0,0 +1,117 @@
+//! Export fonts as Adafruit GFX `GFXfont` C structs
+//!
+//! Each glyph is cropped to its ink bounds, with bits packed row by row
+//! (most significant bit first) and padded to a byte at the end of the
+//! glyph.  Offsets are relative to the baseline.
+use crate::codegen;
+use crate::common::{Error, ParseContext, Result};
+use crate::font::Font;
+use std::io::Write;
+
+/// Glyph metrics for a `GFXglyph`
+struct Glyph {
+    /// Offset into bitmap array
+    offset: u16,
+    /// Bitmap width
+    width: u8,
+    /// Bitmap height
+    height: u8,
+    /// Distance to advance cursor
+    x_advance: u8,
+    /// Offset from cursor to left edge
+    x_offset: i8,
+    /// Offset from baseline to top edge
+    y_offset: i8,
+}
+
+/// Write a font as an Adafruit GFX `GFXfont`
+///
+/// All code points between the first and last glyphs are included; any
+/// missing glyphs are empty.
+pub fn write_c<W: Write>(mut writer: W, font: &Font, name: &str) -> Result<()> {
+    let ctx = ParseContext::write("gfx");
+    let id = codegen::ident(name);
+    let (Some((first, _)), Some((last, _))) =
+        (font.glyphs().next(), font.glyphs().last())
+    else {
+        return Err(ctx.wrap(Error::Expected("ch")));
+    };
+    let baseline = i16::from(font.baseline());
+    let mut bitmap = Vec::new();
+    let mut glyphs = Vec::new();
+    for cp in first..=last {
+        let at = ctx.at("Bitmap", Some(cp));
+        let offset = u16::try_from(bitmap.len())
+            .map_err(|_| at.wrap(Error::TooLarge()))?;
+        let Some(bmap) = font.glyph(cp) else {
+            glyphs.push(Glyph {
+                offset,
+                width: 0,
+                height: 0,
+                x_advance: 0,
+                x_offset: 0,
+                y_offset: 0,
+            });
+            continue;
+        };
+        let bounds = bmap.ink_bounds().unwrap_or_default();
+        let ink = bmap.crop(bounds);
+        let mut byte = 0;
+        let mut bit = 0;
+        for pix in ink.pixels() {
+            if pix {
+                byte |= 0x80 >> bit;
+            }
+            bit += 1;
+            if bit == 8 {
+                bitmap.push(byte);
+                byte = 0;
+                bit = 0;
+            }
+        }
+        if bit > 0 {
+            bitmap.push(byte);
+        }
+        let y_offset = i16::from(bounds.y) - baseline;
+        glyphs.push(Glyph {
+            offset,
+            width: bounds.width,
+            height: bounds.height,
+            x_advance: bmap.width().saturating_add(font.char_spacing()),
+            x_offset: i8::try_from(bounds.x)
+                .map_err(|_| at.wrap(Error::TooLarge()))?,
+            y_offset: i8::try_from(y_offset)
+                .map_err(|_| at.wrap(Error::TooLarge()))?,
+        });
+    }
+    let y_advance = font.height().saturating_add(font.line_spacing());
+    writeln!(writer, "// {}", font.name())?;
+    writeln!(writer, "const uint8_t {id}Bitmaps[] PROGMEM = {{")?;
+    for chunk in bitmap.chunks(12) {
+        writeln!(writer, "    {},", codegen::hex_list(chunk))?;
+    }
+    writeln!(writer, "}};")?;
+    writeln!(writer)?;
+    writeln!(writer, "const GFXglyph {id}Glyphs[] PROGMEM = {{")?;
+    for (cp, gl) in (first..=last).zip(&glyphs) {
+        writeln!(
+            writer,
+            "    {{{:5}, {:3}, {:3}, {:3}, {:4}, {:4}}}, // {}",
+            gl.offset,
+            gl.width,
+            gl.height,
+            gl.x_advance,
+            gl.x_offset,
+            gl.y_offset,
+            codegen::char_comment(cp)
+        )?;
+    }
+    writeln!(writer, "}};")?;
+    writeln!(writer)?;
+    writeln!(writer, "const GFXfont {id} PROGMEM = {{")?;
+    writeln!(writer, "    (uint8_t *){id}Bitmaps,")?;
+    writeln!(writer, "    (GFXglyph *){id}Glyphs,")?;
+    writeln!(writer, "    0x{first:02X}, 0x{last:02X}, {y_advance}")?;
+    writeln!(writer, "}};")?;
+    Ok(())
+}
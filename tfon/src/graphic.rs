@@ -0,0 +1,128 @@
+//! Graphics and animated pictograms
+//!
+//! An animation is a sequence of bitmap frames, each displayed for a delay.
+//! Since NTCIP 1203 has no animated graphics, animations are exported as
+//! numbered graphics, with a MULTI string displaying one per page.
+use crate::common::{Bitmap, Error, Result};
+
+/// Frame of an animation
+#[derive(Clone)]
+pub struct Frame {
+    /// Frame bitmap
+    pub bitmap: Bitmap,
+    /// Display time (ms)
+    pub delay: u32,
+}
+
+/// Animated graphic
+#[derive(Clone, Default)]
+pub struct Animation {
+    /// All frames
+    pub frames: Vec<Frame>,
+}
+
+/// Graphic for an NTCIP `dmsGraphicTable` row
+#[derive(Clone)]
+pub struct NtcipGraphic {
+    /// Graphic number
+    pub number: u8,
+    /// Height in pixels
+    pub height: u8,
+    /// Width in pixels
+    pub width: u8,
+    /// Bitmap data (`monochrome1bit` color scheme)
+    pub bitmap: Vec<u8>,
+}
+
+impl Animation {
+    /// Import an animation from a GIF image
+    ///
+    /// Pixels are on when opaque and brighter than 50%.
+    #[cfg(feature = "gif")]
+    pub fn from_gif<R: std::io::Read>(reader: R) -> Result<Self> {
+        let mut options = gif::DecodeOptions::new();
+        options.set_color_output(gif::ColorOutput::RGBA);
+        let mut decoder =
+            options.read_info(reader).map_err(std::io::Error::other)?;
+        let width =
+            u8::try_from(decoder.width()).map_err(|_| Error::TooLarge())?;
+        let height =
+            u8::try_from(decoder.height()).map_err(|_| Error::TooLarge())?;
+        let mut canvas = Bitmap::new_blank(height, width);
+        let mut frames = Vec::new();
+        while let Some(frame) =
+            decoder.read_next_frame().map_err(std::io::Error::other)?
+        {
+            let previous = canvas.clone();
+            for (i, px) in frame.buffer.chunks_exact(4).enumerate() {
+                let x = usize::from(frame.left) + i % usize::from(frame.width);
+                let y = usize::from(frame.top) + i / usize::from(frame.width);
+                let (Ok(x), Ok(y)) = (u8::try_from(x), u8::try_from(y)) else {
+                    continue;
+                };
+                if x < width && y < height && px[3] >= 128 {
+                    let lum = (u32::from(px[0]) * 299
+                        + u32::from(px[1]) * 587
+                        + u32::from(px[2]) * 114)
+                        / 1000;
+                    canvas.set_pixel(x, y, lum >= 128);
+                }
+            }
+            frames.push(Frame {
+                bitmap: canvas.clone(),
+                delay: u32::from(frame.delay) * 10,
+            });
+            match frame.dispose {
+                gif::DisposalMethod::Background => {
+                    canvas = Bitmap::new_blank(height, width);
+                }
+                gif::DisposalMethod::Previous => canvas = previous,
+                _ => (),
+            }
+        }
+        Ok(Animation { frames })
+    }
+
+    /// Get graphic numbers of all frames
+    ///
+    /// An error is returned if the last frame would be numbered above 255.
+    fn numbers(&self, number: u8) -> Result<impl Iterator<Item = u8>> {
+        let count = u8::try_from(self.frames.len().saturating_sub(1))
+            .map_err(|_| Error::TooLarge())?;
+        let last = number.checked_add(count).ok_or(Error::TooLarge())?;
+        Ok(number..=last)
+    }
+
+    /// Get NTCIP graphics for all frames
+    ///
+    /// * `number` Graphic number of first frame.
+    pub fn ntcip_graphics(&self, number: u8) -> Result<Vec<NtcipGraphic>> {
+        Ok(self
+            .frames
+            .iter()
+            .zip(self.numbers(number)?)
+            .map(|(frame, number)| NtcipGraphic {
+                number,
+                height: frame.bitmap.height(),
+                width: frame.bitmap.width(),
+                bitmap: frame.bitmap.clone().into_bits(),
+            })
+            .collect())
+    }
+
+    /// Get a MULTI string displaying all frames, one per page
+    ///
+    /// * `number` Graphic number of first frame.
+    pub fn ntcip_multi(&self, number: u8) -> Result<String> {
+        let mut ms = String::new();
+        for (frame, number) in self.frames.iter().zip(self.numbers(number)?) {
+            if !ms.is_empty() {
+                ms.push_str("[np]");
+            }
+            // page times are in tenths of a second
+            let pt = (frame.delay / 100).clamp(1, 255);
+            ms.push_str(&format!("[pt{pt}o0][g{number}]"));
+        }
+        Ok(ms)
+    }
+}
@@ -0,0 +1,28 @@
+//! Gzip-compressed fonts
+//!
+//! Many BDF fonts ship compressed, as `.bdf.gz`.  Compressed data is
+//! recognized by its magic number, so it can be decompressed transparently
+//! whatever the file name.
+use crate::common::Result;
+use flate2::read::MultiGzDecoder;
+use std::io::Read;
+
+/// Gzip magic number
+const MAGIC: [u8; 2] = [0x1F, 0x8B];
+
+/// Check if data is gzip-compressed
+pub fn is_gzip(data: &[u8]) -> bool {
+    data.starts_with(&MAGIC)
+}
+
+/// Decompress data if it is gzip-compressed
+///
+/// Uncompressed data is returned unchanged.
+pub fn decode(data: Vec<u8>) -> Result<Vec<u8>> {
+    if !is_gzip(&data) {
+        return Ok(data);
+    }
+    let mut buf = Vec::with_capacity(data.len() * 4);
+    MultiGzDecoder::new(&data[..]).read_to_end(&mut buf)?;
+    Ok(buf)
+}
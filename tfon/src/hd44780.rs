@@ -0,0 +1,84 @@
+//! Export custom characters for HD44780 character LCDs
+//!
+//! These controllers have CGRAM for 8 custom characters (a bank), each a
+//! 5x8 cell stored as 8 bytes: one per row, with the leftmost pixel in
+//! bit 4.
+use crate::codegen;
+use crate::common::{Bitmap, Error, Result};
+use crate::font::Font;
+use std::io::Write;
+
+/// Custom characters per CGRAM bank
+pub const BANK_SIZE: usize = 8;
+
+/// Character cell width
+const CELL_WIDTH: u8 = 5;
+
+/// Character cell height
+const CELL_HEIGHT: u8 = 8;
+
+/// CGRAM pattern for one character
+pub type Pattern = [u8; 8];
+
+/// Get the CGRAM pattern for a glyph
+///
+/// The glyph is placed at the top left of the cell.
+pub fn pattern(cp: u16, bmap: &Bitmap) -> Result<Pattern> {
+    if bmap.width() > CELL_WIDTH || bmap.height() > CELL_HEIGHT {
+        return Err(Error::GlyphTooLarge(cp));
+    }
+    let mut pat = [0; 8];
+    for (y, row) in (0..).zip(pat.iter_mut()) {
+        for x in 0..CELL_WIDTH {
+            if bmap.pixel(x, y) {
+                *row |= 0x10 >> x;
+            }
+        }
+    }
+    Ok(pat)
+}
+
+/// Get CGRAM banks for selected characters of a font
+///
+/// Characters are split into banks of [BANK_SIZE].
+pub fn banks(font: &Font, chars: &str) -> Result<Vec<Vec<Pattern>>> {
+    let mut patterns = Vec::new();
+    for c in chars.chars() {
+        let bmap = font.glyph_char(c).ok_or(Error::MissingGlyph(c))?;
+        patterns.push(pattern(u32::from(c) as u16, bmap)?);
+    }
+    Ok(patterns.chunks(BANK_SIZE).map(|b| b.to_vec()).collect())
+}
+
+/// Write CGRAM banks for selected characters as C arrays
+///
+/// * `name` Array name prefix; each bank is suffixed with its number.
+pub fn write_c<W: Write>(
+    mut writer: W,
+    font: &Font,
+    chars: &str,
+    name: &str,
+) -> Result<()> {
+    let id = codegen::ident(name);
+    let banks = banks(font, chars)?;
+    let chars: Vec<char> = chars.chars().collect();
+    for (num, (bank, chars)) in
+        banks.iter().zip(chars.chunks(BANK_SIZE)).enumerate()
+    {
+        if num > 0 {
+            writeln!(writer)?;
+        }
+        writeln!(writer, "// {}: CGRAM bank {num}", font.name())?;
+        writeln!(writer, "const uint8_t {id}_{num}[{}][8] = {{", bank.len())?;
+        for (pat, c) in bank.iter().zip(chars) {
+            let comment = codegen::char_comment(u32::from(*c) as u16);
+            writeln!(
+                writer,
+                "    {{{}}}, // {comment}",
+                codegen::hex_list(pat)
+            )?;
+        }
+        writeln!(writer, "}};")?;
+    }
+    Ok(())
+}
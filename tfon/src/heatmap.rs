@@ -0,0 +1,141 @@
+//! Heatmaps of pixel usage
+//!
+//! Ink is summed at each position across all glyphs of a font, aligned at
+//! the top left of a common cell (the tallest by the widest glyph).
+//! Systematic alignment problems stand out, such as every glyph hugging the
+//! top row, or a column which is never used.
+use crate::common::Bitmap;
+#[cfg(feature = "png")]
+use crate::common::Result;
+use crate::font::Font;
+#[cfg(feature = "png")]
+use std::io::Write;
+
+/// Shades for text output, from unused to most used
+const SHADES: &[char] = &[' ', '.', ':', '-', '=', '+', '*', '#', '%', '@'];
+
+/// Scale a count to a level, from 0 to `top`
+///
+/// Any ink at all is at least level 1.
+fn level(count: u32, max: u32, top: u32) -> u32 {
+    if max > 0 {
+        (u64::from(count) * u64::from(top)).div_ceil(u64::from(max)) as u32
+    } else {
+        0
+    }
+}
+
+/// Sum of ink at each position of a character cell
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Heatmap {
+    /// Cell width
+    width: u8,
+    /// Cell height
+    height: u8,
+    /// Number of glyphs summed
+    glyphs: u32,
+    /// Glyphs with ink at each position, row by row
+    counts: Vec<u32>,
+}
+
+impl Heatmap {
+    /// Make a heatmap of all glyphs in a font
+    pub fn new(font: &Font) -> Self {
+        let bitmaps: Vec<&Bitmap> =
+            font.glyphs().map(|(_cp, bmap)| bmap).collect();
+        let width = bitmaps.iter().map(|b| b.width()).max().unwrap_or(0);
+        let height = bitmaps.iter().map(|b| b.height()).max().unwrap_or(0);
+        let mut counts = vec![0; usize::from(width) * usize::from(height)];
+        for bmap in &bitmaps {
+            for y in 0..bmap.height() {
+                for x in 0..bmap.width() {
+                    if bmap.pixel(x, y) {
+                        let pos = usize::from(y) * usize::from(width)
+                            + usize::from(x);
+                        counts[pos] += 1;
+                    }
+                }
+            }
+        }
+        Heatmap {
+            width,
+            height,
+            glyphs: bitmaps.len() as u32,
+            counts,
+        }
+    }
+
+    /// Get cell width
+    pub fn width(&self) -> u8 {
+        self.width
+    }
+
+    /// Get cell height
+    pub fn height(&self) -> u8 {
+        self.height
+    }
+
+    /// Get number of glyphs summed
+    pub fn glyphs(&self) -> u32 {
+        self.glyphs
+    }
+
+    /// Get number of glyphs with ink at a position
+    ///
+    /// Positions outside the cell are 0.
+    pub fn count(&self, x: u8, y: u8) -> u32 {
+        if x >= self.width || y >= self.height {
+            return 0;
+        }
+        self.counts[usize::from(y) * usize::from(self.width) + usize::from(x)]
+    }
+
+    /// Get the highest count
+    fn max(&self) -> u32 {
+        self.counts.iter().copied().max().unwrap_or(0)
+    }
+
+    /// Get heatmap as text, with one line per row
+    ///
+    /// Each position is shaded relative to the most used position, from
+    /// ` ` (unused) to `@`.
+    pub fn to_text(&self) -> String {
+        let (max, top) = (self.max(), SHADES.len() as u32 - 1);
+        let mut text = String::new();
+        for y in 0..self.height {
+            text.extend(
+                (0..self.width).map(|x| {
+                    SHADES[level(self.count(x, y), max, top) as usize]
+                }),
+            );
+            text.push('\n');
+        }
+        text
+    }
+
+    /// Write heatmap as a grayscale PNG image
+    ///
+    /// * `scale` Size of each position in the image.
+    #[cfg(feature = "png")]
+    pub fn write_png<W: Write>(&self, writer: W, scale: u8) -> Result<()> {
+        let scale = u32::from(scale.max(1));
+        let width = u32::from(self.width) * scale;
+        let height = u32::from(self.height) * scale;
+        let max = self.max();
+        let mut pixels = Vec::with_capacity((width * height) as usize);
+        for y in 0..height {
+            for x in 0..width {
+                let count = self.count((x / scale) as u8, (y / scale) as u8);
+                pixels.push(level(count, max, 255) as u8);
+            }
+        }
+        let mut enc = png::Encoder::new(writer, width, height);
+        enc.set_color(png::ColorType::Grayscale);
+        enc.set_depth(png::BitDepth::Eight);
+        let mut writer = enc.write_header().map_err(std::io::Error::from)?;
+        writer
+            .write_image_data(&pixels)
+            .map_err(std::io::Error::from)?;
+        Ok(())
+    }
+}
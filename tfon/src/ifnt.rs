@@ -1,14 +1,28 @@
 //! Parse and write fonts in `ifnt` format
-use crate::common::{Bitmap, Error, Prop, Result};
+use crate::common::{
+    Bitmap, Error, ParseContext, Prop, Result, WriteOptions, WriteReport,
+};
+use crate::format::PropParser;
+use crate::ini::{self, key_value, section};
+use std::collections::VecDeque;
 use std::io::Write;
-use std::str::{FromStr, Lines};
+use std::str::FromStr;
 
 /// Parser for `ifnt` format
+///
+/// The `[FontInfo]` section is parsed first, wherever it appears, so header
+/// properties always come before glyphs.  Lines starting with `;` or `#`
+/// are comments.
 pub struct Parser<'p> {
     /// Lines to parse
-    lines: Lines<'p>,
-    /// Pushed back line
-    line: Option<&'p str>,
+    lines: ini::Reader<'p>,
+    /// Line number where the last property started
+    line: usize,
+    /// Pending properties of a parsed glyph (or the header), with line
+    /// numbers
+    pending: VecDeque<(usize, Prop<'p>)>,
+    /// Current section is `[FontInfo]` (already parsed)
+    font_info: bool,
 }
 
 impl<'p> Iterator for Parser<'p> {
@@ -19,88 +33,182 @@ impl<'p> Iterator for Parser<'p> {
     }
 }
 
-impl<'p> Parser<'p> {
-    /// Create a new `ifnt` parser
-    pub fn new(buf: &'p str) -> Self {
-        let lines = buf.lines();
-        Parser { lines, line: None }
+impl<'p> PropParser<'p> for Parser<'p> {
+    fn line_number(&self) -> Option<usize> {
+        Some(self.line)
     }
+}
 
-    /// Get the next line
-    fn next_line(&mut self) -> Option<&'p str> {
-        if self.line.is_some() {
-            self.line.take()
-        } else {
-            for line in self.lines.by_ref() {
-                if !line.is_empty() {
-                    return Some(line);
-                }
-            }
-            None
+/// Check if a section name is `FontInfo`
+fn is_font_info(name: &str) -> bool {
+    name.eq_ignore_ascii_case("FontInfo")
+}
+
+/// Parse a `[FontInfo]` property
+fn header_prop(line: &str) -> Option<Prop<'_>> {
+    match key_value(line) {
+        Some(("FontName", val)) => Some(Prop::FontName(val.trim_end().into())),
+        Some(("FontHeight", val)) => {
+            u8::from_str(val.trim_end()).ok().map(Prop::FontHeight)
+        }
+        Some(("FontWidth", val)) => {
+            u8::from_str(val.trim_end()).ok().map(Prop::FontWidth)
+        }
+        Some(("CharSpacing", val)) => {
+            u8::from_str(val.trim_end()).ok().map(Prop::CharSpacing)
+        }
+        Some(("LineSpacing", val)) => {
+            u8::from_str(val.trim_end()).ok().map(Prop::LineSpacing)
         }
+        Some(("MaxCharNumber", val)) => {
+            u16::from_str(val.trim_end()).ok().map(Prop::MaxCharNumber)
+        }
+        _ => Some(Prop::Unknown(line)),
     }
+}
 
-    /// Push a line back
-    fn push_line(&mut self, line: &'p str) {
-        self.line = Some(line);
+impl<'p> Parser<'p> {
+    /// Create a new `ifnt` parser
+    pub fn new(buf: &'p str) -> Self {
+        let pending = ini::Reader::section_lines(buf, is_font_info)
+            .filter_map(|(number, line)| Some((number, header_prop(line)?)))
+            .collect();
+        Parser {
+            lines: ini::Reader::new(buf),
+            line: 0,
+            pending,
+            font_info: false,
+        }
     }
 
     /// Parse one property
     fn prop(&mut self) -> Option<Prop<'p>> {
-        let line = self.next_line()?;
-        if let Some(end) = line.strip_prefix("[Char_") {
-            if let Some(cp) = end.strip_suffix(']') {
-                return u16::from_str(cp).ok().map(Prop::CodePoint);
+        if let Some((number, prop)) = self.pending.pop_front() {
+            self.line = number;
+            return Some(prop);
+        }
+        let mut line = self.lines.next()?;
+        loop {
+            if let Some(name) = section(line) {
+                self.font_info = is_font_info(name);
+                if !self.font_info {
+                    break;
+                }
+            } else if !self.font_info {
+                break;
             }
+            // `[FontInfo]` was parsed when created
+            line = self.lines.next()?;
+        }
+        self.line = self.lines.line_number();
+        if let Some(name) = section(line) {
+            return match name.strip_prefix("Char_") {
+                Some(cp) => Some(self.glyph(line, u16::from_str(cp).ok())),
+                None => Some(self.skip(line)),
+            };
         }
-        match line.split_once('=') {
-            Some(("FontName", val)) => Some(Prop::FontName(val)),
-            Some(("FontHeight", val)) => {
-                u8::from_str(val).ok().map(Prop::FontHeight)
+        match key_value(line) {
+            Some(("Character", _val)) => {
+                // section header is missing
+                self.lines.push_back(line);
+                Some(self.glyph(line, None))
             }
-            Some(("CharSpacing", val)) => {
-                u8::from_str(val).ok().map(Prop::CharSpacing)
+            _ if line.starts_with("row") => Some(self.skip(line)),
+            _ => header_prop(line),
+        }
+    }
+
+    /// Parse a glyph block: a `[Char_N]` section with bitmap rows
+    ///
+    /// The `Character` value is checked against the section code point, or
+    /// used in its place if the section header is missing.  If the block is
+    /// malformed, it is skipped.
+    fn glyph(&mut self, line: &'p str, mut cp: Option<u16>) -> Prop<'p> {
+        let mut character = None;
+        let mut bitmap: Option<Bitmap> = None;
+        let mut unknown = Vec::new();
+        let mut valid = true;
+        while let Some(next) = self.lines.next() {
+            let key = key_value(next).map(|(key, _val)| key);
+            if next.starts_with('[')
+                || (character.is_some() && key == Some("Character"))
+            {
+                self.lines.push_back(next);
+                break;
+            }
+            if next.starts_with("row") {
+                let pix: Vec<_> = parse_row(next).collect();
+                match &mut bitmap {
+                    Some(bmap) if usize::from(bmap.width()) == pix.len() => {
+                        if bmap.try_push_row(pix.into_iter()).is_err() {
+                            valid = false;
+                        }
+                    }
+                    Some(_) => valid = false,
+                    None => match u8::try_from(pix.len()) {
+                        Ok(width) if width > 0 => {
+                            let mut bmap = Bitmap::new(width);
+                            bmap.push_row(pix.into_iter());
+                            bitmap = Some(bmap);
+                        }
+                        _ => valid = false,
+                    },
+                }
+            } else if let Some(("Character", val)) = key_value(next) {
+                character = Some(parse_character(val));
+            } else if key.is_some() {
+                unknown.push((self.lines.line_number(), Prop::Unknown(next)));
+            } else {
+                valid = false;
             }
-            Some(("LineSpacing", val)) => {
-                u8::from_str(val).ok().map(Prop::LineSpacing)
+        }
+        match (cp, character.flatten()) {
+            (None, ch) => cp = ch,
+            #[cfg(feature = "tracing")]
+            (Some(sect), Some(ch)) if sect != ch => {
+                tracing::warn!(line, ch, "Character does not match section");
             }
-            Some(("MaxCharNumber", val)) => {
-                u16::from_str(val).ok().map(Prop::MaxCharNumber)
+            _ => (),
+        }
+        match (cp, bitmap) {
+            (Some(cp), Some(bmap)) if valid => {
+                self.pending.extend(unknown);
+                self.pending.push_back((self.line, Prop::Bitmap(bmap)));
+                Prop::CodePoint(cp)
             }
-            Some(("Character", _val)) => self.character(line),
-            Some((key, _val)) => Some(Prop::Unknown(key)),
-            _ => Some(Prop::Unknown(line)),
+            _ => Prop::skipped(line),
         }
     }
 
-    /// Parse a bitmap character property
-    fn character(&mut self, _line: &'p str) -> Option<Prop<'p>> {
-        let line = self.next_line()?;
-        let pix: Vec<_> = parse_row(line).collect();
-        let width = u8::try_from(pix.len()).unwrap_or(0);
-        if width == 0 {
-            return None;
-        }
-        let mut bitmap = Bitmap::new(width);
-        bitmap.push_row(parse_row(line));
-        let width = usize::from(width);
-        while let Some(line) = self.next_line() {
-            let pix: Vec<_> = parse_row(line).collect();
-            if pix.len() == width {
-                bitmap.push_row(parse_row(line));
-            } else {
-                self.push_line(line);
+    /// Skip a malformed block (or unknown section), up to the next section
+    fn skip(&mut self, line: &'p str) -> Prop<'p> {
+        while let Some(next) = self.lines.next() {
+            if next.starts_with('[') {
+                self.lines.push_back(next);
                 break;
             }
         }
-        Some(Prop::Bitmap(bitmap))
+        Prop::skipped(line)
+    }
+}
+
+/// Parse a `Character` value, such as `'A'` or `0x41`
+fn parse_character(val: &str) -> Option<u16> {
+    let val = val.trim();
+    if let Some(hex) = val.strip_prefix("0x") {
+        return u16::from_str_radix(hex, 16).ok();
+    }
+    let mut chars = val.strip_prefix('\'')?.strip_suffix('\'')?.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => u16::try_from(u32::from(c)).ok(),
+        _ => None,
     }
 }
 
 /// Parse a bitmap row
 fn parse_row(line: &str) -> impl Iterator<Item = bool> + '_ {
     if line.starts_with("row") {
-        if let Some((_key, val)) = line.split_once('=') {
+        if let Some((_key, val)) = key_value(line) {
             return val.chars().filter_map(pixel_filter_map);
         }
     }
@@ -120,77 +228,126 @@ fn pixel_filter_map(c: char) -> Option<bool> {
 
 /// Write a font in `ifnt` format
 pub fn write<'a, W: Write>(
-    mut writer: W,
+    writer: W,
     props: impl Iterator<Item = Prop<'a>>,
 ) -> Result<()> {
+    write_with(writer, props, &WriteOptions::default())?;
+    Ok(())
+}
+
+/// Write a font in `ifnt` format, with options
+///
+/// Unknown `key=value` properties can be kept; any others are dropped.
+/// Ligatures are dropped, and listed in the report.
+#[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+pub fn write_with<'a, W: Write>(
+    writer: W,
+    props: impl Iterator<Item = Prop<'a>>,
+    options: &WriteOptions,
+) -> Result<WriteReport> {
+    let ctx = ParseContext::write("ifnt");
+    let mut report = WriteReport::default();
     let props: Vec<_> = props.collect();
     let font_name = props
         .iter()
         .find_map(|v| v.font_name())
-        .ok_or(Error::Expected("FontName"))?;
+        .ok_or_else(|| ctx.wrap(Error::Expected("FontName")))?;
     let font_height = props
         .iter()
         .find_map(|v| v.font_height())
-        .ok_or(Error::Expected("FontHeight"))?;
+        .ok_or_else(|| ctx.wrap(Error::Expected("FontHeight")))?;
+    let font_width = props.iter().find_map(|v| v.font_width());
     let char_spacing = props
         .iter()
         .find_map(|v| v.char_spacing())
-        .ok_or(Error::Expected("CharSpacing"))?;
+        .ok_or_else(|| ctx.wrap(Error::Expected("CharSpacing")))?;
     let line_spacing = props
         .iter()
         .find_map(|v| v.line_spacing())
-        .ok_or(Error::Expected("LineSpacing"))?;
-    let max_char_num = props
-        .iter()
-        .filter_map(|v| v.code_point())
-        .last()
-        .ok_or(Error::Expected("MaxCharNumber"))?;
-    writeln!(writer, "[FontInfo]")?;
-    writeln!(writer, "FontName={font_name:64}")?;
-    writeln!(writer, "FontHeight={font_height}")?;
-    writeln!(writer, "CharSpacing={char_spacing}")?;
-    writeln!(writer, "LineSpacing={line_spacing}")?;
-    writeln!(writer, "MaxCharNumber={max_char_num}")?;
+        .ok_or_else(|| ctx.wrap(Error::Expected("LineSpacing")))?;
+    let last = props.iter().filter_map(|v| v.code_point()).max();
+    let max_char_num =
+        match (props.iter().find_map(|v| v.max_char_number()), last) {
+            (Some(mx), Some(cp)) if cp > mx => {
+                return Err(ctx.wrap(Error::OutOfRange(cp, mx)));
+            }
+            (Some(mx), _) => mx,
+            (None, Some(cp)) => cp,
+            (None, None) => {
+                return Err(ctx.wrap(Error::Expected("MaxCharNumber")));
+            }
+        };
+    let mut writer = ini::Writer::new(writer).line_ending(options.line_ending);
+    writer.section("FontInfo")?;
+    writer.entry("FontName", format_args!("{font_name:64}"))?;
+    writer.entry("FontHeight", font_height)?;
+    if let Some(font_width) = font_width {
+        writer.entry("FontWidth", font_width)?;
+    }
+    writer.entry("CharSpacing", char_spacing)?;
+    writer.entry("LineSpacing", line_spacing)?;
+    writer.entry("MaxCharNumber", max_char_num)?;
     let mut ch = true;
+    let mut code_point = None;
     for prop in props {
+        let at = ctx.at(prop.kind(), code_point);
         match prop {
             Prop::CodePoint(cp) => {
                 ch = false;
-                writeln!(writer)?;
-                writeln!(writer, "[Char_{cp}]")?;
-                if (32..127).contains(&cp) {
-                    let c = char::from_u32(u32::from(cp)).unwrap();
-                    writeln!(writer, "Character='{c}'")?;
+                code_point = Some(cp);
+                writer.blank()?;
+                writer.section(&format!("Char_{cp}"))?;
+                if let Some(c) = char::from_u32(u32::from(cp))
+                    .filter(|_| (32..127).contains(&cp))
+                {
+                    writer.entry("Character", format_args!("'{c}'"))?;
                 } else {
-                    writeln!(writer, "Character=0x{cp:x}")?;
+                    writer.entry("Character", format_args!("0x{cp:x}"))?;
                 }
             }
             Prop::Bitmap(bmap) => {
                 if ch {
-                    return Err(Error::Expected("Character"));
+                    return Err(at.misplaced("Character"));
                 }
                 ch = true;
-                let mut col = 0;
-                let mut row = 1;
-                for pix in bmap.pixels() {
-                    if col == 0 {
-                        write!(writer, "row{row:02}=")?;
-                    }
-                    if pix {
-                        write!(writer, " X")?;
-                    } else {
-                        write!(writer, " .")?;
-                    }
-                    col += 1;
-                    if col >= bmap.width {
-                        writeln!(writer)?;
-                        col = 0;
-                        row += 1;
-                    }
+                let cp = code_point.unwrap_or(0);
+                if bmap.height() != font_height {
+                    return Err(at.wrap(Error::GlyphTooLarge(cp)));
                 }
+                bmap.check_width(font_width.unwrap_or(0), cp)
+                    .map_err(|e| at.wrap(e))?;
+                write_rows(&mut writer, &bmap)?;
+            }
+            Prop::Unknown(line)
+                if options.keep_unknown
+                    && line.contains('=')
+                    && !line.starts_with('[') =>
+            {
+                writer.line(line)?;
+            }
+            Prop::Unknown(_) => report.drop_unknown(&prop),
+            Prop::Ligature { sequence, .. } => {
+                report.ligatures.push(sequence.to_string())
             }
             _ => (),
         }
     }
+    Ok(report)
+}
+
+/// Write bitmap rows, numbered from 1
+///
+/// Row numbers are zero-padded to the same number of digits (at least 2).
+fn write_rows<W: Write>(
+    writer: &mut ini::Writer<W>,
+    bmap: &Bitmap,
+) -> Result<()> {
+    let digits = bmap.height().to_string().len().max(2);
+    for row in 0..bmap.height() {
+        let pixels: String = (0..bmap.width())
+            .map(|col| if bmap.pixel(col, row) { " X" } else { " ." })
+            .collect();
+        writer.entry(&format!("row{:0digits$}", u16::from(row) + 1), pixels)?;
+    }
     Ok(())
 }
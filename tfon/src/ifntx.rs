@@ -3,14 +3,18 @@
 //! This is an obsolete font format which is sort of half way between `ifnt`
 //! and `tfon`.  Writing is not supported.
 use crate::common::{Bitmap, Prop};
-use std::str::{FromStr, Lines};
+use crate::cursor::{LineCursor, LinePolicy};
+use crate::format::PropParser;
+use std::str::FromStr;
 
 /// Parser for `ifnt` (X) format
 pub struct Parser<'p> {
     /// Lines to parse
-    lines: Lines<'p>,
-    /// Pushed back line
-    line: Option<&'p str>,
+    lines: LineCursor<'p>,
+    /// Line number where the last property started
+    line: usize,
+    /// Pending bitmap of a parsed glyph
+    bitmap: Option<Bitmap>,
 }
 
 impl<'p> Iterator for Parser<'p> {
@@ -21,37 +25,31 @@ impl<'p> Iterator for Parser<'p> {
     }
 }
 
+impl<'p> PropParser<'p> for Parser<'p> {
+    fn line_number(&self) -> Option<usize> {
+        Some(self.line)
+    }
+}
+
 impl<'p> Parser<'p> {
     /// Create a new `ifnt` (X) parser
     pub fn new(buf: &'p str) -> Self {
-        let lines = buf.lines();
-        Parser { lines, line: None }
-    }
-
-    /// Get the next line
-    fn next_line(&mut self) -> Option<&'p str> {
-        if self.line.is_some() {
-            self.line.take()
-        } else {
-            for line in self.lines.by_ref() {
-                if !line.is_empty() {
-                    return Some(line);
-                }
-            }
-            None
+        Parser {
+            lines: LineCursor::new(buf, LinePolicy::default()),
+            line: 0,
+            bitmap: None,
         }
     }
 
-    /// Push a line back
-    fn push_line(&mut self, line: &'p str) {
-        self.line = Some(line);
-    }
-
     /// Parse one property
     fn prop(&mut self) -> Option<Prop<'p>> {
-        let line = self.next_line()?;
+        if let Some(bmap) = self.bitmap.take() {
+            return Some(Prop::Bitmap(bmap));
+        }
+        let line = self.lines.next_line()?;
+        self.line = self.lines.line_number();
         match line.split_once(": ") {
-            Some(("name", val)) => Some(Prop::FontName(val)),
+            Some(("name", val)) => Some(Prop::FontName(val.into())),
             Some(("font_number", val)) => {
                 u8::from_str(val).ok().map(Prop::FontNumber)
             }
@@ -65,33 +63,65 @@ impl<'p> Parser<'p> {
             Some(("line_spacing", val)) => {
                 u8::from_str(val).ok().map(Prop::LineSpacing)
             }
-            Some(("codepoint", val)) => {
-                let cp = val.split_ascii_whitespace().next().unwrap_or(val);
-                u16::from_str(cp).ok().map(Prop::CodePoint)
+            Some(("codepoint", val)) => Some(self.glyph(line, val)),
+            Some((_key, _val)) => Some(Prop::Unknown(line)),
+            _ => Some(self.skip(line)),
+        }
+    }
+
+    /// Parse a glyph block: a `codepoint` header followed by pixel rows
+    ///
+    /// If the block is malformed, it is skipped.
+    fn glyph(&mut self, line: &'p str, val: &str) -> Prop<'p> {
+        let cp = val.split_ascii_whitespace().next().unwrap_or(val);
+        let cp = u16::from_str(cp).ok();
+        let bitmap = self.bitmap();
+        // block must end at another header, or end of input
+        let ended = match self.lines.peek() {
+            Some(next) => next.contains(": "),
+            None => true,
+        };
+        match (cp, bitmap) {
+            (Some(cp), Some(bmap)) if ended => {
+                self.bitmap = Some(bmap);
+                Prop::CodePoint(cp)
             }
-            Some((key, _val)) => Some(Prop::Unknown(key)),
-            _ => self.character(line),
+            _ => self.skip(line),
         }
     }
 
-    /// Parse a bitmap character property
-    fn character(&mut self, line: &'p str) -> Option<Prop<'p>> {
+    /// Parse pixel rows of a bitmap
+    fn bitmap(&mut self) -> Option<Bitmap> {
+        let line = self.lines.next_line()?;
         let width = u8::try_from(line.len()).unwrap_or(0);
         if width == 0 || !is_pixel_row(line) {
+            self.lines.push_back(line);
             return None;
         }
         let mut bitmap = Bitmap::new(width);
         bitmap.push_row(row_pixels(line));
         let width = usize::from(width);
-        while let Some(line) = self.next_line() {
-            if is_pixel_row(line) && line.len() == width {
-                bitmap.push_row(row_pixels(line));
-            } else {
-                self.push_line(line);
+        while let Some(line) = self.lines.next_line() {
+            let pushed = is_pixel_row(line)
+                && line.len() == width
+                && bitmap.try_push_row(row_pixels(line)).is_ok();
+            if !pushed {
+                self.lines.push_back(line);
+                break;
+            }
+        }
+        Some(bitmap)
+    }
+
+    /// Skip a malformed block, up to the next `codepoint` header
+    fn skip(&mut self, line: &'p str) -> Prop<'p> {
+        while let Some(next) = self.lines.next_line() {
+            if next.starts_with("codepoint: ") {
+                self.lines.push_back(next);
                 break;
             }
         }
-        Some(Prop::Bitmap(bitmap))
+        Prop::skipped(line)
     }
 }
 
@@ -0,0 +1,203 @@
+//! Reading and writing INI files
+//!
+//! Vendor font formats such as `ifnt` are INI files: `[section]` headers
+//! followed by `key=value` lines.  Lines are trimmed, so CRLF line endings
+//! and indentation are accepted, and lines starting with `;` or `#` are
+//! comments.
+use crate::common::LineEnding;
+use crate::cursor::{LineCursor, LinePolicy};
+use std::fmt::Display;
+use std::io::Write;
+
+/// Get the name of a `[section]` line
+pub(crate) fn section(line: &str) -> Option<&str> {
+    Some(line.strip_prefix('[')?.strip_suffix(']')?.trim())
+}
+
+/// Split a `key=value` line, trimming whitespace around the `=`
+///
+/// Sections are not split, even if they contain `=`.
+pub(crate) fn key_value(line: &str) -> Option<(&str, &str)> {
+    if line.starts_with('[') {
+        return None;
+    }
+    line.split_once('=')
+        .map(|(key, val)| (key.trim_end(), val.trim_start()))
+}
+
+/// Reader of INI lines, skipping blank lines and comments
+///
+/// One line can be pushed back, for parsers which read up to the next
+/// section.
+#[derive(Clone, Debug)]
+pub(crate) struct Reader<'a> {
+    /// Lines to read
+    lines: LineCursor<'a>,
+}
+
+impl<'a> Iterator for Reader<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.lines.next_line()
+    }
+}
+
+impl<'a> Reader<'a> {
+    /// Create a new INI reader
+    pub(crate) fn new(buf: &'a str) -> Self {
+        Reader {
+            lines: LineCursor::new(
+                buf,
+                LinePolicy {
+                    trim: true,
+                    comments: &[';', '#'],
+                },
+            ),
+        }
+    }
+
+    /// Push a line back, to be read again
+    pub(crate) fn push_back(&mut self, line: &'a str) {
+        self.lines.push_back(line);
+    }
+
+    /// Get the line number (from 1) of the last line read
+    pub(crate) fn line_number(&self) -> usize {
+        self.lines.line_number()
+    }
+
+    /// Get all `key=value` lines of sections matching a predicate
+    ///
+    /// Each line comes with its line number.  Lines which are not
+    /// `key=value` are included, for reporting.
+    pub(crate) fn section_lines(
+        buf: &'a str,
+        matches: impl Fn(&str) -> bool,
+    ) -> impl Iterator<Item = (usize, &'a str)> {
+        let mut reader = Reader::new(buf);
+        let mut found = false;
+        std::iter::from_fn(move || {
+            while let Some(line) = reader.next() {
+                if let Some(name) = section(line) {
+                    found = matches(name);
+                } else if found {
+                    return Some((reader.line_number(), line));
+                }
+            }
+            None
+        })
+    }
+}
+
+/// Writer of INI lines
+pub(crate) struct Writer<W: Write> {
+    /// Inner writer
+    writer: W,
+    /// Line ending
+    line_ending: LineEnding,
+}
+
+impl<W: Write> Writer<W> {
+    /// Create a new INI writer, with LF line endings
+    pub(crate) fn new(writer: W) -> Self {
+        Writer {
+            writer,
+            line_ending: LineEnding::Lf,
+        }
+    }
+
+    /// Use a line ending
+    pub(crate) fn line_ending(mut self, line_ending: LineEnding) -> Self {
+        self.line_ending = line_ending;
+        self
+    }
+
+    /// Write a blank line
+    pub(crate) fn blank(&mut self) -> std::io::Result<()> {
+        self.writer.write_all(self.line_ending.as_str().as_bytes())
+    }
+
+    /// Write a `[section]` line
+    pub(crate) fn section(&mut self, name: &str) -> std::io::Result<()> {
+        write!(self.writer, "[{name}]{}", self.line_ending.as_str())
+    }
+
+    /// Write a `key=value` line
+    pub(crate) fn entry(
+        &mut self,
+        key: &str,
+        val: impl Display,
+    ) -> std::io::Result<()> {
+        write!(self.writer, "{key}={val}{}", self.line_ending.as_str())
+    }
+
+    /// Write a line as is (such as an unknown entry)
+    pub(crate) fn line(&mut self, line: &str) -> std::io::Result<()> {
+        write!(self.writer, "{line}{}", self.line_ending.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INI: &str = concat!(
+        "; vendor font\r\n",
+        "[Info]\r\n",
+        "  Name = Test \r\n",
+        "\r\n",
+        "# trailing section\r\n",
+        "[Data]\r\n",
+        "row1=X.\r\n",
+        "[info]\r\n",
+        "Size=8\r\n",
+    );
+
+    #[test]
+    fn lines() {
+        assert_eq!(section("[Char_65]"), Some("Char_65"));
+        assert_eq!(section("[ Info ]"), Some("Info"));
+        assert_eq!(section("Info"), None);
+        assert_eq!(key_value("Name = Test"), Some(("Name", "Test")));
+        assert_eq!(key_value("row01= X ."), Some(("row01", "X .")));
+        assert_eq!(key_value("[a=b]"), None);
+        assert_eq!(key_value("junk"), None);
+    }
+
+    #[test]
+    fn reader() {
+        let mut reader = Reader::new(INI);
+        assert_eq!(reader.next(), Some("[Info]"));
+        let line = reader.next().unwrap();
+        assert_eq!(line, "Name = Test");
+        reader.push_back(line);
+        assert_eq!(reader.line_number(), 2);
+        assert_eq!(reader.next(), Some("Name = Test"));
+        assert_eq!(reader.line_number(), 3);
+        assert_eq!(reader.next(), Some("[Data]"));
+        assert_eq!(reader.line_number(), 6);
+        assert_eq!(reader.next(), Some("row1=X."));
+        assert_eq!(reader.count(), 2);
+    }
+
+    #[test]
+    fn section_lines() {
+        let lines: Vec<_> = Reader::section_lines(INI, |name| {
+            name.eq_ignore_ascii_case("info")
+        })
+        .collect();
+        assert_eq!(lines, [(3, "Name = Test"), (9, "Size=8")]);
+    }
+
+    #[test]
+    fn writer() {
+        let mut buf = Vec::new();
+        let mut writer = Writer::new(&mut buf).line_ending(LineEnding::Crlf);
+        writer.section("Info").unwrap();
+        writer.entry("Size", 8).unwrap();
+        writer.blank().unwrap();
+        writer.line("Extra=1").unwrap();
+        assert_eq!(buf, b"[Info]\r\nSize=8\r\n\r\nExtra=1\r\n");
+    }
+}
@@ -0,0 +1,386 @@
+//! Parse and write fonts in IRIS sign configuration XML
+//!
+//! The [IRIS] ATMS exports fonts as `font` elements, each containing
+//! `glyph` elements with pixels packed into bits and encoded as base64:
+//! ```xml
+//! <font name="F08" f_number="8" height="7" width="5" char_spacing="1"
+//!  line_spacing="3">
+//!   <glyph code_point="65" width="5" pixels="I...Q=="/>
+//! </font>
+//! ```
+//! Other elements are ignored, so fonts can be read from a complete sign
+//! configuration export.  An export usually holds many fonts; use
+//! [read_fonts] for those, since [Format::parse] rejects more than one.
+//!
+//! [Format::parse]: crate::Format::parse
+//!
+//! [IRIS]: https://github.com/mnit-rtmc/iris
+use crate::common::{
+    Bitmap, Error, ParseContext, Prop, Result, WriteOptions, WriteReport,
+};
+use crate::font::Font;
+use crate::format::PropParser;
+use std::borrow::Cow;
+use std::collections::VecDeque;
+use std::io::Write;
+use std::str::FromStr;
+
+/// Base64 alphabet
+const BASE64: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Parser for IRIS XML fonts
+pub struct Parser<'p> {
+    /// Remaining source
+    buf: &'p str,
+    /// Height of current font
+    height: Option<u8>,
+    /// Pending properties of a parsed element
+    pending: VecDeque<Prop<'p>>,
+}
+
+impl<'p> Iterator for Parser<'p> {
+    type Item = Prop<'p>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.prop()
+    }
+}
+
+impl<'p> PropParser<'p> for Parser<'p> {}
+
+/// Split an element tag into name and attributes
+fn split_tag(tag: &str) -> (&str, &str) {
+    tag.split_once(char::is_whitespace).unwrap_or((tag, ""))
+}
+
+/// Get an attribute value from a tag
+fn attr<'p>(attrs: &'p str, name: &str) -> Option<&'p str> {
+    let mut rest = attrs;
+    loop {
+        let (key, tail) = rest.split_once('=')?;
+        let tail = tail.trim_start();
+        let quote = tail.chars().next().filter(|q| *q == '"' || *q == '\'')?;
+        let (val, tail) = tail.strip_prefix(quote)?.split_once(quote)?;
+        if key.trim() == name {
+            return Some(val);
+        }
+        rest = tail;
+    }
+}
+
+/// Parse an attribute value
+fn parse_attr<T: FromStr>(attrs: &str, name: &str) -> Option<T> {
+    attr(attrs, name)?.trim().parse().ok()
+}
+
+impl<'p> Parser<'p> {
+    /// Create a new IRIS XML parser
+    pub fn new(buf: &'p str) -> Self {
+        Parser {
+            buf,
+            height: None,
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// Get the next element tag, without angle brackets
+    ///
+    /// Comments, declarations and closing tags are skipped.
+    fn next_tag(&mut self) -> Option<&'p str> {
+        loop {
+            let start = self.buf.find('<')?;
+            let rest = &self.buf[start..];
+            let end = if rest.starts_with("<!--") {
+                rest.find("-->").map(|e| e + 3)
+            } else {
+                tag_end(rest)
+            };
+            let Some(end) = end else {
+                self.buf = "";
+                return None;
+            };
+            self.buf = &rest[end..];
+            let Some(tag) = rest.get(1..end.saturating_sub(1)) else {
+                continue;
+            };
+            if !tag.starts_with(['!', '?', '/']) {
+                return Some(tag.trim_end_matches('/').trim());
+            }
+        }
+    }
+
+    /// Parse one property
+    fn prop(&mut self) -> Option<Prop<'p>> {
+        while self.pending.is_empty() {
+            let tag = self.next_tag()?;
+            match split_tag(tag) {
+                ("font", attrs) => self.parse_font(tag, attrs),
+                ("glyph", attrs) => self.parse_glyph(tag, attrs),
+                _ => (),
+            }
+        }
+        self.pending.pop_front()
+    }
+
+    /// Parse a `font` element
+    fn parse_font(&mut self, tag: &'p str, attrs: &'p str) {
+        self.height = parse_attr(attrs, "height");
+        let (Some(name), Some(height)) = (attr(attrs, "name"), self.height)
+        else {
+            self.pending.push_back(Prop::skipped(tag));
+            return;
+        };
+        self.pending.push_back(Prop::FontName(unescape(name)));
+        if let Some(num) = parse_attr(attrs, "f_number") {
+            self.pending.push_back(Prop::FontNumber(num));
+        }
+        self.pending.push_back(Prop::FontHeight(height));
+        if let Some(width) = parse_attr(attrs, "width") {
+            self.pending.push_back(Prop::FontWidth(width));
+        }
+        if let Some(cs) = parse_attr(attrs, "char_spacing") {
+            self.pending.push_back(Prop::CharSpacing(cs));
+        }
+        if let Some(ls) = parse_attr(attrs, "line_spacing") {
+            self.pending.push_back(Prop::LineSpacing(ls));
+        }
+    }
+
+    /// Parse a `glyph` element
+    fn parse_glyph(&mut self, tag: &'p str, attrs: &'p str) {
+        let glyph = parse_attr(attrs, "code_point").and_then(|cp| {
+            let width = parse_attr(attrs, "width")?;
+            let bits = decode_base64(attr(attrs, "pixels")?)?;
+            Some((cp, Bitmap::from_bits(self.height?, width, bits)?))
+        });
+        match glyph {
+            Some((cp, bmap)) => {
+                self.pending.push_back(Prop::CodePoint(cp));
+                self.pending.push_back(Prop::Bitmap(bmap));
+            }
+            None => self.pending.push_back(Prop::skipped(tag)),
+        }
+    }
+}
+
+/// Find the end of a tag, skipping over quoted attribute values
+fn tag_end(tag: &str) -> Option<usize> {
+    let mut quote = None;
+    for (i, c) in tag.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '>') => return Some(i + 1),
+            (Some(q), _) if q == c => quote = None,
+            _ => (),
+        }
+    }
+    None
+}
+
+/// Decode base64 data
+fn decode_base64(text: &str) -> Option<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(text.len() * 3 / 4);
+    let mut acc = 0u32;
+    let mut bits = 0;
+    for c in text.bytes().filter(|c| !c.is_ascii_whitespace()) {
+        if c == b'=' {
+            break;
+        }
+        let val = BASE64.iter().position(|b| *b == c)?;
+        acc = (acc << 6) | val as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((acc >> bits) as u8);
+        }
+    }
+    Some(bytes)
+}
+
+/// Encode data as base64
+fn encode_base64(bytes: &[u8]) -> String {
+    let mut text = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let b = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let acc =
+            u32::from(b[0]) << 16 | u32::from(b[1]) << 8 | u32::from(b[2]);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                let val = (acc >> (18 - 6 * i)) & 0x3F;
+                text.push(char::from(BASE64[val as usize]));
+            } else {
+                text.push('=');
+            }
+        }
+    }
+    text
+}
+
+/// Escape text for an XML attribute value
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// Decode entity references in an XML attribute value
+///
+/// Unknown references are left as is.
+fn unescape(text: &str) -> Cow<'_, str> {
+    if !text.contains('&') {
+        return Cow::Borrowed(text);
+    }
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let decoded = rest.find(';').and_then(|end| {
+            let c = match &rest[1..end] {
+                "amp" => '&',
+                "lt" => '<',
+                "gt" => '>',
+                "quot" => '"',
+                "apos" => '\'',
+                ent => {
+                    let num = ent.strip_prefix('#')?;
+                    let cp = match num.strip_prefix('x') {
+                        Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                        None => num.parse().ok()?,
+                    };
+                    char::from_u32(cp)?
+                }
+            };
+            Some((c, end + 1))
+        });
+        match decoded {
+            Some((c, len)) => {
+                out.push(c);
+                rest = &rest[len..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    Cow::Owned(out)
+}
+
+/// Count `font` elements in an IRIS XML source
+pub fn font_count(buf: &str) -> usize {
+    let mut parser = Parser::new(buf);
+    std::iter::from_fn(|| parser.next_tag())
+        .filter(|tag| split_tag(tag).0 == "font")
+        .count()
+}
+
+/// Read all fonts in an IRIS XML source
+///
+/// Each `font` element starts a new font.
+pub fn read_fonts(buf: &str) -> Result<Vec<Font>> {
+    let mut fonts = Vec::new();
+    let mut props = Vec::new();
+    for prop in Parser::new(buf) {
+        if matches!(prop, Prop::FontName(_)) && !props.is_empty() {
+            fonts.push(Font::from_props(props.drain(..))?);
+        }
+        props.push(prop);
+    }
+    if !props.is_empty() {
+        fonts.push(Font::from_props(props.drain(..))?);
+    }
+    Ok(fonts)
+}
+
+/// Write a font in IRIS XML format
+pub fn write<'a, W: Write>(
+    writer: W,
+    props: impl Iterator<Item = Prop<'a>>,
+) -> Result<()> {
+    write_with(writer, props, &WriteOptions::default())?;
+    Ok(())
+}
+
+/// Write a font in IRIS XML format, with options
+///
+/// IRIS has no baseline or ligatures; those properties are not written
+/// (dropped ligatures are listed in the report).  Unknown properties are
+/// always dropped.
+#[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+pub fn write_with<'a, W: Write>(
+    mut writer: W,
+    props: impl Iterator<Item = Prop<'a>>,
+    _options: &WriteOptions,
+) -> Result<WriteReport> {
+    let ctx = ParseContext::write("iris");
+    let mut report = WriteReport::default();
+    let props: Vec<_> = props.collect();
+    let font_name = props
+        .iter()
+        .find_map(|v| v.font_name())
+        .ok_or_else(|| ctx.wrap(Error::Expected("FontName")))?;
+    let font_height = props
+        .iter()
+        .find_map(|v| v.font_height())
+        .ok_or_else(|| ctx.wrap(Error::Expected("FontHeight")))?;
+    let font_number = props.iter().find_map(|v| v.font_number()).unwrap_or(1);
+    let font_width = props.iter().find_map(|v| v.font_width()).unwrap_or(0);
+    let char_spacing = props
+        .iter()
+        .find_map(|v| v.char_spacing())
+        .ok_or_else(|| ctx.wrap(Error::Expected("CharSpacing")))?;
+    let line_spacing = props
+        .iter()
+        .find_map(|v| v.line_spacing())
+        .ok_or_else(|| ctx.wrap(Error::Expected("LineSpacing")))?;
+    writeln!(writer, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    write!(writer, r#"<font name="{}""#, escape(font_name))?;
+    write!(writer, r#" f_number="{font_number}""#)?;
+    write!(writer, r#" height="{font_height}" width="{font_width}""#)?;
+    write!(writer, r#" char_spacing="{char_spacing}""#)?;
+    writeln!(writer, r#" line_spacing="{line_spacing}">"#)?;
+    let mut code_point = None;
+    let mut last = None;
+    for prop in props {
+        let at = ctx.at(prop.kind(), code_point.or(last));
+        match prop {
+            Prop::CodePoint(cp) => code_point = Some(cp),
+            Prop::Bitmap(bmap) => {
+                let cp = code_point
+                    .take()
+                    .ok_or_else(|| at.misplaced("CodePoint"))?;
+                last = Some(cp);
+                if bmap.height() != font_height {
+                    return Err(at.wrap(Error::GlyphTooLarge(cp)));
+                }
+                bmap.check_width(font_width, cp).map_err(|e| at.wrap(e))?;
+                let width = bmap.width();
+                let pixels = encode_base64(&bmap.into_bits());
+                write!(writer, r#"  <glyph code_point="{cp}""#)?;
+                writeln!(writer, r#" width="{width}" pixels="{pixels}"/>"#)?;
+            }
+            Prop::Unknown(_) => report.drop_unknown(&prop),
+            Prop::Ligature { sequence, .. } => {
+                report.ligatures.push(sequence.to_string())
+            }
+            _ => (),
+        }
+    }
+    writeln!(writer, "</font>")?;
+    Ok(report)
+}
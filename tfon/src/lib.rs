@@ -3,10 +3,87 @@
 //! ` ↖ ↙ `
 #![forbid(unsafe_code)]
 
+pub mod atlas;
+#[cfg(feature = "fmt-bdf")]
 pub mod bdf;
+#[cfg(feature = "bundle")]
+pub mod bundle;
+#[cfg(feature = "checksum")]
+pub mod checksum;
+mod class;
+mod codegen;
 mod common;
+#[cfg(feature = "fmt-ctable")]
+pub mod ctable;
+mod cursor;
+pub mod demo;
+pub mod diff;
+#[cfg(feature = "embedded-graphics")]
+pub mod embedded;
+#[cfg(feature = "fmt-escpos")]
+pub mod escpos;
+pub mod family;
+#[cfg(feature = "fixtures")]
+pub mod fixtures;
+mod font;
+pub mod footprint;
+mod format;
+#[cfg(feature = "fuzz-utils")]
+pub mod fuzz_utils;
+#[cfg(feature = "fmt-gfx")]
+pub mod gfx;
+pub mod graphic;
+#[cfg(feature = "gzip")]
+pub mod gzip;
+pub mod hd44780;
+pub mod heatmap;
+#[cfg(feature = "fmt-ifnt")]
 pub mod ifnt;
+#[cfg(feature = "fmt-ifntx")]
 pub mod ifntx;
+#[cfg(feature = "fmt-ifnt")]
+mod ini;
+#[cfg(feature = "fmt-iris")]
+pub mod iris;
+mod load;
+mod merge;
+#[cfg(feature = "multi")]
+pub mod multi;
+pub mod names;
+pub mod ntcip;
+pub mod ocr;
+pub mod patch;
+pub mod plotter;
+#[cfg(feature = "png")]
+pub mod preview;
+pub mod quality;
+#[cfg(feature = "png")]
+pub mod quick;
+pub mod render;
+pub mod repo;
+pub mod sdf;
+pub mod segment;
+pub mod sevenseg;
+#[cfg(feature = "png")]
+pub mod sign;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+pub mod static_font;
+pub mod stream;
 pub mod tfon;
+#[cfg(feature = "fmt-u8g2")]
+pub mod u8g2;
 
-pub use common::{Bitmap, Error, Prop};
+pub use class::CharClass;
+pub use common::{
+    Bitmap, Error, Limits, LineEnding, ParseContext, Prop, Rect, WriteOptions,
+    WriteReport,
+};
+pub use font::{Align, ConvertOptions, FillPolicy, Font, Modified, WidthTable};
+pub use format::{split, Format, PropParser, Props};
+pub use load::{load_dir, LoadError};
+pub use merge::{merge3, Conflict, Merge};
+pub use static_font::{Constraints, StaticFont};
+pub use stream::{PropSet, PropStream};
+#[cfg(feature = "macros")]
+pub use tfon_macros::{glyph, include_font, include_static_font};
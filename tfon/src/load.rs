@@ -0,0 +1,121 @@
+// load.rs
+//
+use crate::common::{Error, Result};
+use crate::font::Font;
+use crate::format::Format;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Error loading one font file
+#[derive(Debug)]
+pub struct LoadError {
+    /// Path of font file
+    pub path: PathBuf,
+    /// Error from loading
+    pub error: Error,
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path.display(), self.error)
+    }
+}
+
+impl std::error::Error for LoadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+/// Collect paths of all files in a directory tree, sorted
+pub(crate) fn walk(
+    dir: &Path,
+    paths: &mut Vec<PathBuf>,
+    errors: &mut Vec<LoadError>,
+) {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            errors.push(LoadError {
+                path: dir.to_path_buf(),
+                error: e.into(),
+            });
+            return;
+        }
+    };
+    let mut found: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .collect();
+    found.sort();
+    for path in found {
+        if path.is_dir() {
+            walk(&path, paths, errors);
+        } else {
+            paths.push(path);
+        }
+    }
+}
+
+/// Get the extension of a font file, ignoring any `.gz` extension
+fn font_extension(path: &Path) -> Option<&str> {
+    match path.extension()?.to_str()? {
+        "gz" => Path::new(path.file_stem()?).extension()?.to_str(),
+        ext => Some(ext),
+    }
+}
+
+/// Read the source of a font file
+///
+/// With the `gzip` feature, compressed files are decompressed.
+fn read_source(path: &Path) -> Result<String> {
+    let data = std::fs::read(path)?;
+    #[cfg(feature = "checksum")]
+    crate::checksum::verify_file(path, &data)?;
+    #[cfg(feature = "gzip")]
+    let data = crate::gzip::decode(data)?;
+    String::from_utf8(data).map_err(|e| {
+        std::io::Error::new(std::io::ErrorKind::InvalidData, e).into()
+    })
+}
+
+/// Load one font file, detecting its format
+///
+/// Returns `None` for files which are not fonts.
+fn load_file(path: &Path) -> Option<Result<Font>> {
+    let font_ext = font_extension(path).and_then(Format::from_extension);
+    let src = match read_source(path) {
+        Ok(src) => src,
+        Err(e) if font_ext.is_some() => return Some(Err(e)),
+        Err(_) => return None,
+    };
+    let format = match (Format::detect(&src), font_ext) {
+        (Some(format), _) => format,
+        (None, Some(_)) => return Some(Err(Error::UnknownFormat())),
+        (None, None) => return None,
+    };
+    Some(format.parse(&src).and_then(Font::from_props))
+}
+
+/// Load all fonts in a directory tree
+///
+/// The format of each file is detected from its contents; files which are
+/// not fonts are ignored.  Files which fail to load are reported without
+/// stopping the rest of the batch.  Fonts are ordered by path.
+///
+/// With the `checksum` feature, files with a `.sha256` sidecar are verified.
+/// With the `gzip` feature, compressed files (such as `.bdf.gz`) are
+/// decompressed.
+pub fn load_dir(dir: impl AsRef<Path>) -> (Vec<Font>, Vec<LoadError>) {
+    let mut paths = Vec::new();
+    let mut errors = Vec::new();
+    walk(dir.as_ref(), &mut paths, &mut errors);
+    let mut fonts = Vec::new();
+    for path in paths {
+        match load_file(&path) {
+            Some(Ok(font)) => fonts.push(font),
+            Some(Err(error)) => errors.push(LoadError { path, error }),
+            None => (),
+        }
+    }
+    (fonts, errors)
+}
@@ -0,0 +1,171 @@
+// merge.rs
+//
+use crate::common::{Bitmap, Prop, Result};
+use crate::font::{Font, Modified};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+/// Conflicting change in a three-way merge
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Conflict {
+    /// Header property changed differently (property key)
+    Header(&'static str),
+    /// Ligature changed differently (character sequence)
+    Ligature(String),
+    /// Glyph changed differently (code point)
+    Glyph(u16),
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Conflict::Header(key) => write!(f, "header {key}"),
+            Conflict::Ligature(seq) => write!(f, "ligature {seq}"),
+            Conflict::Glyph(cp) => match char::from_u32((*cp).into()) {
+                Some(ch) if !ch.is_control() => write!(f, "glyph {cp} {ch}"),
+                _ => write!(f, "glyph {cp}"),
+            },
+        }
+    }
+}
+
+/// Result of a three-way merge
+#[derive(Clone, Debug)]
+pub struct Merge {
+    /// Merged font (with our side of any conflicts)
+    pub font: Font,
+    /// Conflicting changes
+    pub conflicts: Vec<Conflict>,
+}
+
+/// Glyph with optional blinking frame and last change
+#[derive(Clone, Copy, Debug)]
+struct Glyph<'a> {
+    bmap: &'a Bitmap,
+    frame: Option<&'a Bitmap>,
+    modified: Option<&'a Modified>,
+}
+
+impl PartialEq for Glyph<'_> {
+    /// Compare pixels only, ignoring the last change
+    fn eq(&self, other: &Self) -> bool {
+        self.bmap == other.bmap && self.frame == other.frame
+    }
+}
+
+/// Pick a value changed on either side from a base
+///
+/// On conflict, our value is picked, and `true` is returned.
+fn pick<T: PartialEq>(base: T, ours: T, theirs: T) -> (T, bool) {
+    if ours == theirs || theirs == base {
+        (ours, false)
+    } else if ours == base {
+        (theirs, false)
+    } else {
+        (ours, true)
+    }
+}
+
+/// Get header property key
+fn header_key(prop: &Prop) -> Option<&'static str> {
+    match prop {
+        Prop::FontName(_) => Some("font_name"),
+        Prop::Family(_) => Some("family"),
+        Prop::Style(_) => Some("style"),
+        Prop::Copyright(_) => Some("copyright"),
+        Prop::Foundry(_) => Some("foundry"),
+        Prop::FontNumber(_) => Some("font_number"),
+        Prop::FontHeight(_) => Some("font_height"),
+        Prop::FontWidth(_) => Some("font_width"),
+        Prop::CharSpacing(_) => Some("char_spacing"),
+        Prop::LineSpacing(_) => Some("line_spacing"),
+        Prop::Baseline(_) => Some("baseline"),
+        Prop::MaxCharNumber(_) => Some("max_char_number"),
+        _ => None,
+    }
+}
+
+/// Get header properties of a font, by key
+fn header(font: &Font) -> BTreeMap<&'static str, Prop<'_>> {
+    font.props()
+        .filter_map(|prop| Some((header_key(&prop)?, prop)))
+        .collect()
+}
+
+/// Get glyphs of a font, with frames and last changes
+fn glyphs(font: &Font) -> BTreeMap<u16, Glyph<'_>> {
+    font.glyphs()
+        .map(|(cp, bmap)| {
+            let frame = font.frame(cp);
+            let modified = font.modified(cp);
+            (
+                cp,
+                Glyph {
+                    bmap,
+                    frame,
+                    modified,
+                },
+            )
+        })
+        .collect()
+}
+
+/// Merge changes to a font from two sides
+///
+/// Changes are resolved for each header property, ligature and glyph (with
+/// its frame and last change).  A change made on only one side (or the
+/// same change on both) is merged; different changes to the same item
+/// conflict, keeping our side.
+///
+/// An error is returned if the merged font is inconsistent, such as glyphs
+/// not matching a changed fixed width.
+pub fn merge3(base: &Font, ours: &Font, theirs: &Font) -> Result<Merge> {
+    let mut conflicts = Vec::new();
+    let mut props = Vec::new();
+    let (b, o, t) = (header(base), header(ours), header(theirs));
+    let keys: BTreeSet<_> = b.keys().chain(o.keys()).chain(t.keys()).collect();
+    for key in keys {
+        let (prop, conflict) = pick(b.get(key), o.get(key), t.get(key));
+        if conflict {
+            conflicts.push(Conflict::Header(key));
+        }
+        props.extend(prop.cloned());
+    }
+    let (b, o, t): (BTreeMap<_, _>, BTreeMap<_, _>, BTreeMap<_, _>) = (
+        base.ligatures().collect(),
+        ours.ligatures().collect(),
+        theirs.ligatures().collect(),
+    );
+    let keys: BTreeSet<_> = b.keys().chain(o.keys()).chain(t.keys()).collect();
+    for seq in keys {
+        let (cp, conflict) = pick(b.get(seq), o.get(seq), t.get(seq));
+        if conflict {
+            conflicts.push(Conflict::Ligature(seq.to_string()));
+        }
+        if let Some(cp) = cp {
+            props.push(Prop::Ligature {
+                sequence: (*seq).into(),
+                code_point: *cp,
+            });
+        }
+    }
+    let (b, o, t) = (glyphs(base), glyphs(ours), glyphs(theirs));
+    let keys: BTreeSet<_> = b.keys().chain(o.keys()).chain(t.keys()).collect();
+    for cp in keys {
+        let (glyph, conflict) = pick(b.get(cp), o.get(cp), t.get(cp));
+        if conflict {
+            conflicts.push(Conflict::Glyph(*cp));
+        }
+        if let Some(glyph) = glyph {
+            props.push(Prop::CodePoint(*cp));
+            props.push(Prop::Bitmap(glyph.bmap.clone()));
+            props.extend(glyph.frame.cloned().map(Prop::Frame));
+            props.extend(glyph.modified.map(|m| Prop::Modified {
+                time: m.time.as_str().into(),
+                author: m.author.as_str().into(),
+            }));
+        }
+    }
+    let font = Font::from_props(props.into_iter())?;
+    Ok(Merge { font, conflicts })
+}
@@ -0,0 +1,325 @@
+//! Render a subset of NTCIP 1203 MULTI markup
+//!
+//! Supported tags:
+//!
+//! - `[nl]` / `[nlN]`: new line, with optional line spacing
+//! - `[jlN]`: line justification (`2`: left, `3`: center, `4`: right)
+//! - `[foN]` / `[foN,cccc]`: select font by number
+//! - `[scN]` / `[/sc]`: character spacing
+//! - `[spN]`: horizontal space of N pixels (replacing character spacing)
+//!
+//! Brackets are escaped as `[[` and `]]`.  All lines are rendered onto a
+//! single page, stacked from the top.
+use crate::common::{Bitmap, Error, Result};
+use crate::font::Font;
+use crate::render::{self, Span};
+use std::str::FromStr;
+
+/// Line justification
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum Justification {
+    /// Left justified
+    #[default]
+    Left,
+    /// Centered
+    Center,
+    /// Right justified
+    Right,
+}
+
+/// Text segment in one font
+struct Segment {
+    /// Font index in bank
+    font: usize,
+    /// Segment text
+    text: String,
+    /// Character spacing override
+    char_spacing: Option<u8>,
+    /// Space before segment (pixels)
+    space: u32,
+}
+
+/// Line of text
+#[derive(Default)]
+struct Line {
+    /// Font index at start of line
+    font: usize,
+    /// Text segments
+    segments: Vec<Segment>,
+    /// Line justification
+    justification: Justification,
+    /// Line spacing override
+    spacing: Option<u8>,
+}
+
+/// MULTI interpreter state
+struct State<'a> {
+    /// Font bank
+    fonts: &'a [Font],
+    /// Current font index
+    font: usize,
+    /// Current character spacing
+    char_spacing: Option<u8>,
+    /// Current justification
+    justification: Justification,
+    /// Completed lines
+    lines: Vec<Line>,
+}
+
+impl Line {
+    /// Push a character to the line
+    fn push_char(&mut self, state: &State, c: char) {
+        match self.segments.last_mut() {
+            Some(seg) if seg.text.is_empty() => {
+                seg.font = state.font;
+                seg.char_spacing = state.char_spacing;
+                seg.text.push(c);
+            }
+            Some(seg)
+                if seg.font == state.font
+                    && seg.char_spacing == state.char_spacing =>
+            {
+                seg.text.push(c);
+            }
+            _ => self.segments.push(Segment {
+                font: state.font,
+                text: c.to_string(),
+                char_spacing: state.char_spacing,
+                space: 0,
+            }),
+        }
+    }
+
+    /// Push horizontal space to the line
+    fn push_space(&mut self, state: &State, space: u8) {
+        match self.segments.last_mut() {
+            Some(seg) if seg.text.is_empty() => seg.space += u32::from(space),
+            _ => self.segments.push(Segment {
+                font: state.font,
+                text: String::new(),
+                char_spacing: state.char_spacing,
+                space: space.into(),
+            }),
+        }
+    }
+
+    /// Get groups of text spans, each with the space before it
+    fn groups<'a>(&'a self, fonts: &'a [Font]) -> Vec<(u32, Vec<Span<'a>>)> {
+        let mut groups: Vec<(u32, Vec<Span>)> = Vec::new();
+        for seg in &self.segments {
+            let span = Span {
+                font: &fonts[seg.font],
+                text: &seg.text,
+                char_spacing: seg.char_spacing,
+            };
+            match groups.last_mut() {
+                Some((_space, spans)) if seg.space == 0 => spans.push(span),
+                _ => groups.push((seg.space, vec![span])),
+            }
+        }
+        groups
+    }
+}
+
+/// Get the ascent (rows above baseline) of text spans
+fn ascent(spans: &[Span]) -> u32 {
+    spans
+        .iter()
+        .map(|s| u32::from(s.font.baseline()))
+        .max()
+        .unwrap_or(0)
+}
+
+/// Get the descent (rows from baseline down) of text spans
+fn descent(spans: &[Span]) -> u32 {
+    spans
+        .iter()
+        .map(|s| u32::from(s.font.height() - s.font.baseline()))
+        .max()
+        .unwrap_or(0)
+}
+
+impl<'a> State<'a> {
+    /// Create new interpreter state
+    fn new(fonts: &'a [Font]) -> Result<Self> {
+        if fonts.is_empty() {
+            return Err(Error::Expected("font"));
+        }
+        Ok(State {
+            fonts,
+            font: 0,
+            char_spacing: None,
+            justification: Justification::default(),
+            lines: Vec::new(),
+        })
+    }
+
+    /// Start a new line
+    fn new_line(&mut self, spacing: Option<u8>) {
+        self.lines.push(Line {
+            font: self.font,
+            justification: self.justification,
+            spacing,
+            ..Default::default()
+        });
+    }
+
+    /// Get the current line
+    fn line(&mut self) -> &mut Line {
+        if self.lines.is_empty() {
+            self.new_line(None);
+        }
+        self.lines.last_mut().unwrap()
+    }
+
+    /// Push a text character
+    fn push_char(&mut self, c: char) {
+        let mut line = std::mem::take(self.line());
+        line.push_char(self, c);
+        *self.line() = line;
+    }
+
+    /// Push horizontal space
+    fn push_space(&mut self, space: u8) {
+        let mut line = std::mem::take(self.line());
+        line.push_space(self, space);
+        *self.line() = line;
+    }
+
+    /// Interpret one tag
+    fn tag(&mut self, tag: &str) -> Result<()> {
+        let lower = tag.to_ascii_lowercase();
+        if let Some(val) = lower.strip_prefix("nl") {
+            self.line();
+            let spacing = if val.is_empty() {
+                None
+            } else {
+                Some(parse_num(tag, val)?)
+            };
+            self.new_line(spacing);
+        } else if let Some(val) = lower.strip_prefix("jl") {
+            self.justification = match val {
+                "" | "2" => Justification::Left,
+                "3" => Justification::Center,
+                "4" => Justification::Right,
+                _ => return Err(Error::UnsupportedTag(tag.into())),
+            };
+            self.line().justification = self.justification;
+        } else if let Some(val) = lower.strip_prefix("fo") {
+            self.font = if val.is_empty() {
+                0
+            } else {
+                let num = val.split(',').next().unwrap_or(val);
+                let num = parse_num(tag, num)?;
+                self.fonts
+                    .iter()
+                    .position(|f| f.number() == num)
+                    .ok_or(Error::MissingFont(num))?
+            };
+        } else if let Some(val) = lower.strip_prefix("sc") {
+            self.char_spacing = Some(parse_num(tag, val)?);
+        } else if lower == "/sc" {
+            self.char_spacing = None;
+        } else if let Some(val) = lower.strip_prefix("sp") {
+            self.push_space(parse_num(tag, val)?);
+        } else {
+            return Err(Error::UnsupportedTag(tag.into()));
+        }
+        Ok(())
+    }
+
+    /// Interpret a MULTI string
+    fn interpret(&mut self, ms: &str) -> Result<()> {
+        let mut chars = ms.char_indices().peekable();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '[' if chars.next_if(|(_, c)| *c == '[').is_some() => {
+                    self.push_char('[');
+                }
+                '[' => {
+                    let end = ms[i..]
+                        .find(']')
+                        .ok_or(Error::UnsupportedTag(ms[i + 1..].into()))?;
+                    self.tag(&ms[i + 1..i + end])?;
+                    while chars.next_if(|(j, _)| *j <= i + end).is_some() {}
+                }
+                ']' if chars.next_if(|(_, c)| *c == ']').is_some() => {
+                    self.push_char(']');
+                }
+                ']' => return Err(Error::UnsupportedTag("]".into())),
+                _ => self.push_char(c),
+            }
+        }
+        Ok(())
+    }
+
+    /// Render all lines to a bitmap
+    fn render(&self, width: u8, height: u8) -> Result<Bitmap> {
+        let mut bitmap = Bitmap::new_blank(height, width);
+        let mut y = 0;
+        let mut prev: Option<&Font> = None;
+        for line in &self.lines {
+            let font = &self.fonts[line.font];
+            if let Some(prev) = prev {
+                let spacing = line
+                    .spacing
+                    .unwrap_or(prev.line_spacing().max(font.line_spacing()));
+                y += u32::from(spacing);
+            }
+            let groups = line.groups(self.fonts);
+            let mut w = 0;
+            for (space, spans) in &groups {
+                w += space + render::measure_spans(spans)?.0;
+            }
+            let spans: Vec<_> = groups
+                .iter()
+                .flat_map(|(_, spans)| spans)
+                .copied()
+                .collect();
+            let (asc, h) = if spans.is_empty() {
+                (0, u32::from(font.height()))
+            } else {
+                (ascent(&spans), ascent(&spans) + descent(&spans))
+            };
+            if w > u32::from(width) || y + h > u32::from(height) {
+                return Err(Error::TooLarge());
+            }
+            let mut x = match line.justification {
+                Justification::Left => 0,
+                Justification::Center => (u32::from(width) - w) / 2,
+                Justification::Right => u32::from(width) - w,
+            };
+            for (space, spans) in &groups {
+                x += space;
+                let bmap = render::spans(spans)?;
+                render::blit(&bmap, &mut bitmap, x, y + asc - ascent(spans));
+                x += u32::from(bmap.width());
+            }
+            y += h;
+            prev = Some(font);
+        }
+        Ok(bitmap)
+    }
+}
+
+/// Parse a numeric tag parameter
+fn parse_num(tag: &str, val: &str) -> Result<u8> {
+    u8::from_str(val).map_err(|_| Error::UnsupportedTag(tag.into()))
+}
+
+/// Render a MULTI string to a bitmap
+///
+/// * `fonts` Font bank; the first font is the default.
+/// * `width` Sign width in pixels.
+/// * `height` Sign height in pixels.
+/// * `ms` MULTI string.
+pub fn render(
+    fonts: &[Font],
+    width: u8,
+    height: u8,
+    ms: &str,
+) -> Result<Bitmap> {
+    let mut state = State::new(fonts)?;
+    state.interpret(ms)?;
+    state.render(width, height)
+}
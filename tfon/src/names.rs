@@ -0,0 +1,89 @@
+//! Character names
+//!
+//! Characters which are hard to type (or see) can be looked up by an
+//! abbreviation such as `NBSP`, or a name such as `no-break space`.
+use std::str::FromStr;
+
+/// Named code points: (code point, abbreviation, name)
+const NAMES: &[(u16, &str, &str)] = &[
+    (0x00, "NUL", "null"),
+    (0x07, "BEL", "bell"),
+    (0x08, "BS", "backspace"),
+    (0x09, "HT", "tab"),
+    (0x0A, "LF", "line feed"),
+    (0x0D, "CR", "carriage return"),
+    (0x1B, "ESC", "escape"),
+    (0x20, "SP", "space"),
+    (0x7F, "DEL", "delete"),
+    (0xA0, "NBSP", "no-break space"),
+    (0xA9, "COPY", "copyright sign"),
+    (0xAD, "SHY", "soft hyphen"),
+    (0xAE, "REG", "registered sign"),
+    (0xB0, "DEG", "degree sign"),
+    (0xB1, "PLUSMN", "plus-minus sign"),
+    (0xB5, "MICRO", "micro sign"),
+    (0xB7, "MIDDOT", "middle dot"),
+    (0xD7, "TIMES", "multiplication sign"),
+    (0xF7, "DIVIDE", "division sign"),
+    (0x2002, "ENSP", "en space"),
+    (0x2003, "EMSP", "em space"),
+    (0x2009, "THINSP", "thin space"),
+    (0x200B, "ZWSP", "zero width space"),
+    (0x2013, "NDASH", "en dash"),
+    (0x2014, "MDASH", "em dash"),
+    (0x2018, "LSQUO", "left single quotation mark"),
+    (0x2019, "RSQUO", "right single quotation mark"),
+    (0x201C, "LDQUO", "left double quotation mark"),
+    (0x201D, "RDQUO", "right double quotation mark"),
+    (0x2022, "BULL", "bullet"),
+    (0x2026, "HELLIP", "horizontal ellipsis"),
+    (0x20AC, "EURO", "euro sign"),
+    (0x2122, "TRADE", "trade mark sign"),
+    (0x2190, "LARR", "leftwards arrow"),
+    (0x2191, "UARR", "upwards arrow"),
+    (0x2192, "RARR", "rightwards arrow"),
+    (0x2193, "DARR", "downwards arrow"),
+    (0xFFFD, "REPL", "replacement character"),
+];
+
+/// Look up a code point by abbreviation or name (ignoring case)
+pub fn code_point(name: &str) -> Option<u16> {
+    NAMES
+        .iter()
+        .find(|(_cp, abbr, nm)| {
+            abbr.eq_ignore_ascii_case(name) || nm.eq_ignore_ascii_case(name)
+        })
+        .map(|(cp, _abbr, _nm)| *cp)
+}
+
+/// Get the abbreviation and name of a code point, if known
+pub fn name(cp: u16) -> Option<(&'static str, &'static str)> {
+    NAMES
+        .iter()
+        .find(|(c, _abbr, _nm)| *c == cp)
+        .map(|(_cp, abbr, nm)| (*abbr, *nm))
+}
+
+/// Resolve a character query to a code point
+///
+/// A query can be a single character (`é`), decimal (`233`), an
+/// abbreviation or name (`NBSP`), a Unicode code point (`U+00E9`) or
+/// hexadecimal (`0xE9`).  A single digit is the character itself, so `7` is
+/// `U+0037`; control codes can be given as `U+0007` or by name (`BEL`).
+pub fn resolve(query: &str) -> Option<u16> {
+    let mut chars = query.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return u16::try_from(u32::from(c)).ok();
+    }
+    if let Ok(cp) = u16::from_str(query) {
+        return Some(cp);
+    }
+    if let Some(hex) = query
+        .strip_prefix("U+")
+        .or_else(|| query.strip_prefix("u+"))
+        .or_else(|| query.strip_prefix("0x"))
+    {
+        return u16::from_str_radix(hex, 16).ok();
+    }
+    code_point(query)
+}
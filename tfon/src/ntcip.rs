@@ -0,0 +1,335 @@
+//! NTCIP 1203 font upload
+//!
+//! Sign controllers are sent fonts over SNMP, as one row of the `fontTable`
+//! and one row of the `characterTable` for each glyph.  Rows are indexed by
+//! `fontIndex` (and `characterNumber`), so each can be set separately,
+//! keeping SNMP requests small.
+//!
+//! Objects can be listed as `snmpset` arguments (OID, type and value):
+//! ```text
+//! 1.3.6.1.4.1.1206.4.2.3.3.4.1.2.1.65 i 5
+//! 1.3.6.1.4.1.1206.4.2.3.3.4.1.3.1.65 x 74631FC620
+//! ```
+//!
+//! Values can also be encoded with BER (as in SNMP messages) or OER (as in
+//! NTCIP 1203 data dumps and the `fontVersionID` checksum).
+use crate::common::{Error, Result};
+use crate::font::Font;
+use std::fmt;
+
+/// OID of `fontEntry` (row of `fontTable`)
+pub const FONT_ENTRY: &str = "1.3.6.1.4.1.1206.4.2.3.3.2.1";
+
+/// OID of `characterEntry` (row of `characterTable`)
+pub const CHARACTER_ENTRY: &str = "1.3.6.1.4.1.1206.4.2.3.3.4.1";
+
+/// Value of an SNMP object
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Value {
+    /// INTEGER (0..255)
+    Uint8(u8),
+    /// INTEGER (0..65535)
+    Uint16(u16),
+    /// OCTET STRING
+    OctetString(Vec<u8>),
+}
+
+/// SNMP object, with OID and value
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Object {
+    /// Object name, including row index (such as `fontHeight.1`)
+    pub name: String,
+    /// Object identifier, including row index
+    pub oid: String,
+    /// Object value
+    pub value: Value,
+}
+
+/// Row of the `fontTable`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FontRow {
+    /// Row index (`fontIndex`)
+    pub index: u8,
+    /// Font number (`fontNumber`)
+    pub number: u8,
+    /// Font name (`fontName`), up to 64 characters
+    pub name: String,
+    /// Font height (`fontHeight`)
+    pub height: u8,
+    /// Pixel spacing between characters (`fontCharSpacing`)
+    pub char_spacing: u8,
+    /// Pixel spacing between lines (`fontLineSpacing`)
+    pub line_spacing: u8,
+    /// Checksum of font and characters (`fontVersionID`)
+    pub version_id: u16,
+}
+
+/// Row of the `characterTable`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CharacterRow {
+    /// Font row index (`fontIndex`)
+    pub font_index: u8,
+    /// Character number (`characterNumber`), which is the code point
+    pub number: u16,
+    /// Width in pixels (`characterWidth`)
+    pub width: u8,
+    /// Packed bits, without row padding (`characterBitmap`)
+    pub bitmap: Vec<u8>,
+}
+
+impl fmt::Display for Object {
+    /// Format as `snmpset` arguments: OID, type and value
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.value {
+            Value::Uint8(v) => write!(f, "{} i {v}", self.oid),
+            Value::Uint16(v) => write!(f, "{} i {v}", self.oid),
+            Value::OctetString(v) => write!(f, "{} x {}", self.oid, hex(v)),
+        }
+    }
+}
+
+/// Format bytes as hexadecimal
+pub fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02X}")).collect()
+}
+
+/// Append a length (BER definite form, or OER length determinant)
+fn push_length(buf: &mut Vec<u8>, len: usize) {
+    if len < 0x80 {
+        buf.push(len as u8);
+    } else {
+        let bytes = len.to_be_bytes();
+        let skip = bytes.iter().take_while(|b| **b == 0).count();
+        buf.push(0x80 | (bytes.len() - skip) as u8);
+        buf.extend(&bytes[skip..]);
+    }
+}
+
+impl Value {
+    /// Encode with Basic Encoding Rules (tag, length and contents)
+    pub fn ber(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        match self {
+            Value::Uint8(v) => ber_integer(&mut buf, (*v).into()),
+            Value::Uint16(v) => ber_integer(&mut buf, *v),
+            Value::OctetString(v) => {
+                buf.push(0x04);
+                push_length(&mut buf, v.len());
+                buf.extend(v);
+            }
+        }
+        buf
+    }
+
+    /// Encode with Octet Encoding Rules
+    ///
+    /// Integers are fixed-size unsigned, as constrained; octet strings have
+    /// a length determinant.
+    pub fn oer(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        self.push_oer(&mut buf);
+        buf
+    }
+
+    /// Append OER encoding
+    fn push_oer(&self, buf: &mut Vec<u8>) {
+        match self {
+            Value::Uint8(v) => buf.push(*v),
+            Value::Uint16(v) => buf.extend(v.to_be_bytes()),
+            Value::OctetString(v) => {
+                push_length(buf, v.len());
+                buf.extend(v);
+            }
+        }
+    }
+}
+
+/// Append a BER INTEGER (minimal two's complement contents)
+fn ber_integer(buf: &mut Vec<u8>, v: u16) {
+    buf.push(0x02);
+    let contents: &[u8] = match v.to_be_bytes() {
+        [0, lo] if lo < 0x80 => &[lo],
+        [0, lo] => &[0, lo],
+        [hi, lo] if hi < 0x80 => &[hi, lo],
+        [hi, lo] => &[0, hi, lo],
+    };
+    buf.push(contents.len() as u8);
+    buf.extend(contents);
+}
+
+/// Compute CRC-16 (ISO 3309 / X.25 frame check sequence)
+fn crc16(bytes: &[u8]) -> u16 {
+    let mut crc = 0xFFFF;
+    for b in bytes {
+        crc ^= u16::from(*b);
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0x8408
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+/// Compute `fontVersionID` of a font
+///
+/// This is the CRC-16 of the OER encoding of the font number, height,
+/// spacing and characters, as specified by NTCIP 1203.
+pub fn version_id(font: &Font, rows: &[CharacterRow]) -> u16 {
+    let mut buf = vec![
+        font.number(),
+        font.height(),
+        font.char_spacing(),
+        font.line_spacing(),
+    ];
+    // SEQUENCE OF quantity: length determinant, then count
+    let count = rows.len().to_be_bytes();
+    let skip = count.iter().take_while(|b| **b == 0).count();
+    let count = &count[skip.min(count.len() - 1)..];
+    push_length(&mut buf, count.len());
+    buf.extend(count);
+    for row in rows {
+        Value::Uint16(row.number).push_oer(&mut buf);
+        for obj in row.objects() {
+            obj.value.push_oer(&mut buf);
+        }
+    }
+    crc16(&buf)
+}
+
+impl FontRow {
+    /// Make a `fontTable` row for a font
+    ///
+    /// * `index` Row index (`fontIndex`), starting at 1.
+    /// * `rows` Character rows, for `fontVersionID`.
+    pub fn new(font: &Font, index: u8, rows: &[CharacterRow]) -> Self {
+        FontRow {
+            index,
+            number: font.number(),
+            name: font.name().chars().take(64).collect(),
+            height: font.height(),
+            char_spacing: font.char_spacing(),
+            line_spacing: font.line_spacing(),
+            version_id: version_id(font, rows),
+        }
+    }
+
+    /// Get SNMP objects of the row
+    ///
+    /// `fontVersionID` is read-only, so it is not included.
+    pub fn objects(&self) -> Vec<Object> {
+        let obj = |col: u8, name: &str, value| Object {
+            name: format!("{name}.{}", self.index),
+            oid: format!("{FONT_ENTRY}.{col}.{}", self.index),
+            value,
+        };
+        vec![
+            obj(2, "fontNumber", Value::Uint8(self.number)),
+            obj(
+                3,
+                "fontName",
+                Value::OctetString(self.name.as_bytes().to_vec()),
+            ),
+            obj(4, "fontHeight", Value::Uint8(self.height)),
+            obj(5, "fontCharSpacing", Value::Uint8(self.char_spacing)),
+            obj(6, "fontLineSpacing", Value::Uint8(self.line_spacing)),
+        ]
+    }
+
+    /// Get `fontVersionID` object, for checking an upload
+    pub fn version_id_object(&self) -> Object {
+        Object {
+            name: format!("fontVersionID.{}", self.index),
+            oid: format!("{FONT_ENTRY}.7.{}", self.index),
+            value: Value::Uint16(self.version_id),
+        }
+    }
+}
+
+impl CharacterRow {
+    /// Get the row index, `fontIndex.characterNumber`
+    pub fn index(&self) -> String {
+        format!("{}.{}", self.font_index, self.number)
+    }
+
+    /// Get SNMP objects of the row
+    pub fn objects(&self) -> Vec<Object> {
+        let obj = |col: u8, name: &str, value| Object {
+            name: format!("{name}.{}", self.index()),
+            oid: format!("{CHARACTER_ENTRY}.{col}.{}", self.index()),
+            value,
+        };
+        vec![
+            obj(2, "characterWidth", Value::Uint8(self.width)),
+            obj(
+                3,
+                "characterBitmap",
+                Value::OctetString(self.bitmap.clone()),
+            ),
+        ]
+    }
+}
+
+/// Split a font into `characterTable` rows, ordered by character number
+///
+/// Code point 0 is skipped, since character numbers start at 1.  An error
+/// is returned if a glyph does not match the font height.
+///
+/// * `font_index` Font row index (`fontIndex`), starting at 1.
+pub fn character_rows(
+    font: &Font,
+    font_index: u8,
+) -> Result<Vec<CharacterRow>> {
+    let mut rows = Vec::new();
+    for (cp, bmap) in font.glyphs().filter(|(cp, _bmap)| *cp > 0) {
+        if bmap.height() != font.height() {
+            return Err(Error::GlyphTooLarge(cp));
+        }
+        rows.push(CharacterRow {
+            font_index,
+            number: cp,
+            width: bmap.width(),
+            bitmap: bmap.clone().into_bits(),
+        });
+    }
+    Ok(rows)
+}
+
+/// Group character rows into batches, for SNMP set requests
+///
+/// Each batch has bitmaps totaling at most `max_bytes`, except that a
+/// larger row is sent in a batch by itself.
+pub fn batches(
+    rows: &[CharacterRow],
+    max_bytes: usize,
+) -> Vec<&[CharacterRow]> {
+    let mut batches = Vec::new();
+    let mut start = 0;
+    let mut bytes = 0;
+    for (i, row) in rows.iter().enumerate() {
+        if i > start && bytes + row.bitmap.len() > max_bytes {
+            batches.push(&rows[start..i]);
+            start = i;
+            bytes = 0;
+        }
+        bytes += row.bitmap.len();
+    }
+    if start < rows.len() {
+        batches.push(&rows[start..]);
+    }
+    batches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crc16_check_value() {
+        // Published check value of CRC-16/X-25
+        assert_eq!(crc16(b"123456789"), 0x906E);
+        assert_eq!(crc16(b""), 0x0000);
+    }
+}
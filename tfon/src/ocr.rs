@@ -0,0 +1,158 @@
+//! Reverse lookup of text from a rendered message
+//!
+//! A bitmap of a sign message, such as one transcribed from a field
+//! photograph, is matched against the glyphs of candidate fonts.  Both the
+//! text and the most likely font are identified.
+use crate::common::Bitmap;
+use crate::font::Font;
+
+/// Text recognized in a bitmap
+pub struct Recognized<'a> {
+    /// Font which best matches the bitmap
+    pub font: &'a Font,
+    /// Recognized text (unmatched shapes are `U+FFFD`)
+    pub text: String,
+    /// Match score, from 0 (no match) to 1 (exact)
+    pub score: f32,
+}
+
+/// Pixel match tally
+#[derive(Clone, Copy, Default)]
+struct Tally {
+    /// Pixels lit in both glyph and bitmap
+    hits: u32,
+    /// Pixels lit in glyph but not bitmap
+    extra: u32,
+    /// Pixels lit in bitmap but not glyph
+    missed: u32,
+}
+
+impl Tally {
+    /// Get quality for choosing between glyphs
+    fn quality(self) -> i64 {
+        i64::from(self.hits) - i64::from(self.extra) - i64::from(self.missed)
+    }
+}
+
+/// Check if a pixel is lit, treating out of bounds pixels as off
+fn lit(bmap: &Bitmap, x: i32, y: i32) -> bool {
+    match (u8::try_from(x), u8::try_from(y)) {
+        (Ok(x), Ok(y)) if x < bmap.width() && y < bmap.height() => {
+            bmap.pixel(x, y)
+        }
+        _ => false,
+    }
+}
+
+/// Check if a column is blank within a band of rows
+fn blank_col(bmap: &Bitmap, x: i32, y: i32, height: u8) -> bool {
+    (0..i32::from(height)).all(|row| !lit(bmap, x, y + row))
+}
+
+/// Compare a glyph with the bitmap at a position
+fn compare(bmap: &Bitmap, glyph: &Bitmap, x: i32, y: i32) -> Tally {
+    let mut tally = Tally::default();
+    for gy in 0..glyph.height() {
+        for gx in 0..glyph.width() {
+            let on = lit(bmap, x + i32::from(gx), y + i32::from(gy));
+            match (glyph.pixel(gx, gy), on) {
+                (true, true) => tally.hits += 1,
+                (true, false) => tally.extra += 1,
+                (false, true) => tally.missed += 1,
+                (false, false) => (),
+            }
+        }
+    }
+    tally
+}
+
+/// Read one line of text using a font, with its top row at `y`
+///
+/// Returns the text and the number of hits and extra pixels.
+fn read_line(bmap: &Bitmap, font: &Font, y: i32) -> (String, u32, u32) {
+    let spacing = i32::from(font.char_spacing());
+    let space = font
+        .glyph(u16::from(b' '))
+        .map_or(i32::from(font.width()), |g| i32::from(g.width()));
+    let glyphs: Vec<(u16, &Bitmap, i32)> = font
+        .glyphs()
+        .filter_map(|(cp, glyph)| {
+            glyph.ink_bounds().map(|r| (cp, glyph, i32::from(r.x)))
+        })
+        .collect();
+    let width = i32::from(bmap.width());
+    let mut text = String::new();
+    let (mut hits, mut extra) = (0, 0);
+    let mut x = 0;
+    loop {
+        let start = x;
+        while x < width && blank_col(bmap, x, y, font.height()) {
+            x += 1;
+        }
+        if x >= width {
+            break;
+        }
+        if !text.is_empty() && space + spacing > 0 {
+            let spaces = (x - start - spacing + (space + spacing) / 2)
+                / (space + spacing);
+            for _ in 0..spaces {
+                text.push(' ');
+            }
+        }
+        let best = glyphs
+            .iter()
+            .map(|(cp, glyph, lead)| {
+                let tally = compare(bmap, glyph, x - lead, y);
+                (tally, *cp, *glyph, *lead)
+            })
+            .filter(|(tally, ..)| tally.quality() > 0)
+            .max_by(|a, b| {
+                a.0.quality().cmp(&b.0.quality()).then(b.1.cmp(&a.1))
+            });
+        match best {
+            Some((tally, cp, glyph, lead)) => {
+                text.push(char::from_u32(cp.into()).unwrap_or('\u{FFFD}'));
+                hits += tally.hits;
+                extra += tally.extra;
+                x = (x - lead + i32::from(glyph.width())).max(x + 1);
+            }
+            None => {
+                text.push('\u{FFFD}');
+                while x < width && !blank_col(bmap, x, y, font.height()) {
+                    x += 1;
+                }
+            }
+        }
+    }
+    (text, hits, extra)
+}
+
+/// Recognize the text of a bitmap, and the font used to render it
+///
+/// Each candidate font is tried at every vertical position which covers
+/// the lit pixels, and the best match is returned.  Returns `None` if the
+/// bitmap is blank or there are no candidate fonts.
+pub fn recognize<'a>(
+    bmap: &Bitmap,
+    fonts: impl IntoIterator<Item = &'a Font>,
+) -> Option<Recognized<'a>> {
+    let bounds = bmap.ink_bounds()?;
+    let ink = (0..bmap.height())
+        .flat_map(|y| (0..bmap.width()).map(move |x| (x, y)))
+        .filter(|(x, y)| bmap.pixel(*x, *y))
+        .count() as f32;
+    let top = i32::from(bounds.y);
+    let bottom = top + i32::from(bounds.height);
+    let mut best: Option<Recognized<'a>> = None;
+    for font in fonts {
+        let first = (bottom - i32::from(font.height())).min(top);
+        for y in first..=top {
+            let (text, hits, extra) = read_line(bmap, font, y);
+            let score = hits as f32 / (ink + extra as f32);
+            if best.as_ref().is_none_or(|b| score > b.score) {
+                best = Some(Recognized { font, text, score });
+            }
+        }
+    }
+    best
+}
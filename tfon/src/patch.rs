@@ -0,0 +1,133 @@
+//! Glyph-level font patches
+//!
+//! A patch holds the header of the new font, plus only the glyphs which
+//! were added, changed or removed.  Patches are written in `tfon` format,
+//! with a `remove: <code point>` line for each removed glyph:
+//! ```text
+//! font_name: tfon example
+//! font_number: 2
+//! char_spacing: 1
+//! line_spacing: 3
+//! remove: 36
+//!
+//! ch: 65 A
+//! .@@@@.
+//! ...
+//! ```
+use crate::common::{Bitmap, Error, Prop, Result, WriteOptions};
+use crate::tfon;
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::str::FromStr;
+
+/// Changes between two versions of a font
+#[derive(Clone, Default)]
+pub struct Patch {
+    /// Font name
+    pub(crate) name: String,
+    /// Font number
+    pub(crate) number: u8,
+    /// Font width (0 for proportional)
+    pub(crate) width: u8,
+    /// Pixel spacing between characters
+    pub(crate) char_spacing: u8,
+    /// Pixel spacing between lines
+    pub(crate) line_spacing: u8,
+    /// Baseline (rows above)
+    pub(crate) baseline: Option<u8>,
+    /// Changed glyphs (`None` when removed)
+    pub(crate) glyphs: BTreeMap<u16, Option<Bitmap>>,
+}
+
+impl Patch {
+    /// Parse a patch in `tfon` format
+    pub fn parse(buf: &str) -> Result<Self> {
+        let mut patch = Patch {
+            number: 1,
+            ..Default::default()
+        };
+        let mut name = None;
+        let mut cp = None;
+        for prop in tfon::Parser::new(buf) {
+            match prop {
+                Prop::FontName(nm) => name = Some(nm.to_string()),
+                Prop::FontNumber(num) => patch.number = num,
+                Prop::FontWidth(fw) => patch.width = fw,
+                Prop::CharSpacing(cs) => patch.char_spacing = cs,
+                Prop::LineSpacing(ls) => patch.line_spacing = ls,
+                Prop::Baseline(bl) => patch.baseline = Some(bl),
+                Prop::CodePoint(c) => cp = Some(c),
+                Prop::Bitmap(bmap) => {
+                    let c = cp.take().ok_or(Error::Expected("ch"))?;
+                    patch.glyphs.insert(c, Some(bmap));
+                }
+                Prop::Unknown(line) => {
+                    if let Some(val) = line.strip_prefix("remove: ") {
+                        let c = u16::from_str(val.trim())
+                            .map_err(|_| Error::Expected("remove"))?;
+                        patch.glyphs.insert(c, None);
+                    }
+                }
+                Prop::Skipped(_) => return Err(Error::Expected("ch")),
+                _ => (),
+            }
+        }
+        patch.name = name.ok_or(Error::Expected("font_name"))?;
+        Ok(patch)
+    }
+
+    /// Write a patch in `tfon` format
+    pub fn write<W: Write>(&self, writer: W) -> Result<()> {
+        let removed: Vec<String> = self
+            .glyphs
+            .iter()
+            .filter(|(_cp, bmap)| bmap.is_none())
+            .map(|(cp, _bmap)| format!("remove: {cp}"))
+            .collect();
+        let props = [
+            Prop::FontName(self.name.as_str().into()),
+            Prop::FontNumber(self.number),
+            Prop::CharSpacing(self.char_spacing),
+            Prop::LineSpacing(self.line_spacing),
+        ]
+        .into_iter()
+        .chain((self.width > 0).then_some(Prop::FontWidth(self.width)))
+        .chain(self.baseline.map(Prop::Baseline))
+        .chain(removed.iter().map(|line| Prop::Unknown(line)))
+        .chain(self.glyphs.iter().flat_map(|(cp, bmap)| {
+            bmap.iter().flat_map(|bmap| {
+                [Prop::CodePoint(*cp), Prop::Bitmap(bmap.clone())]
+            })
+        }));
+        let options = WriteOptions {
+            keep_unknown: true,
+            ..Default::default()
+        };
+        tfon::write_with(writer, props, &options)?;
+        Ok(())
+    }
+
+    /// Get number of changed glyphs
+    pub fn len(&self) -> usize {
+        self.glyphs.len()
+    }
+
+    /// Check if no glyphs were changed
+    pub fn is_empty(&self) -> bool {
+        self.glyphs.is_empty()
+    }
+
+    /// Get code points of changed glyphs
+    pub fn changed(&self) -> impl Iterator<Item = u16> + '_ {
+        self.glyphs
+            .iter()
+            .filter_map(|(cp, b)| b.as_ref().map(|_| *cp))
+    }
+
+    /// Get code points of removed glyphs
+    pub fn removed(&self) -> impl Iterator<Item = u16> + '_ {
+        self.glyphs
+            .iter()
+            .filter_map(|(cp, b)| b.is_none().then_some(*cp))
+    }
+}
@@ -0,0 +1,123 @@
+//! Vector export for pen plotters and engravers
+//!
+//! Each horizontal run of pixels becomes one stroke, through the centers of
+//! its pixels.  With a pen (or tool) as wide as a pixel, strokes cover the
+//! whole bitmap, for making stencils or engraved signs from the same fonts
+//! used on displays.
+//!
+//! Strokes can be written as HPGL or as simple G-code.
+use crate::common::{Bitmap, Result};
+use std::io::Write;
+
+/// Plotter language
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Dialect {
+    /// Hewlett-Packard Graphics Language (40 units per mm)
+    #[default]
+    Hpgl,
+    /// G-code, in mm, with Z moves to lift and lower the tool
+    Gcode,
+}
+
+/// Straight stroke, in pixels from the bottom left corner
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Stroke {
+    /// Start point (x, y)
+    pub start: (f32, f32),
+    /// End point (x, y)
+    pub end: (f32, f32),
+}
+
+/// Options for plotting
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PlotOptions {
+    /// Plotter language
+    pub dialect: Dialect,
+    /// Size of each pixel (mm)
+    pub pitch: f32,
+    /// Feed rate for drawing (mm per minute, G-code only)
+    pub feed: u32,
+    /// Tool height when lifted (mm, G-code only)
+    pub lift: f32,
+}
+
+impl Default for PlotOptions {
+    fn default() -> Self {
+        PlotOptions {
+            dialect: Dialect::Hpgl,
+            pitch: 1.0,
+            feed: 600,
+            lift: 2.0,
+        }
+    }
+}
+
+/// Get strokes for each horizontal run of pixels
+///
+/// Rows are plotted from top to bottom, alternating direction to reduce
+/// pen travel.  A single pixel is a stroke with the same start and end.
+pub fn strokes(bmap: &Bitmap) -> Vec<Stroke> {
+    let mut strokes = Vec::new();
+    for row in 0..bmap.height() {
+        let y = f32::from(bmap.height() - row) - 0.5;
+        let mut runs = Vec::new();
+        let mut start = None;
+        for col in 0..=bmap.width() {
+            let on = col < bmap.width() && bmap.pixel(col, row);
+            match (on, start) {
+                (true, None) => start = Some(col),
+                (false, Some(x)) => {
+                    runs.push(Stroke {
+                        start: (f32::from(x) + 0.5, y),
+                        end: (f32::from(col) - 0.5, y),
+                    });
+                    start = None;
+                }
+                _ => (),
+            }
+        }
+        if row % 2 == 1 {
+            runs.reverse();
+            for run in &mut runs {
+                std::mem::swap(&mut run.start, &mut run.end);
+            }
+        }
+        strokes.extend(runs);
+    }
+    strokes
+}
+
+/// Write strokes of a bitmap for a plotter
+pub fn write<W: Write>(
+    mut writer: W,
+    bmap: &Bitmap,
+    options: &PlotOptions,
+) -> Result<()> {
+    let strokes = strokes(bmap);
+    match options.dialect {
+        Dialect::Hpgl => {
+            // plotter units are 0.025 mm
+            let unit = |v: f32| (v * options.pitch * 40.0).round() as i32;
+            write!(writer, "IN;SP1;")?;
+            for Stroke { start, end } in strokes {
+                write!(writer, "PU{},{};", unit(start.0), unit(start.1))?;
+                write!(writer, "PD{},{};", unit(end.0), unit(end.1))?;
+            }
+            writeln!(writer, "PU;SP0;")?;
+        }
+        Dialect::Gcode => {
+            let mm = |v: f32| v * options.pitch;
+            writeln!(writer, "G21")?;
+            writeln!(writer, "G90")?;
+            writeln!(writer, "G0 Z{:.3}", options.lift)?;
+            for Stroke { start, end } in strokes {
+                writeln!(writer, "G0 X{:.3} Y{:.3}", mm(start.0), mm(start.1))?;
+                writeln!(writer, "G1 Z0 F{}", options.feed)?;
+                writeln!(writer, "G1 X{:.3} Y{:.3}", mm(end.0), mm(end.1))?;
+                writeln!(writer, "G0 Z{:.3}", options.lift)?;
+            }
+            writeln!(writer, "M2")?;
+        }
+    }
+    Ok(())
+}
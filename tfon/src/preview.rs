@@ -0,0 +1,130 @@
+//! Preview images of rendered bitmaps
+//!
+//! Each bitmap pixel is scaled up to a square block of a grayscale image,
+//! which can then be filtered and encoded as PNG.
+use crate::common::{Bitmap, Result};
+use std::io::Write;
+
+/// Encode 8-bit image data as PNG
+///
+/// * `color` Color type of `pixels`, one byte per channel.
+pub(crate) fn write_png<W: Write>(
+    writer: W,
+    width: u32,
+    height: u32,
+    color: png::ColorType,
+    pixels: &[u8],
+) -> Result<()> {
+    let mut enc = png::Encoder::new(writer, width, height);
+    enc.set_color(color);
+    enc.set_depth(png::BitDepth::Eight);
+    let mut writer = enc.write_header().map_err(std::io::Error::from)?;
+    writer
+        .write_image_data(pixels)
+        .map_err(std::io::Error::from)?;
+    Ok(())
+}
+
+/// Grayscale preview image
+#[derive(Clone)]
+pub struct Preview {
+    /// Width in pixels
+    width: u32,
+    /// Height in pixels
+    height: u32,
+    /// Pixel values (0 off, 255 on)
+    pixels: Vec<u8>,
+}
+
+impl Preview {
+    /// Create a preview of a bitmap
+    ///
+    /// * `bitmap` Bitmap to preview.
+    /// * `scale` Size of each bitmap pixel in the image.
+    pub fn new(bitmap: &Bitmap, scale: u8) -> Self {
+        let scale = u32::from(scale.max(1));
+        let width = u32::from(bitmap.width()) * scale;
+        let height = u32::from(bitmap.height()) * scale;
+        let mut pixels = Vec::with_capacity((width * height) as usize);
+        for y in 0..height {
+            for x in 0..width {
+                let on = bitmap.pixel((x / scale) as u8, (y / scale) as u8);
+                pixels.push(if on { 255 } else { 0 });
+            }
+        }
+        Preview {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    /// Get image width
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Get image height
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Get grayscale pixel values
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    /// Get one pixel value, or 0 when out of bounds
+    fn pixel(&self, x: i64, y: i64) -> u8 {
+        if x < 0 || y < 0 || x >= i64::from(self.width) {
+            return 0;
+        }
+        let pos = y as usize * self.width as usize + x as usize;
+        self.pixels.get(pos).copied().unwrap_or(0)
+    }
+
+    /// Apply a filter over a square neighborhood of every pixel
+    fn filter(&mut self, radius: u32, f: impl Fn(&[u8]) -> u8) {
+        let r = i64::from(radius);
+        let mut pixels = Vec::with_capacity(self.pixels.len());
+        let mut area = Vec::new();
+        for y in 0..i64::from(self.height) {
+            for x in 0..i64::from(self.width) {
+                area.clear();
+                for ny in y - r..=y + r {
+                    for nx in x - r..=x + r {
+                        area.push(self.pixel(nx, ny));
+                    }
+                }
+                pixels.push(f(&area));
+            }
+        }
+        self.pixels = pixels;
+    }
+
+    /// Apply an LED bloom (halation) filter
+    ///
+    /// Lit pixels are dilated, then blurred, by `radius` image pixels.
+    /// This approximates how LEDs bleed into neighboring dots.
+    pub fn bloom(mut self, radius: u32) -> Self {
+        if radius > 0 {
+            self.filter(radius, |a| a.iter().copied().max().unwrap_or(0));
+            self.filter(radius, |a| {
+                let sum: u32 = a.iter().map(|v| u32::from(*v)).sum();
+                (sum / a.len() as u32) as u8
+            });
+        }
+        self
+    }
+
+    /// Write preview image in PNG format
+    pub fn write_png<W: Write>(&self, writer: W) -> Result<()> {
+        write_png(
+            writer,
+            self.width,
+            self.height,
+            png::ColorType::Grayscale,
+            &self.pixels,
+        )
+    }
+}
@@ -0,0 +1,226 @@
+//! Font legibility heuristics
+//!
+//! These heuristics give a rough way to compare candidate fonts.  Each
+//! measure ranges from 0 (worst) to 1 (best).
+use crate::common::Bitmap;
+use crate::font::Font;
+
+/// Commonly confused character pairs
+pub const CONFUSABLE: &[(char, char)] = &[
+    ('0', 'O'),
+    ('0', 'D'),
+    ('1', 'I'),
+    ('1', 'l'),
+    ('I', 'l'),
+    ('2', 'Z'),
+    ('5', 'S'),
+    ('6', 'G'),
+    ('8', 'B'),
+    ('M', 'N'),
+    ('U', 'V'),
+];
+
+/// Font quality report
+#[derive(Clone, Debug, PartialEq)]
+pub struct Report {
+    /// Stroke width consistency
+    ///
+    /// Fraction of lit pixels with the most common stroke width.
+    pub stroke_consistency: f32,
+    /// Counter openness
+    ///
+    /// Fraction of enclosed counters at least 2 pixels wide and high.
+    pub counter_openness: f32,
+    /// Character distinctness
+    ///
+    /// One minus the mean similarity of confusable pairs.
+    pub distinctness: f32,
+    /// Similarity of each confusable pair present in the font
+    pub confusables: Vec<(char, char, f32)>,
+    /// Overall score (mean of all measures)
+    pub score: f32,
+}
+
+/// Score the legibility of a font
+pub fn score(font: &Font) -> Report {
+    let stroke_consistency = stroke_consistency(font);
+    let counter_openness = counter_openness(font);
+    let confusables: Vec<_> = CONFUSABLE
+        .iter()
+        .filter_map(|(a, b)| {
+            let ga = font.glyph_char(*a)?;
+            let gb = font.glyph_char(*b)?;
+            Some((*a, *b, similarity(ga, gb)))
+        })
+        .collect();
+    let distinctness = if confusables.is_empty() {
+        1.0
+    } else {
+        let sum: f32 = confusables.iter().map(|(_, _, s)| s).sum();
+        1.0 - sum / confusables.len() as f32
+    };
+    let score = (stroke_consistency + counter_openness + distinctness) / 3.0;
+    Report {
+        stroke_consistency,
+        counter_openness,
+        distinctness,
+        confusables,
+        score,
+    }
+}
+
+/// Calculate the visual similarity of two bitmaps
+///
+/// Bitmaps are aligned at the top and centered horizontally.  The result
+/// is the number of lit pixels in both, divided by the number lit in
+/// either.
+pub fn similarity(a: &Bitmap, b: &Bitmap) -> f32 {
+    let height = a.height().max(b.height());
+    let width = a.width().max(b.width());
+    let ax = (width - a.width()) / 2;
+    let bx = (width - b.width()) / 2;
+    let mut both = 0;
+    let mut either = 0;
+    for y in 0..height {
+        for x in 0..width {
+            let pa = lit(a, i16::from(x) - i16::from(ax), i16::from(y));
+            let pb = lit(b, i16::from(x) - i16::from(bx), i16::from(y));
+            if pa && pb {
+                both += 1;
+            }
+            if pa || pb {
+                either += 1;
+            }
+        }
+    }
+    if either > 0 {
+        both as f32 / either as f32
+    } else {
+        1.0
+    }
+}
+
+/// Check if a pixel is lit, treating out of bounds pixels as off
+fn lit(bmap: &Bitmap, x: i16, y: i16) -> bool {
+    match (u8::try_from(x), u8::try_from(y)) {
+        (Ok(x), Ok(y)) if x < bmap.width() && y < bmap.height() => {
+            bmap.pixel(x, y)
+        }
+        _ => false,
+    }
+}
+
+/// Get length of the run of lit pixels through a pixel
+fn run_len(bmap: &Bitmap, x: u8, y: u8, dx: i16, dy: i16) -> u8 {
+    let mut len = 1;
+    for dir in [-1, 1] {
+        let (mut px, mut py) = (i16::from(x), i16::from(y));
+        loop {
+            px += dx * dir;
+            py += dy * dir;
+            if !lit(bmap, px, py) {
+                break;
+            }
+            len += 1;
+        }
+    }
+    len
+}
+
+/// Calculate stroke width consistency
+///
+/// The stroke width at a lit pixel is the shorter of its horizontal and
+/// vertical runs.
+fn stroke_consistency(font: &Font) -> f32 {
+    let mut counts = [0u32; 256];
+    for (_cp, bmap) in font.glyphs() {
+        for y in 0..bmap.height() {
+            for x in 0..bmap.width() {
+                if bmap.pixel(x, y) {
+                    let w = run_len(bmap, x, y, 1, 0)
+                        .min(run_len(bmap, x, y, 0, 1));
+                    counts[usize::from(w)] += 1;
+                }
+            }
+        }
+    }
+    let total: u32 = counts.iter().sum();
+    let mode = counts.iter().max().copied().unwrap_or(0);
+    if total > 0 {
+        mode as f32 / total as f32
+    } else {
+        1.0
+    }
+}
+
+/// Get bounding boxes (width, height) of enclosed counters in a bitmap
+fn counters(bmap: &Bitmap) -> Vec<(u8, u8)> {
+    let (width, height) = (bmap.width(), bmap.height());
+    let mut seen = vec![false; usize::from(width) * usize::from(height)];
+    let mut found = Vec::new();
+    for y in 0..height {
+        for x in 0..width {
+            let pos = usize::from(y) * usize::from(width) + usize::from(x);
+            if seen[pos] || bmap.pixel(x, y) {
+                continue;
+            }
+            // flood fill one region of unlit pixels (4-connected)
+            seen[pos] = true;
+            let mut stack = vec![(x, y)];
+            let (mut x0, mut y0, mut x1, mut y1) = (x, y, x, y);
+            let mut enclosed = true;
+            while let Some((px, py)) = stack.pop() {
+                x0 = x0.min(px);
+                y0 = y0.min(py);
+                x1 = x1.max(px);
+                y1 = y1.max(py);
+                if px == 0 || py == 0 || px + 1 == width || py + 1 == height {
+                    enclosed = false;
+                }
+                let neighbors = [
+                    (px.checked_sub(1), Some(py)),
+                    (px.checked_add(1), Some(py)),
+                    (Some(px), py.checked_sub(1)),
+                    (Some(px), py.checked_add(1)),
+                ];
+                for (nx, ny) in neighbors {
+                    let (Some(nx), Some(ny)) = (nx, ny) else {
+                        continue;
+                    };
+                    if nx >= width || ny >= height {
+                        continue;
+                    }
+                    let npos =
+                        usize::from(ny) * usize::from(width) + usize::from(nx);
+                    if !seen[npos] && !bmap.pixel(nx, ny) {
+                        seen[npos] = true;
+                        stack.push((nx, ny));
+                    }
+                }
+            }
+            if enclosed {
+                found.push((x1 - x0 + 1, y1 - y0 + 1));
+            }
+        }
+    }
+    found
+}
+
+/// Calculate counter openness
+fn counter_openness(font: &Font) -> f32 {
+    let mut total = 0;
+    let mut open = 0;
+    for (_cp, bmap) in font.glyphs() {
+        for (w, h) in counters(bmap) {
+            total += 1;
+            if w >= 2 && h >= 2 {
+                open += 1;
+            }
+        }
+    }
+    if total > 0 {
+        open as f32 / total as f32
+    } else {
+        1.0
+    }
+}
@@ -0,0 +1,29 @@
+//! Quick one-call helpers
+//!
+//! For tooling which just wants a result, without choosing a format,
+//! building a `Font` or setting up an encoder.
+use crate::common::{Error, Result};
+use crate::format::Format;
+use crate::preview::Preview;
+use crate::render;
+
+/// Render text with a font, encoded as a PNG image
+///
+/// The font format is detected from its contents.  With the `gzip` feature,
+/// compressed fonts are decompressed.
+///
+/// * `font` Font source, in any readable format.
+/// * `text` Text to render.
+/// * `scale` Size of each font pixel in the image.
+pub fn text_png(font: &[u8], text: &str, scale: u8) -> Result<Vec<u8>> {
+    #[cfg(feature = "gzip")]
+    let font = &crate::gzip::decode(font.to_vec())?;
+    let src = std::str::from_utf8(font)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+    let format = Format::detect(src).ok_or(Error::UnknownFormat())?;
+    let font = format.load(src)?;
+    let bitmap = render::text(&font, text)?;
+    let mut png = Vec::new();
+    Preview::new(&bitmap, scale).write_png(&mut png)?;
+    Ok(png)
+}
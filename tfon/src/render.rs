@@ -0,0 +1,405 @@
+//! Render text using a font
+//!
+//! Text is rendered on a single line, with glyphs separated by the font's
+//! character spacing.  Character sequences with a ligature in the font are
+//! replaced by the ligature glyph.
+use crate::common::{Bitmap, Error, Result};
+use crate::font::Font;
+
+/// Look up the glyph for a character
+fn glyph(font: &Font, c: char) -> Result<&Bitmap> {
+    font.glyph_char(c).ok_or(Error::MissingGlyph(c))
+}
+
+/// Look up glyphs for text, substituting ligatures
+fn glyphs<'f>(font: &'f Font, text: &str) -> Result<Vec<&'f Bitmap>> {
+    Ok(glyph_ends(font, text)?
+        .into_iter()
+        .map(|(_end, gl)| gl)
+        .collect())
+}
+
+/// Look up glyphs for text, with the end of each glyph's characters
+fn glyph_ends<'f>(
+    font: &'f Font,
+    text: &str,
+) -> Result<Vec<(usize, &'f Bitmap)>> {
+    let mut glyphs = Vec::new();
+    let mut end = 0;
+    while let Some(c) = text[end..].chars().next() {
+        match font.ligature_at(&text[end..]) {
+            Some((len, gl)) => {
+                end += len;
+                glyphs.push((end, gl));
+            }
+            None => {
+                end += c.len_utf8();
+                glyphs.push((end, glyph(font, c)?));
+            }
+        }
+    }
+    Ok(glyphs)
+}
+
+/// Measure the size of rendered text
+///
+/// Only glyph widths and character spacing are used, so no bitmap is
+/// composed.  Returns `(width, height)` in pixels.
+pub fn measure(font: &Font, text: &str) -> Result<(u32, u32)> {
+    let width = measure_width(font, text, font.char_spacing())?;
+    Ok((width, u32::from(font.height())))
+}
+
+/// Measure the width of rendered text with a given character spacing
+fn measure_width(font: &Font, text: &str, spacing: u8) -> Result<u32> {
+    let mut width = 0;
+    for (i, gl) in glyphs(font, text)?.into_iter().enumerate() {
+        if i > 0 {
+            width += u32::from(spacing);
+        }
+        width += u32::from(gl.width());
+    }
+    Ok(width)
+}
+
+/// Render text to a bitmap
+#[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+pub fn text(font: &Font, text: &str) -> Result<Bitmap> {
+    let (width, height) = measure(font, text)?;
+    let width = u8::try_from(width).map_err(|_| Error::TooLarge())?;
+    let height = u8::try_from(height).map_err(|_| Error::TooLarge())?;
+    let mut bitmap = Bitmap::new_blank(height, width);
+    blit_text(font, text, &mut bitmap, 0, 0)?;
+    Ok(bitmap)
+}
+
+/// Destination for rendered pixels
+///
+/// This can be implemented for any framebuffer, so that text is drawn
+/// without allocating an intermediate bitmap.  Pixels may be outside the
+/// framebuffer, and should be ignored in that case.
+pub trait PixelSink {
+    /// Set one pixel on or off
+    fn set(&mut self, x: u32, y: u32, on: bool);
+}
+
+impl PixelSink for Bitmap {
+    fn set(&mut self, x: u32, y: u32, on: bool) {
+        if let (Ok(x), Ok(y)) = (u8::try_from(x), u8::try_from(y)) {
+            if x < self.width() && y < self.height() {
+                self.set_pixel(x, y, on);
+            }
+        }
+    }
+}
+
+impl<F: FnMut(u32, u32, bool)> PixelSink for F {
+    fn set(&mut self, x: u32, y: u32, on: bool) {
+        self(x, y, on)
+    }
+}
+
+/// Draw a bitmap into a pixel sink
+///
+/// Only on pixels are drawn, so the background shows through.
+///
+/// * `x` / `y` Position of top left pixel.
+pub fn blit(src: &Bitmap, sink: &mut impl PixelSink, x: u32, y: u32) {
+    draw(src, sink, x, y, false);
+}
+
+/// Draw a bitmap into a pixel sink, including off pixels
+///
+/// * `x` / `y` Position of top left pixel.
+pub fn blit_opaque(src: &Bitmap, sink: &mut impl PixelSink, x: u32, y: u32) {
+    draw(src, sink, x, y, true);
+}
+
+/// Draw a bitmap, with or without off pixels
+///
+/// Pixels past `u32::MAX` are clipped.
+fn draw(src: &Bitmap, sink: &mut impl PixelSink, x: u32, y: u32, opaque: bool) {
+    for sy in 0..src.height() {
+        let Some(py) = y.checked_add(u32::from(sy)) else {
+            return;
+        };
+        for sx in 0..src.width() {
+            let Some(px) = x.checked_add(u32::from(sx)) else {
+                break;
+            };
+            let on = src.pixel(sx, sy);
+            if on || opaque {
+                sink.set(px, py, on);
+            }
+        }
+    }
+}
+
+/// Draw text into a pixel sink
+///
+/// Only on pixels are drawn, as with [blit].
+///
+/// * `x` / `y` Position of top left pixel.
+///
+/// Returns the width of the text, in pixels.
+pub fn blit_text(
+    font: &Font,
+    text: &str,
+    sink: &mut impl PixelSink,
+    x: u32,
+    y: u32,
+) -> Result<u32> {
+    let spacing = u32::from(font.char_spacing());
+    let mut width = 0;
+    for (i, gl) in glyphs(font, text)?.into_iter().enumerate() {
+        if i > 0 {
+            width += spacing;
+        }
+        if let Some(gx) = x.checked_add(width) {
+            blit(gl, sink, gx, y);
+        }
+        width += u32::from(gl.width());
+    }
+    Ok(width)
+}
+
+/// Policy for truncating text which does not fit
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TruncatePolicy {
+    /// Drop characters from the end
+    Clip,
+    /// Drop characters from the end, appending an ellipsis
+    Ellipsis,
+    /// Drop whole words from the end
+    Word,
+}
+
+/// Fit text into a pixel width
+///
+/// If the text is too wide, it is truncated according to the policy.
+/// Returns the fitted text and its measured width.
+#[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+pub fn fit(
+    font: &Font,
+    text: &str,
+    max_width: u32,
+    policy: TruncatePolicy,
+) -> Result<(String, u32)> {
+    let (width, _height) = measure(font, text)?;
+    if width <= max_width {
+        return Ok((text.to_string(), width));
+    }
+    match policy {
+        TruncatePolicy::Clip => clip(font, text, "", max_width),
+        TruncatePolicy::Ellipsis => {
+            let ellipsis = if font.glyph_char('…').is_some() {
+                "…"
+            } else if font.glyph_char('.').is_some() {
+                "..."
+            } else {
+                ""
+            };
+            clip(font, text, ellipsis, max_width)
+        }
+        TruncatePolicy::Word => {
+            let mut best = None;
+            for (i, c) in text.char_indices() {
+                if c.is_whitespace() {
+                    let prefix = text[..i].trim_end();
+                    let (width, _height) = measure(font, prefix)?;
+                    if width > max_width {
+                        break;
+                    }
+                    if !prefix.is_empty() {
+                        best = Some((prefix.to_string(), width));
+                    }
+                }
+            }
+            match best {
+                Some(best) => Ok(best),
+                None => clip(font, text, "", max_width),
+            }
+        }
+    }
+}
+
+/// Clip text to the longest prefix which fits with a suffix appended
+///
+/// Prefixes end between glyphs (not within a ligature), and are measured
+/// with the suffix, as by [measure].
+fn clip(
+    font: &Font,
+    text: &str,
+    suffix: &str,
+    max_width: u32,
+) -> Result<(String, u32)> {
+    let mut best = (String::new(), 0);
+    for (end, _gl) in glyph_ends(font, text)? {
+        let fitted = format!("{}{suffix}", &text[..end]);
+        let (width, _height) = measure(font, &fitted)?;
+        if width > max_width {
+            break;
+        }
+        best = (fitted, width);
+    }
+    Ok(best)
+}
+
+/// Span of text rendered with one font
+#[derive(Clone, Copy)]
+pub struct Span<'a> {
+    /// Font for span
+    pub font: &'a Font,
+    /// Text of span
+    pub text: &'a str,
+    /// Character spacing override
+    pub char_spacing: Option<u8>,
+}
+
+impl<'a> Span<'a> {
+    /// Create a new text span
+    pub fn new(font: &'a Font, text: &'a str) -> Self {
+        Span {
+            font,
+            text,
+            char_spacing: None,
+        }
+    }
+
+    /// Get pixel spacing between characters
+    fn spacing(&self) -> u8 {
+        self.char_spacing.unwrap_or(self.font.char_spacing())
+    }
+}
+
+/// Get pixel spacing between two adjacent spans
+fn span_spacing(prev: &Span, next: &Span) -> u32 {
+    u32::from(prev.spacing().max(next.spacing()))
+}
+
+/// Measure the size of text spans rendered on a shared baseline
+///
+/// Returns `(width, height)` in pixels.
+pub fn measure_spans(spans: &[Span]) -> Result<(u32, u32)> {
+    let mut width = 0;
+    let mut prev: Option<&Span> = None;
+    for span in spans.iter().filter(|s| !s.text.is_empty()) {
+        if let Some(prev) = prev {
+            width += span_spacing(prev, span);
+        }
+        width += measure_width(span.font, span.text, span.spacing())?;
+        prev = Some(span);
+    }
+    let ascent = spans.iter().map(|s| s.font.baseline()).max().unwrap_or(0);
+    let descent = spans
+        .iter()
+        .map(|s| s.font.height() - s.font.baseline())
+        .max()
+        .unwrap_or(0);
+    Ok((width, u32::from(ascent) + u32::from(descent)))
+}
+
+/// Render text spans to a bitmap, aligned on a shared baseline
+#[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+pub fn spans(spans: &[Span]) -> Result<Bitmap> {
+    let (width, height) = measure_spans(spans)?;
+    let width = u8::try_from(width).map_err(|_| Error::TooLarge())?;
+    let height = u8::try_from(height).map_err(|_| Error::TooLarge())?;
+    let ascent = spans.iter().map(|s| s.font.baseline()).max().unwrap_or(0);
+    let mut bitmap = Bitmap::new_blank(height, width);
+    let mut x: u8 = 0;
+    let mut prev: Option<&Span> = None;
+    for span in spans.iter().filter(|s| !s.text.is_empty()) {
+        if let Some(prev) = prev {
+            let spacing = u8::try_from(span_spacing(prev, span)).unwrap_or(0);
+            x = x.saturating_add(spacing);
+        }
+        let y = ascent - span.font.baseline();
+        for (i, gl) in glyphs(span.font, span.text)?.into_iter().enumerate() {
+            if i > 0 {
+                x = x.saturating_add(span.spacing());
+            }
+            blit(gl, &mut bitmap, u32::from(x), u32::from(y));
+            x = x.saturating_add(gl.width());
+        }
+        prev = Some(span);
+    }
+    Ok(bitmap)
+}
+
+/// Simulated LED failures
+///
+/// Failures are chosen pseudo-randomly from a seed, so a given set of
+/// options always disables the same pixels.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Failures {
+    /// Random seed
+    pub seed: u64,
+    /// Percentage of failed pixels (0 to 100)
+    pub pixels: u8,
+    /// Number of failed rows
+    pub rows: u8,
+    /// Number of failed columns
+    pub cols: u8,
+}
+
+/// Pseudo-random number generator (splitmix64)
+struct Rng(u64);
+
+impl Rng {
+    /// Get next random number
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Get a random number less than `n`
+    fn below(&mut self, n: u8) -> u8 {
+        (self.next() % u64::from(n)) as u8
+    }
+}
+
+impl Failures {
+    /// Disable failed pixels of a bitmap
+    pub fn apply(&self, bitmap: &mut Bitmap) {
+        let (height, width) = (bitmap.height(), bitmap.width());
+        if height == 0 || width == 0 {
+            return;
+        }
+        let mut rng = Rng(self.seed);
+        for _ in 0..self.rows {
+            let y = rng.below(height);
+            for x in 0..width {
+                bitmap.set_pixel(x, y, false);
+            }
+        }
+        for _ in 0..self.cols {
+            let x = rng.below(width);
+            for y in 0..height {
+                bitmap.set_pixel(x, y, false);
+            }
+        }
+        let pixels = u64::from(self.pixels.min(100));
+        for y in 0..height {
+            for x in 0..width {
+                if rng.next() % 100 < pixels {
+                    bitmap.set_pixel(x, y, false);
+                }
+            }
+        }
+    }
+}
+
+/// Render text to a bitmap with simulated LED failures
+pub fn text_failures(
+    font: &Font,
+    text: &str,
+    failures: &Failures,
+) -> Result<Bitmap> {
+    let mut bitmap = self::text(font, text)?;
+    failures.apply(&mut bitmap);
+    Ok(bitmap)
+}
@@ -0,0 +1,217 @@
+//! Shared font repository
+//!
+//! A [FontRepo] can be shared between threads (in an `Arc`), with fonts
+//! looked up by name or number.  Fonts loaded from a directory can be
+//! reloaded while other threads are using the repository, either on demand
+//! or automatically when files change.
+use crate::common::Result;
+use crate::family::Family;
+use crate::font::Font;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::thread::JoinHandle;
+use std::time::{Duration, SystemTime};
+
+/// Repository contents
+#[derive(Default)]
+struct Inner {
+    /// Directory to load fonts from
+    dir: Option<PathBuf>,
+    /// Fonts keyed by number
+    fonts: BTreeMap<u8, Arc<Font>>,
+}
+
+/// Thread-safe font repository
+#[derive(Default)]
+pub struct FontRepo {
+    /// Contents, behind a lock
+    inner: RwLock<Inner>,
+}
+
+/// Load all fonts in a directory
+///
+/// If any font cannot be loaded, the first error is returned.
+fn load_fonts(dir: &Path) -> Result<BTreeMap<u8, Arc<Font>>> {
+    let (fonts, errors) = crate::load_dir(dir);
+    if let Some(err) = errors.into_iter().next() {
+        return Err(err.error);
+    }
+    Ok(fonts
+        .into_iter()
+        .map(|font| (font.number(), Arc::new(font)))
+        .collect())
+}
+
+/// File-watching thread for a [FontRepo]
+///
+/// The thread is stopped when this handle is dropped.
+pub struct Watcher {
+    /// Flag to stop thread
+    stop: Arc<AtomicBool>,
+    /// Thread handle
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Drop for Watcher {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            thread.thread().unpark();
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Snapshot of files in a directory (path, modified time, length)
+fn snapshot(dir: &Path) -> Vec<(PathBuf, Option<SystemTime>, u64)> {
+    let mut paths = Vec::new();
+    crate::load::walk(dir, &mut paths, &mut Vec::new());
+    paths
+        .into_iter()
+        .map(|path| {
+            let meta = std::fs::metadata(&path).ok();
+            let modified = meta.as_ref().and_then(|m| m.modified().ok());
+            let len = meta.map_or(0, |m| m.len());
+            (path, modified, len)
+        })
+        .collect()
+}
+
+impl FontRepo {
+    /// Create an empty repository
+    pub fn new() -> Self {
+        FontRepo::default()
+    }
+
+    /// Create a repository from all fonts in a directory
+    ///
+    /// Fonts are loaded with [load_dir](crate::load_dir), in order by path.
+    /// If two fonts have the same number, the last one is kept.
+    pub fn load_dir(dir: impl AsRef<Path>) -> Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        let fonts = load_fonts(&dir)?;
+        let inner = Inner {
+            dir: Some(dir),
+            fonts,
+        };
+        Ok(FontRepo {
+            inner: RwLock::new(inner),
+        })
+    }
+
+    /// Lock for reading
+    fn read(&self) -> RwLockReadGuard<'_, Inner> {
+        self.inner.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Lock for writing
+    fn write(&self) -> RwLockWriteGuard<'_, Inner> {
+        self.inner.write().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Reload fonts from the directory
+    ///
+    /// All fonts are replaced at once; if any font cannot be loaded, the
+    /// repository is unchanged.  Returns the number of fonts loaded.
+    pub fn reload(&self) -> Result<usize> {
+        let Some(dir) = self.read().dir.clone() else {
+            return Ok(0);
+        };
+        let fonts = load_fonts(&dir)?;
+        let len = fonts.len();
+        self.write().fonts = fonts;
+        Ok(len)
+    }
+
+    /// Watch the directory, reloading when files change
+    ///
+    /// Files are checked every `interval`.  After each reload, `notify` is
+    /// called with the result; it could send on a channel, for example.
+    /// The thread stops when the [Watcher] is dropped, or when the
+    /// repository is no longer in use.
+    pub fn watch<F>(
+        self: &Arc<Self>,
+        interval: Duration,
+        mut notify: F,
+    ) -> Watcher
+    where
+        F: FnMut(Result<usize>) + Send + 'static,
+    {
+        let stop = Arc::new(AtomicBool::new(false));
+        let repo = Arc::downgrade(self);
+        let dir = self.read().dir.clone();
+        let flag = Arc::clone(&stop);
+        let thread = std::thread::spawn(move || {
+            let Some(dir) = dir else {
+                return;
+            };
+            let mut files = snapshot(&dir);
+            while !flag.load(Ordering::Relaxed) {
+                std::thread::park_timeout(interval);
+                let Some(repo) = repo.upgrade() else {
+                    break;
+                };
+                let current = snapshot(&dir);
+                if current != files && !flag.load(Ordering::Relaxed) {
+                    files = current;
+                    notify(repo.reload());
+                }
+            }
+        });
+        Watcher {
+            stop,
+            thread: Some(thread),
+        }
+    }
+
+    /// Insert a font, replacing any with the same number
+    pub fn insert(&self, font: Font) -> Option<Arc<Font>> {
+        self.write().fonts.insert(font.number(), Arc::new(font))
+    }
+
+    /// Remove a font by number
+    pub fn remove(&self, number: u8) -> Option<Arc<Font>> {
+        self.write().fonts.remove(&number)
+    }
+
+    /// Look up a font by number
+    pub fn by_number(&self, number: u8) -> Option<Arc<Font>> {
+        self.read().fonts.get(&number).cloned()
+    }
+
+    /// Look up a font by name
+    pub fn by_name(&self, name: &str) -> Option<Arc<Font>> {
+        self.read()
+            .fonts
+            .values()
+            .find(|font| font.name() == name)
+            .cloned()
+    }
+
+    /// Get all fonts, ordered by number
+    pub fn fonts(&self) -> Vec<Arc<Font>> {
+        self.read().fonts.values().cloned().collect()
+    }
+
+    /// Get all fonts, grouped into families
+    pub fn families(&self) -> Vec<Family> {
+        Family::group(self.fonts())
+    }
+
+    /// Look up a family by name
+    pub fn family(&self, name: &str) -> Option<Family> {
+        self.families().into_iter().find(|fam| fam.name() == name)
+    }
+
+    /// Get the number of fonts
+    pub fn len(&self) -> usize {
+        self.read().fonts.len()
+    }
+
+    /// Check if the repository is empty
+    pub fn is_empty(&self) -> bool {
+        self.read().fonts.is_empty()
+    }
+}
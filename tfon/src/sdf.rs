@@ -0,0 +1,104 @@
+//! Signed distance fields
+//!
+//! A distance field stores, for each pixel, the distance to the nearest
+//! glyph edge: above 128 inside, below 128 outside.  Rendering with a
+//! threshold allows glyphs to be scaled smoothly.
+use crate::common::Bitmap;
+#[cfg(feature = "png")]
+use crate::common::Result;
+#[cfg(feature = "png")]
+use std::io::Write;
+
+/// Signed distance field for one glyph
+#[derive(Clone)]
+pub struct Sdf {
+    /// Field width
+    width: u16,
+    /// Field height
+    height: u16,
+    /// Distance (pixels) mapped to full range
+    spread: u8,
+    /// Distance values (128 at edge)
+    values: Vec<u8>,
+}
+
+impl Sdf {
+    /// Generate a distance field from a bitmap
+    ///
+    /// * `spread` Maximum distance (pixels); the field is padded by this
+    ///   amount on each side.
+    pub fn new(bmap: &Bitmap, spread: u8) -> Self {
+        let spread = spread.max(1);
+        let s = i32::from(spread);
+        let width = u16::from(bmap.width()) + 2 * u16::from(spread);
+        let height = u16::from(bmap.height()) + 2 * u16::from(spread);
+        // pixel in field coordinates (blank outside bitmap)
+        let pixel = |x: i32, y: i32| {
+            let (bx, by) = (x - s, y - s);
+            match (u8::try_from(bx), u8::try_from(by)) {
+                (Ok(bx), Ok(by)) => bmap.pixel(bx, by),
+                _ => false,
+            }
+        };
+        let mut values =
+            Vec::with_capacity(usize::from(width) * usize::from(height));
+        for y in 0..i32::from(height) {
+            for x in 0..i32::from(width) {
+                let inside = pixel(x, y);
+                // squared distance to nearest pixel of opposite state
+                let mut near = s * s;
+                for dy in -s..=s {
+                    for dx in -s..=s {
+                        let d = dx * dx + dy * dy;
+                        if d < near && pixel(x + dx, y + dy) != inside {
+                            near = d;
+                        }
+                    }
+                }
+                // edge is half way between pixel centers
+                let dist = ((near as f32).sqrt() - 0.5).max(0.0);
+                let dist = if inside { dist } else { -dist };
+                let val = 128.0 + dist * 127.0 / f32::from(spread);
+                values.push(val.round().clamp(0.0, 255.0) as u8);
+            }
+        }
+        Sdf {
+            width,
+            height,
+            spread,
+            values,
+        }
+    }
+
+    /// Get field width
+    pub fn width(&self) -> u16 {
+        self.width
+    }
+
+    /// Get field height
+    pub fn height(&self) -> u16 {
+        self.height
+    }
+
+    /// Get spread (padding on each side)
+    pub fn spread(&self) -> u8 {
+        self.spread
+    }
+
+    /// Get distance values, row by row
+    pub fn values(&self) -> &[u8] {
+        &self.values
+    }
+
+    /// Write distance field in PNG format (grayscale)
+    #[cfg(feature = "png")]
+    pub fn write_png<W: Write>(&self, writer: W) -> Result<()> {
+        crate::preview::write_png(
+            writer,
+            u32::from(self.width),
+            u32::from(self.height),
+            png::ColorType::Grayscale,
+            &self.values,
+        )
+    }
+}
@@ -0,0 +1,82 @@
+//! Code point segments
+//!
+//! Code points can be grouped into contiguous ranges, like the segments of
+//! a TrueType `cmap` format 4 table.  The [ctable](crate::ctable) export
+//! has a range table of segments, so fonts with scattered coverage don't
+//! need entries for missing code points.  The
+//! [StaticFont](crate::StaticFont) layout has no range table, since its
+//! sorted index is searched directly.
+use std::fmt;
+
+/// Contiguous range of code points
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Segment {
+    /// First code point
+    pub start: u16,
+    /// Last code point
+    pub end: u16,
+    /// Table index of first code point
+    pub index: usize,
+}
+
+impl fmt::Display for Segment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.start == self.end {
+            write!(f, "{}", self.start)
+        } else {
+            write!(f, "{}-{}", self.start, self.end)
+        }
+    }
+}
+
+impl Segment {
+    /// Get number of code points in segment
+    pub fn len(&self) -> usize {
+        usize::from(self.end - self.start) + 1
+    }
+
+    /// Check if segment is empty (never true)
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+
+    /// Check if segment contains a code point
+    pub fn contains(&self, cp: u16) -> bool {
+        (self.start..=self.end).contains(&cp)
+    }
+}
+
+/// Group ascending code points into segments
+///
+/// Gaps of up to `max_gap` missing code points are merged into one
+/// segment; a table must then have filler entries for them.
+pub fn segments(
+    code_points: impl IntoIterator<Item = u16>,
+    max_gap: u16,
+) -> Vec<Segment> {
+    let mut segs: Vec<Segment> = Vec::new();
+    let mut index = 0;
+    for cp in code_points {
+        match segs.last_mut() {
+            Some(seg)
+                if cp > seg.end
+                    && u32::from(cp) - u32::from(seg.end)
+                        <= u32::from(max_gap) + 1 =>
+            {
+                index += usize::from(cp - seg.end);
+                seg.end = cp;
+            }
+            _ => {
+                if let Some(seg) = segs.last() {
+                    index = seg.index + seg.len();
+                }
+                segs.push(Segment {
+                    start: cp,
+                    end: cp,
+                    index,
+                });
+            }
+        }
+    }
+    segs
+}
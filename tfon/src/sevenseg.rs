@@ -0,0 +1,197 @@
+//! Segment display masks
+//!
+//! Glyphs can be converted to and from segment masks, so a segment display
+//! and a matrix display can share one font.  Mask bits are:
+//! ```text
+//!   ---a---      0 a   7 g2
+//!  |\  |  /|     1 b   8 h
+//!  f h i j b     2 c   9 i
+//!  |  \|/  |     3 d  10 j
+//!   -g1-g2-      4 e  11 k
+//!  |  /|\  |     5 f  12 l
+//!  e k l m c     6 g  13 m
+//!  |/  |  \|
+//!   ---d---
+//! ```
+//! On seven-segment displays, bit 6 is the whole middle bar (`g`); on
+//! fourteen-segment displays it is the left half (`g1`).
+//!
+//! Segments are drawn one pixel thick, with corners shared between
+//! segments.  When converting a glyph to a mask, a segment is lit if most
+//! of its pixels (excluding corners) are on.
+use crate::common::{Bitmap, Error, Prop, Result};
+use crate::font::Font;
+
+/// Segment display kind
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Display {
+    /// Seven-segment display
+    Seven,
+    /// Fourteen-segment display
+    Fourteen,
+}
+
+/// Seven-segment masks for digits `0` to `9`
+const SEVEN_DIGITS: [u16; 10] =
+    [0x3F, 0x06, 0x5B, 0x4F, 0x66, 0x6D, 0x7D, 0x07, 0x7F, 0x6F];
+
+/// Seven-segment mask for `-`
+const SEVEN_MINUS: u16 = 0x40;
+
+/// Fourteen-segment middle bar (`g1` and `g2`)
+const MIDDLE: u16 = 0xC0;
+
+impl Display {
+    /// Get number of segments
+    pub fn segments(self) -> u8 {
+        match self {
+            Display::Seven => 7,
+            Display::Fourteen => 14,
+        }
+    }
+
+    /// Get preset masks for digits, minus and space
+    pub fn digits(self) -> Vec<(u16, u16)> {
+        let mut masks: Vec<(u16, u16)> = (u16::from(b'0')..)
+            .zip(SEVEN_DIGITS)
+            .map(|(cp, mask)| (cp, self.seven_mask(mask)))
+            .collect();
+        masks.push((u16::from(b' '), 0));
+        masks.push((u16::from(b'-'), self.seven_mask(SEVEN_MINUS)));
+        masks.sort();
+        masks
+    }
+
+    /// Convert a seven-segment mask
+    fn seven_mask(self, mask: u16) -> u16 {
+        match self {
+            Display::Seven => mask,
+            Display::Fourteen if mask & 0x40 != 0 => mask | MIDDLE,
+            Display::Fourteen => mask,
+        }
+    }
+
+    /// Get pixels of each segment in a character cell
+    ///
+    /// The first and last pixels of each segment are corners.
+    fn pixels(self, height: u8, width: u8) -> Result<Vec<Vec<(u8, u8)>>> {
+        if height < 5 || width < 3 {
+            return Err(Error::CellTooSmall(width, height));
+        }
+        let (right, bottom) = (width - 1, height - 1);
+        let mid = bottom / 2;
+        let cx = right / 2;
+        let row = |y: u8, x0: u8, x1: u8| (x0..=x1).map(|x| (x, y)).collect();
+        let col = |x: u8, y0: u8, y1: u8| (y0..=y1).map(|y| (x, y)).collect();
+        let mut segs: Vec<Vec<(u8, u8)>> = vec![
+            row(0, 0, right),
+            col(right, 0, mid),
+            col(right, mid, bottom),
+            row(bottom, 0, right),
+            col(0, mid, bottom),
+            col(0, 0, mid),
+        ];
+        if self == Display::Seven {
+            segs.push(row(mid, 0, right));
+            return Ok(segs);
+        }
+        // diagonal from a corner (x0, y0) to the center (cx, mid)
+        let diag = |x0: u8, y0: u8| -> Vec<(u8, u8)> {
+            let rows = y0.abs_diff(mid);
+            let cols = x0.abs_diff(cx);
+            (0..=rows)
+                .map(|i| {
+                    let dx = (u16::from(i) * u16::from(cols) * 2
+                        + u16::from(rows))
+                        / (u16::from(rows) * 2);
+                    let x = if x0 < cx {
+                        x0 + dx as u8
+                    } else {
+                        x0 - dx as u8
+                    };
+                    let y = if y0 < mid { y0 + i } else { y0 - i };
+                    (x, y)
+                })
+                .collect()
+        };
+        segs.push(row(mid, 0, cx));
+        segs.push(row(mid, cx, right));
+        segs.push(diag(0, 0));
+        segs.push(col(cx, 0, mid));
+        segs.push(diag(right, 0));
+        segs.push(diag(0, bottom));
+        segs.push(col(cx, mid, bottom));
+        segs.push(diag(right, bottom));
+        Ok(segs)
+    }
+
+    /// Render a segment mask as a glyph
+    pub fn render(self, mask: u16, height: u8, width: u8) -> Result<Bitmap> {
+        let mut bmap = Bitmap::new_blank(height, width);
+        for (i, seg) in self.pixels(height, width)?.iter().enumerate() {
+            if mask & (1 << i) != 0 {
+                for &(x, y) in seg {
+                    bmap.set_pixel(x, y, true);
+                }
+            }
+        }
+        Ok(bmap)
+    }
+
+    /// Convert a glyph to a segment mask
+    ///
+    /// Blank glyphs (such as a narrow space) have no segments lit, whatever
+    /// their size.
+    pub fn mask(self, bmap: &Bitmap) -> Result<u16> {
+        let mut mask = 0;
+        if bmap.ink_bounds().is_none() {
+            return Ok(mask);
+        }
+        for (i, seg) in
+            self.pixels(bmap.height(), bmap.width())?.iter().enumerate()
+        {
+            let inner = &seg[1..seg.len() - 1];
+            let lit = inner.iter().filter(|(x, y)| bmap.pixel(*x, *y)).count();
+            if !inner.is_empty() && lit * 2 > inner.len() {
+                mask |= 1 << i;
+            }
+        }
+        Ok(mask)
+    }
+
+    /// Make a font from segment masks
+    ///
+    /// * `name` Font name.
+    /// * `masks` Code points and masks, such as [Display::digits].
+    /// * `height` Character cell height.
+    /// * `width` Character cell width.
+    pub fn font(
+        self,
+        name: &str,
+        masks: &[(u16, u16)],
+        height: u8,
+        width: u8,
+    ) -> Result<Font> {
+        let mut props = vec![
+            Prop::FontName(name.into()),
+            Prop::FontHeight(height),
+            Prop::FontWidth(width),
+            Prop::CharSpacing(1),
+            Prop::LineSpacing(1),
+        ];
+        for &(cp, mask) in masks {
+            props.push(Prop::CodePoint(cp));
+            props.push(Prop::Bitmap(self.render(mask, height, width)?));
+        }
+        Font::from_props(props.into_iter())
+    }
+
+    /// Convert all glyphs in a font to segment masks
+    ///
+    /// Glyphs too small for segments, such as a narrow colon, are skipped.
+    pub fn masks(self, font: &Font) -> Vec<(u16, u16)> {
+        font.glyphs()
+            .filter_map(|(cp, bmap)| Some((cp, self.mask(bmap).ok()?)))
+            .collect()
+    }
+}
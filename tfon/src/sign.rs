@@ -0,0 +1,164 @@
+//! Simulated sign images
+//!
+//! A bitmap is drawn as an array of round LED dots, for a realistic preview
+//! of a message on a dynamic message sign.
+use crate::common::{Bitmap, Result};
+use std::io::Write;
+
+/// RGB color
+pub type Rgb = [u8; 3];
+
+/// LED color palette
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Palette {
+    /// Color of lit dots
+    pub on: Rgb,
+    /// Color of unlit dots
+    pub off: Rgb,
+    /// Background color between dots
+    pub background: Rgb,
+}
+
+impl Palette {
+    /// Amber LEDs
+    pub const AMBER: Palette = Palette {
+        on: [255, 176, 0],
+        off: [40, 32, 24],
+        background: [8, 8, 8],
+    };
+
+    /// White LEDs
+    pub const WHITE: Palette = Palette {
+        on: [240, 240, 255],
+        off: [36, 36, 40],
+        background: [8, 8, 8],
+    };
+}
+
+/// Dot geometry and colors
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Style {
+    /// Distance between dot centers (image pixels)
+    pub pitch: u8,
+    /// Dot diameter (image pixels)
+    pub diameter: u8,
+    /// Color palette
+    pub palette: Palette,
+}
+
+impl Default for Style {
+    fn default() -> Self {
+        Style {
+            pitch: 8,
+            diameter: 6,
+            palette: Palette::AMBER,
+        }
+    }
+}
+
+/// Simulated sign image
+#[derive(Clone)]
+pub struct Image {
+    /// Width in pixels
+    width: u32,
+    /// Height in pixels
+    height: u32,
+    /// RGB pixel values
+    pixels: Vec<u8>,
+}
+
+/// Number of samples per pixel axis, for smoothing dot edges
+const SAMPLES: u32 = 4;
+
+impl Image {
+    /// Create a sign image of a bitmap
+    pub fn new(bitmap: &Bitmap, style: &Style) -> Self {
+        let pitch = u32::from(style.pitch.max(1));
+        let width = u32::from(bitmap.width()) * pitch;
+        let height = u32::from(bitmap.height()) * pitch;
+        // dot radius, in sub-samples
+        let radius = u32::from(style.diameter.min(style.pitch)) * SAMPLES / 2;
+        let center = pitch * SAMPLES / 2;
+        let mut pixels = Vec::with_capacity((width * height * 3) as usize);
+        for y in 0..height {
+            for x in 0..width {
+                let on = bitmap.pixel((x / pitch) as u8, (y / pitch) as u8);
+                let dot = if on {
+                    style.palette.on
+                } else {
+                    style.palette.off
+                };
+                let cover = coverage(
+                    (x % pitch) * SAMPLES,
+                    (y % pitch) * SAMPLES,
+                    center,
+                    radius,
+                );
+                pixels.extend(blend(style.palette.background, dot, cover));
+            }
+        }
+        Image {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    /// Get image width
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Get image height
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Get RGB pixel values
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    /// Write sign image in PNG format
+    pub fn write_png<W: Write>(&self, writer: W) -> Result<()> {
+        crate::preview::write_png(
+            writer,
+            self.width,
+            self.height,
+            png::ColorType::Rgb,
+            &self.pixels,
+        )
+    }
+}
+
+/// Calculate dot coverage of one pixel (0 to `SAMPLES` squared)
+///
+/// * `sx` / `sy` Top-left sub-sample of pixel within its cell.
+fn coverage(sx: u32, sy: u32, center: u32, radius: u32) -> u32 {
+    let mut cover = 0;
+    for y in sy..sy + SAMPLES {
+        for x in sx..sx + SAMPLES {
+            // sample at the middle of each sub-sample (doubled coordinates)
+            let dx = i64::from(x * 2 + 1) - i64::from(center * 2);
+            let dy = i64::from(y * 2 + 1) - i64::from(center * 2);
+            let r = i64::from(radius * 2);
+            if dx * dx + dy * dy <= r * r {
+                cover += 1;
+            }
+        }
+    }
+    cover
+}
+
+/// Blend background and dot colors by coverage
+fn blend(background: Rgb, dot: Rgb, cover: u32) -> Rgb {
+    let full = SAMPLES * SAMPLES;
+    let mix = |b: u8, d: u8| {
+        ((u32::from(b) * (full - cover) + u32::from(d) * cover) / full) as u8
+    };
+    [
+        mix(background[0], dot[0]),
+        mix(background[1], dot[1]),
+        mix(background[2], dot[2]),
+    ]
+}
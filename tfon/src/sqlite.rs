@@ -0,0 +1,249 @@
+//! SQLite font store
+//!
+//! Fonts are persisted in two tables, like many traffic management systems:
+//! ```sql
+//! CREATE TABLE fonts (
+//!     name TEXT PRIMARY KEY,
+//!     f_number INTEGER NOT NULL,
+//!     height INTEGER NOT NULL,
+//!     width INTEGER NOT NULL,
+//!     char_spacing INTEGER NOT NULL,
+//!     line_spacing INTEGER NOT NULL,
+//!     baseline INTEGER NOT NULL
+//! );
+//! CREATE TABLE glyphs (
+//!     font TEXT NOT NULL REFERENCES fonts (name) ON DELETE CASCADE,
+//!     code_point INTEGER NOT NULL,
+//!     width INTEGER NOT NULL,
+//!     pixels BLOB NOT NULL,
+//!     PRIMARY KEY (font, code_point)
+//! );
+//! ```
+//! Glyph pixels are packed into bits, like
+//! [Bitmap::into_bits](crate::Bitmap::into_bits).  Ligatures are not stored.
+use crate::common::{Bitmap, Error, Prop, Result};
+use crate::font::Font;
+use crate::load::{load_dir, LoadError};
+use rusqlite::{params, Connection, OptionalExtension};
+use std::path::Path;
+
+/// Schema for font tables
+const SCHEMA: &str = "\
+CREATE TABLE IF NOT EXISTS fonts (
+    name TEXT PRIMARY KEY,
+    f_number INTEGER NOT NULL,
+    height INTEGER NOT NULL,
+    width INTEGER NOT NULL,
+    char_spacing INTEGER NOT NULL,
+    line_spacing INTEGER NOT NULL,
+    baseline INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS glyphs (
+    font TEXT NOT NULL REFERENCES fonts (name) ON DELETE CASCADE,
+    code_point INTEGER NOT NULL,
+    width INTEGER NOT NULL,
+    pixels BLOB NOT NULL,
+    PRIMARY KEY (font, code_point)
+);";
+
+/// Convert a database error
+fn sql_err(e: rusqlite::Error) -> Error {
+    std::io::Error::other(e).into()
+}
+
+/// Font store in a SQLite database
+pub struct Store {
+    /// Database connection
+    conn: Connection,
+}
+
+/// Font header row
+struct Header {
+    /// Font name
+    name: String,
+    /// Font number
+    number: u8,
+    /// Height in pixels
+    height: u8,
+    /// Width in pixels (0 for proportional)
+    width: u8,
+    /// Pixel spacing between characters
+    char_spacing: u8,
+    /// Pixel spacing between lines
+    line_spacing: u8,
+    /// Baseline (rows above)
+    baseline: u8,
+}
+
+/// Insert a font, replacing any font with the same name
+fn insert(conn: &Connection, font: &Font) -> Result<()> {
+    conn.execute("DELETE FROM fonts WHERE name = ?1", [font.name()])
+        .map_err(sql_err)?;
+    conn.execute(
+        "INSERT INTO fonts (name, f_number, height, width, char_spacing, \
+         line_spacing, baseline) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![
+            font.name(),
+            font.number(),
+            font.height(),
+            font.width(),
+            font.char_spacing(),
+            font.line_spacing(),
+            font.baseline(),
+        ],
+    )
+    .map_err(sql_err)?;
+    let mut stmt = conn
+        .prepare(
+            "INSERT INTO glyphs (font, code_point, width, pixels) \
+             VALUES (?1, ?2, ?3, ?4)",
+        )
+        .map_err(sql_err)?;
+    for (cp, bmap) in font.glyphs() {
+        let pixels = bmap.clone().into_bits();
+        stmt.execute(params![font.name(), cp, bmap.width(), pixels])
+            .map_err(sql_err)?;
+    }
+    Ok(())
+}
+
+impl Store {
+    /// Open a store in a database file, creating tables if needed
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        Store::from_connection(Connection::open(path).map_err(sql_err)?)
+    }
+
+    /// Open a store in memory
+    pub fn open_in_memory() -> Result<Self> {
+        Store::from_connection(Connection::open_in_memory().map_err(sql_err)?)
+    }
+
+    /// Use an existing connection, creating tables if needed
+    pub fn from_connection(conn: Connection) -> Result<Self> {
+        conn.execute_batch("PRAGMA foreign_keys = ON;")
+            .map_err(sql_err)?;
+        conn.execute_batch(SCHEMA).map_err(sql_err)?;
+        Ok(Store { conn })
+    }
+
+    /// Get the database connection
+    pub fn connection(&self) -> &Connection {
+        &self.conn
+    }
+
+    /// Get names of all stored fonts
+    pub fn names(&self) -> Result<Vec<String>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT name FROM fonts ORDER BY f_number, name")
+            .map_err(sql_err)?;
+        let names = stmt
+            .query_map([], |row| row.get(0))
+            .map_err(sql_err)?
+            .collect::<rusqlite::Result<_>>()
+            .map_err(sql_err)?;
+        Ok(names)
+    }
+
+    /// Store a font, replacing any font with the same name
+    pub fn store(&mut self, font: &Font) -> Result<()> {
+        let tx = self.conn.transaction().map_err(sql_err)?;
+        insert(&tx, font)?;
+        tx.commit().map_err(sql_err)
+    }
+
+    /// Load a font by name
+    pub fn load(&self, name: &str) -> Result<Option<Font>> {
+        let header = self
+            .conn
+            .query_row(
+                "SELECT name, f_number, height, width, char_spacing, \
+                 line_spacing, baseline FROM fonts WHERE name = ?1",
+                [name],
+                |row| {
+                    Ok(Header {
+                        name: row.get(0)?,
+                        number: row.get(1)?,
+                        height: row.get(2)?,
+                        width: row.get(3)?,
+                        char_spacing: row.get(4)?,
+                        line_spacing: row.get(5)?,
+                        baseline: row.get(6)?,
+                    })
+                },
+            )
+            .optional()
+            .map_err(sql_err)?;
+        let Some(header) = header else {
+            return Ok(None);
+        };
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT code_point, width, pixels FROM glyphs \
+                 WHERE font = ?1 ORDER BY code_point",
+            )
+            .map_err(sql_err)?;
+        let rows: Vec<(u16, u8, Vec<u8>)> = stmt
+            .query_map([name], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+            })
+            .map_err(sql_err)?
+            .collect::<rusqlite::Result<_>>()
+            .map_err(sql_err)?;
+        let mut props = vec![
+            Prop::FontName(header.name.as_str().into()),
+            Prop::FontNumber(header.number),
+            Prop::FontHeight(header.height),
+            Prop::FontWidth(header.width),
+            Prop::CharSpacing(header.char_spacing),
+            Prop::LineSpacing(header.line_spacing),
+            Prop::Baseline(header.baseline),
+        ];
+        for (cp, width, pixels) in rows {
+            let bmap = Bitmap::from_bits(header.height, width, pixels)
+                .ok_or(Error::GlyphTooLarge(cp))?;
+            props.push(Prop::CodePoint(cp));
+            props.push(Prop::Bitmap(bmap));
+        }
+        Font::from_props(props.into_iter()).map(Some)
+    }
+
+    /// Load all stored fonts
+    pub fn load_all(&self) -> Result<Vec<Font>> {
+        let mut fonts = Vec::new();
+        for name in self.names()? {
+            fonts.extend(self.load(&name)?);
+        }
+        Ok(fonts)
+    }
+
+    /// Remove a font by name
+    ///
+    /// Returns `true` if the font was stored.
+    pub fn remove(&mut self, name: &str) -> Result<bool> {
+        let n = self
+            .conn
+            .execute("DELETE FROM fonts WHERE name = ?1", [name])
+            .map_err(sql_err)?;
+        Ok(n > 0)
+    }
+
+    /// Import a file-based font bank from a directory
+    ///
+    /// Fonts are loaded with [load_dir](crate::load_dir) and stored in one
+    /// transaction, so a failure leaves the store unchanged.  Returns the
+    /// number of fonts imported, and any files which failed to load.
+    pub fn import_dir(
+        &mut self,
+        dir: impl AsRef<Path>,
+    ) -> Result<(usize, Vec<LoadError>)> {
+        let (fonts, errors) = load_dir(dir);
+        let tx = self.conn.transaction().map_err(sql_err)?;
+        for font in &fonts {
+            insert(&tx, font)?;
+        }
+        tx.commit().map_err(sql_err)?;
+        Ok((fonts.len(), errors))
+    }
+}
@@ -0,0 +1,448 @@
+//! Static fonts, for use without heap allocation
+//!
+//! A [StaticFont] is backed by a `&'static [u8]` table, so it can be stored
+//! in flash on tiny microcontrollers.  Glyph lookup and rendering do not
+//! allocate.  Tables are made with [encode], or at compile time with
+//! [encode_const], as the `include_static_font!` macro (`macros` feature)
+//! does.
+//!
+//! # Layout
+//! All multi-byte values are little-endian.
+//!
+//! Offset | Size  | Value
+//! -------|-------|------
+//! 0      | 1     | Font number
+//! 1      | 1     | Height
+//! 2      | 1     | Width (0 for proportional)
+//! 3      | 1     | Character spacing
+//! 4      | 1     | Line spacing
+//! 5      | 1     | Baseline (rows above)
+//! 6      | 2     | Glyph count (`n`)
+//! 8      | 8 × n | Glyph index, sorted by code point
+//! …      | …     | Glyph bits
+//!
+//! Each glyph index entry has a code point (2 bytes), width (1 byte), a
+//! reserved byte, and the offset of its bits from the start of the table (4
+//! bytes).  Bits are packed in rows, most significant bit first, like
+//! [Bitmap::into_bits](crate::Bitmap::into_bits).
+//!
+//! # Compile-time checks
+//! Header accessors are `const fn`, so target limits can be checked when
+//! the consuming project is built:
+//! ```ignore
+//! const FONT: StaticFont = tfon::include_static_font!("fonts/f8.tfon");
+//! const _: () = assert!(FONT.height() % 8 == 0, "SSD1306 page aligned");
+//! const _: () = assert!(FONT.max_width() <= 16);
+//! ```
+//!
+//! Generated C tables can include `_Static_assert` checks of
+//! [Constraints], with [WriteOptions::constraints](crate::WriteOptions).
+use crate::common::{Error, Result};
+use crate::font::Font;
+use crate::render::PixelSink;
+
+/// Size of table header
+pub(crate) const HEADER_LEN: usize = 8;
+
+/// Size of one glyph index entry
+pub(crate) const ENTRY_LEN: usize = 8;
+
+/// Font backed by a static table
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StaticFont {
+    /// Font name
+    name: &'static str,
+    /// Binary table
+    data: &'static [u8],
+}
+
+/// Glyph of a static font
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StaticGlyph {
+    /// Height in pixels
+    height: u8,
+    /// Width in pixels
+    width: u8,
+    /// Packed bits
+    bits: &'static [u8],
+}
+
+impl StaticGlyph {
+    /// Get pixel height
+    pub fn height(&self) -> u8 {
+        self.height
+    }
+
+    /// Get pixel width
+    pub fn width(&self) -> u8 {
+        self.width
+    }
+
+    /// Get one pixel
+    ///
+    /// Pixels outside the glyph are off.
+    pub fn pixel(&self, x: u8, y: u8) -> bool {
+        if x >= self.width || y >= self.height {
+            return false;
+        }
+        let pos = usize::from(y) * usize::from(self.width) + usize::from(x);
+        let bit = 7 - (pos & 0b111);
+        self.bits.get(pos >> 3).is_some_and(|b| (b >> bit) & 1 != 0)
+    }
+}
+
+impl StaticFont {
+    /// Create a static font from a table
+    ///
+    /// The table is not checked; lookups in a malformed table return
+    /// `None`, rather than panicking.
+    pub const fn new(name: &'static str, data: &'static [u8]) -> Self {
+        StaticFont { name, data }
+    }
+
+    /// Get a header byte
+    const fn byte(&self, off: usize) -> u8 {
+        if off < self.data.len() {
+            self.data[off]
+        } else {
+            0
+        }
+    }
+
+    /// Get font name
+    pub const fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Get font number
+    pub const fn number(&self) -> u8 {
+        self.byte(0)
+    }
+
+    /// Get font height
+    pub const fn height(&self) -> u8 {
+        self.byte(1)
+    }
+
+    /// Get font width (0 for proportional)
+    pub const fn width(&self) -> u8 {
+        self.byte(2)
+    }
+
+    /// Get character spacing
+    pub const fn char_spacing(&self) -> u8 {
+        self.byte(3)
+    }
+
+    /// Get line spacing
+    pub const fn line_spacing(&self) -> u8 {
+        self.byte(4)
+    }
+
+    /// Get baseline (rows above)
+    pub const fn baseline(&self) -> u8 {
+        self.byte(5)
+    }
+
+    /// Get the number of glyphs
+    pub const fn len(&self) -> usize {
+        u16::from_le_bytes([self.byte(6), self.byte(7)]) as usize
+    }
+
+    /// Check if the font has no glyphs
+    pub const fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get the widest glyph width
+    pub const fn max_width(&self) -> u8 {
+        let mut width = 0;
+        let mut i = 0;
+        while i < self.len() {
+            let w = self.byte(HEADER_LEN + i * ENTRY_LEN + 2);
+            if w > width {
+                width = w;
+            }
+            i += 1;
+        }
+        width
+    }
+
+    /// Get a glyph index entry (code point, width, offset)
+    fn entry(&self, i: usize) -> Option<(u16, u8, usize)> {
+        let off = HEADER_LEN + i * ENTRY_LEN;
+        let e = self.data.get(off..off + ENTRY_LEN)?;
+        let cp = u16::from_le_bytes([e[0], e[1]]);
+        let offset = u32::from_le_bytes([e[4], e[5], e[6], e[7]]);
+        Some((cp, e[2], usize::try_from(offset).ok()?))
+    }
+
+    /// Get glyph for a code point
+    pub fn glyph(&self, cp: u16) -> Option<StaticGlyph> {
+        let (mut lo, mut hi) = (0, self.len());
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            let (c, width, offset) = self.entry(mid)?;
+            if c < cp {
+                lo = mid + 1;
+            } else if c > cp {
+                hi = mid;
+            } else {
+                let height = self.height();
+                let len =
+                    (usize::from(height) * usize::from(width)).div_ceil(8);
+                let bits = self.data.get(offset..offset + len)?;
+                return Some(StaticGlyph {
+                    height,
+                    width,
+                    bits,
+                });
+            }
+        }
+        None
+    }
+
+    /// Get glyph for a character
+    pub fn glyph_char(&self, c: char) -> Option<StaticGlyph> {
+        u16::try_from(u32::from(c))
+            .ok()
+            .and_then(|cp| self.glyph(cp))
+    }
+
+    /// Measure the width of rendered text, in pixels
+    pub fn measure(&self, text: &str) -> Result<u32> {
+        let mut width = 0;
+        for (i, c) in text.chars().enumerate() {
+            if i > 0 {
+                width += u32::from(self.char_spacing());
+            }
+            let gl = self.glyph_char(c).ok_or(Error::MissingGlyph(c))?;
+            width += u32::from(gl.width());
+        }
+        Ok(width)
+    }
+
+    /// Draw text into a pixel sink
+    ///
+    /// Only on pixels are drawn, as with [blit](crate::render::blit).
+    ///
+    /// * `x` / `y` Position of top left pixel.
+    ///
+    /// Returns the width of the text, in pixels.
+    pub fn blit_text(
+        &self,
+        text: &str,
+        sink: &mut impl PixelSink,
+        x: u32,
+        y: u32,
+    ) -> Result<u32> {
+        let spacing = u32::from(self.char_spacing());
+        let mut width = 0;
+        for (i, c) in text.chars().enumerate() {
+            if i > 0 {
+                width += spacing;
+            }
+            let gl = self.glyph_char(c).ok_or(Error::MissingGlyph(c))?;
+            for gy in 0..gl.height() {
+                for gx in 0..gl.width() {
+                    let px = x.checked_add(width + u32::from(gx));
+                    let py = y.checked_add(u32::from(gy));
+                    if let (Some(px), Some(py)) = (px, py) {
+                        if gl.pixel(gx, gy) {
+                            sink.set(px, py, true);
+                        }
+                    }
+                }
+            }
+            width += u32::from(gl.width());
+        }
+        Ok(width)
+    }
+}
+
+/// Target display constraints, checked at compile time by generated code
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Constraints {
+    /// Maximum font height
+    pub max_height: Option<u8>,
+    /// Maximum glyph width
+    pub max_width: Option<u8>,
+    /// Rows per display page (font height must be a multiple)
+    pub page_rows: Option<u8>,
+    /// Maximum number of glyphs
+    pub max_glyphs: Option<u16>,
+}
+
+impl Constraints {
+    /// SSD1306 OLED: 128x64 pixels, in pages of 8 rows
+    pub const SSD1306: Constraints = Constraints {
+        max_height: Some(64),
+        max_width: Some(128),
+        page_rows: Some(8),
+        max_glyphs: None,
+    };
+
+    /// Get assertions (expression and message) on generated constants
+    ///
+    /// Constants are named with a prefix: `{prefix}_HEIGHT`,
+    /// `{prefix}_MAX_WIDTH` and `{prefix}_GLYPH_COUNT`.  Expressions are
+    /// valid in both C and Rust.
+    pub fn assertions(&self, prefix: &str) -> Vec<(String, String)> {
+        let mut asserts = Vec::new();
+        if let Some(rows) = self.page_rows {
+            asserts.push((
+                format!("{prefix}_HEIGHT % {rows} == 0"),
+                format!("height must be a multiple of {rows} rows"),
+            ));
+        }
+        if let Some(height) = self.max_height {
+            asserts.push((
+                format!("{prefix}_HEIGHT <= {height}"),
+                format!("height must be at most {height}"),
+            ));
+        }
+        if let Some(width) = self.max_width {
+            asserts.push((
+                format!("{prefix}_MAX_WIDTH <= {width}"),
+                format!("glyph width must be at most {width}"),
+            ));
+        }
+        if let Some(glyphs) = self.max_glyphs {
+            asserts.push((
+                format!("{prefix}_GLYPH_COUNT <= {glyphs}"),
+                format!("at most {glyphs} glyphs"),
+            ));
+        }
+        asserts
+    }
+}
+
+/// Glyph to encode: code point, width and packed bits
+///
+/// Bits are packed like [Bitmap::into_bits](crate::Bitmap::into_bits).
+pub type GlyphBits<'a> = (u16, u8, &'a [u8]);
+
+/// Get the length of an encoded table
+///
+/// * `height` Font height.
+/// * `glyphs` Glyphs to encode.
+pub const fn encoded_len(height: u8, glyphs: &[GlyphBits]) -> usize {
+    let mut len = HEADER_LEN + glyphs.len() * ENTRY_LEN;
+    let mut i = 0;
+    while i < glyphs.len() {
+        len += bits_len(height, glyphs[i].1);
+        i += 1;
+    }
+    len
+}
+
+/// Get the length of packed glyph bits
+const fn bits_len(height: u8, width: u8) -> usize {
+    (height as usize * width as usize).div_ceil(8)
+}
+
+/// Encode a static table into a buffer
+///
+/// This is the one encoder of the table layout, used by [encode] and (at
+/// compile time) by `include_static_font!`.
+///
+/// * `header` Font number, height, width, character spacing, line spacing
+///   and baseline.
+/// * `glyphs` Glyphs to encode, sorted by code point.
+/// * `out` Buffer of [encoded_len] bytes.
+///
+/// # Panics
+///
+/// If glyphs are not sorted, their bits don't match the font height, or the
+/// buffer is the wrong length.
+pub const fn encode_into(
+    header: [u8; 6],
+    glyphs: &[GlyphBits],
+    out: &mut [u8],
+) {
+    let height = header[1];
+    assert!(
+        out.len() == encoded_len(height, glyphs),
+        "wrong table length"
+    );
+    assert!(glyphs.len() <= u16::MAX as usize, "too many glyphs");
+    let mut i = 0;
+    while i < header.len() {
+        out[i] = header[i];
+        i += 1;
+    }
+    let count = (glyphs.len() as u16).to_le_bytes();
+    out[6] = count[0];
+    out[7] = count[1];
+    let mut offset = HEADER_LEN + glyphs.len() * ENTRY_LEN;
+    let mut g = 0;
+    while g < glyphs.len() {
+        let (cp, width, bits) = glyphs[g];
+        assert!(g == 0 || glyphs[g - 1].0 < cp, "glyphs not sorted");
+        assert!(bits.len() == bits_len(height, width), "glyph size mismatch");
+        assert!(offset <= u32::MAX as usize, "table too large");
+        let entry = HEADER_LEN + g * ENTRY_LEN;
+        let cp = cp.to_le_bytes();
+        let off = (offset as u32).to_le_bytes();
+        let e = [cp[0], cp[1], width, 0, off[0], off[1], off[2], off[3]];
+        let mut i = 0;
+        while i < ENTRY_LEN {
+            out[entry + i] = e[i];
+            i += 1;
+        }
+        let mut i = 0;
+        while i < bits.len() {
+            out[offset + i] = bits[i];
+            i += 1;
+        }
+        offset += bits.len();
+        g += 1;
+    }
+}
+
+/// Encode a static table, at compile time
+///
+/// `N` must be the [encoded_len] of the glyphs.
+pub const fn encode_const<const N: usize>(
+    header: [u8; 6],
+    glyphs: &[GlyphBits],
+) -> [u8; N] {
+    let mut out = [0; N];
+    encode_into(header, glyphs, &mut out);
+    out
+}
+
+/// Encode a font as a static table
+///
+/// All glyphs must have the font height.  Ligatures are not included.
+pub fn encode(font: &Font) -> Result<Vec<u8>> {
+    let mut bits = Vec::new();
+    for (cp, bmap) in font.glyphs() {
+        if bmap.height() != font.height() {
+            return Err(Error::GlyphTooLarge(cp));
+        }
+        bits.push((cp, bmap.width(), bmap.clone().into_bits()));
+    }
+    if u16::try_from(bits.len()).is_err() {
+        return Err(Error::TooLarge());
+    }
+    let glyphs: Vec<GlyphBits> = bits
+        .iter()
+        .map(|(cp, w, b)| (*cp, *w, b.as_slice()))
+        .collect();
+    let len = encoded_len(font.height(), &glyphs);
+    if u32::try_from(len).is_err() {
+        return Err(Error::TooLarge());
+    }
+    let header = [
+        font.number(),
+        font.height(),
+        font.width(),
+        font.char_spacing(),
+        font.line_spacing(),
+        font.baseline(),
+    ];
+    let mut data = vec![0; len];
+    encode_into(header, &glyphs, &mut data);
+    Ok(data)
+}
@@ -0,0 +1,209 @@
+//! Adapters for streams of font properties
+//!
+//! Conversions with small tweaks can stay in streaming style, without
+//! collecting into a [Font](crate::Font):
+//! ```
+//! use tfon::PropStream;
+//!
+//! let font = tfon::demo::font(7);
+//! let props = font
+//!     .props()
+//!     .retain_glyphs(|cp| (48..=57).contains(&cp))
+//!     .set_header(tfon::PropSet {
+//!         font_name: Some("Digits"),
+//!         ..Default::default()
+//!     });
+//! let mut out = Vec::new();
+//! tfon::tfon::write(&mut out, props).unwrap();
+//! ```
+use crate::common::{Bitmap, Prop};
+use std::collections::VecDeque;
+
+/// Header properties to set in a stream
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PropSet<'a> {
+    /// Font name
+    pub font_name: Option<&'a str>,
+    /// Font number
+    pub font_number: Option<u8>,
+    /// Font width (0 for proportional)
+    pub font_width: Option<u8>,
+    /// Pixel spacing between characters
+    pub char_spacing: Option<u8>,
+    /// Pixel spacing between lines
+    pub line_spacing: Option<u8>,
+    /// Baseline (rows above)
+    pub baseline: Option<u8>,
+}
+
+impl<'a> PropSet<'a> {
+    /// Replace a property with a value from the set
+    ///
+    /// Each value is only used once.
+    fn replace(&mut self, prop: Prop<'a>) -> Prop<'a> {
+        match prop {
+            Prop::FontName(nm) => {
+                Prop::FontName(self.font_name.take().map_or(nm, |nm| nm.into()))
+            }
+            Prop::FontNumber(num) => {
+                Prop::FontNumber(self.font_number.take().unwrap_or(num))
+            }
+            Prop::FontWidth(fw) => {
+                Prop::FontWidth(self.font_width.take().unwrap_or(fw))
+            }
+            Prop::CharSpacing(cs) => {
+                Prop::CharSpacing(self.char_spacing.take().unwrap_or(cs))
+            }
+            Prop::LineSpacing(ls) => {
+                Prop::LineSpacing(self.line_spacing.take().unwrap_or(ls))
+            }
+            Prop::Baseline(bl) => {
+                Prop::Baseline(self.baseline.take().unwrap_or(bl))
+            }
+            _ => prop,
+        }
+    }
+
+    /// Take all remaining values as properties
+    fn take_remaining(&mut self) -> VecDeque<Prop<'a>> {
+        let set = std::mem::take(self);
+        set.font_name
+            .map(|nm| Prop::FontName(nm.into()))
+            .into_iter()
+            .chain(set.font_number.map(Prop::FontNumber))
+            .chain(set.font_width.map(Prop::FontWidth))
+            .chain(set.char_spacing.map(Prop::CharSpacing))
+            .chain(set.line_spacing.map(Prop::LineSpacing))
+            .chain(set.baseline.map(Prop::Baseline))
+            .collect()
+    }
+}
+
+/// Extension trait for streams of font properties
+pub trait PropStream<'a>: Iterator<Item = Prop<'a>> + Sized {
+    /// Keep only glyphs with code points matching a predicate
+    fn retain_glyphs<F>(self, f: F) -> RetainGlyphs<Self, F>
+    where
+        F: FnMut(u16) -> bool,
+    {
+        RetainGlyphs {
+            props: self,
+            f,
+            skip: false,
+        }
+    }
+
+    /// Replace every glyph bitmap
+    fn map_bitmaps<F>(self, f: F) -> MapBitmaps<Self, F>
+    where
+        F: FnMut(Bitmap) -> Bitmap,
+    {
+        MapBitmaps { props: self, f }
+    }
+
+    /// Set header properties
+    ///
+    /// Matching properties are replaced; any others are added before the
+    /// first glyph.
+    fn set_header(self, header: PropSet<'a>) -> SetHeader<'a, Self> {
+        SetHeader {
+            props: self,
+            header,
+            pending: VecDeque::new(),
+        }
+    }
+}
+
+impl<'a, I: Iterator<Item = Prop<'a>>> PropStream<'a> for I {}
+
+/// Stream adapter from [PropStream::retain_glyphs]
+pub struct RetainGlyphs<I, F> {
+    /// Property stream
+    props: I,
+    /// Predicate for code points
+    f: F,
+    /// Skip bitmap of a removed glyph
+    skip: bool,
+}
+
+impl<'a, I, F> Iterator for RetainGlyphs<I, F>
+where
+    I: Iterator<Item = Prop<'a>>,
+    F: FnMut(u16) -> bool,
+{
+    type Item = Prop<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let prop = self.props.next()?;
+            match prop {
+                Prop::CodePoint(cp) => {
+                    self.skip = !(self.f)(cp);
+                    if !self.skip {
+                        return Some(prop);
+                    }
+                }
+                Prop::Bitmap(_) if self.skip => self.skip = false,
+                _ => return Some(prop),
+            }
+        }
+    }
+}
+
+/// Stream adapter from [PropStream::map_bitmaps]
+pub struct MapBitmaps<I, F> {
+    /// Property stream
+    props: I,
+    /// Bitmap function
+    f: F,
+}
+
+impl<'a, I, F> Iterator for MapBitmaps<I, F>
+where
+    I: Iterator<Item = Prop<'a>>,
+    F: FnMut(Bitmap) -> Bitmap,
+{
+    type Item = Prop<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.props.next()? {
+            Prop::Bitmap(bmap) => Some(Prop::Bitmap((self.f)(bmap))),
+            prop => Some(prop),
+        }
+    }
+}
+
+/// Stream adapter from [PropStream::set_header]
+pub struct SetHeader<'a, I> {
+    /// Property stream
+    props: I,
+    /// Header properties not yet set
+    header: PropSet<'a>,
+    /// Properties waiting to be returned
+    pending: VecDeque<Prop<'a>>,
+}
+
+impl<'a, I> Iterator for SetHeader<'a, I>
+where
+    I: Iterator<Item = Prop<'a>>,
+{
+    type Item = Prop<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(prop) = self.pending.pop_front() {
+            return Some(prop);
+        }
+        match self.props.next() {
+            Some(Prop::CodePoint(cp)) => {
+                self.pending = self.header.take_remaining();
+                self.pending.push_back(Prop::CodePoint(cp));
+                self.pending.pop_front()
+            }
+            Some(prop) => Some(self.header.replace(prop)),
+            None => {
+                self.pending = self.header.take_remaining();
+                self.pending.pop_front()
+            }
+        }
+    }
+}
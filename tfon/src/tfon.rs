@@ -17,6 +17,7 @@
 //! ....@@.
 //! ....@@.
 //!
+//! # Comment lines begin with '#'
 //! ch: 65 A
 //! .@@@@.
 //! @@..@@
@@ -26,9 +27,45 @@
 //! @@..@@
 //! @@..@@
 //! ```
-use crate::common::{Bitmap, Error, Prop, Result};
+//!
+//! A header line such as `ligature: 223 ss` renders a character sequence
+//! with the glyph for a code point, which must be 255 or below.  A
+//! `max_char_number: 255` header reserves code points up to 255 (for a sign
+//! controller character table); no glyph may be above it.  Pixel rows may
+//! have trailing comments, such as row numbers.
+//!
+//! A blinking glyph has a second frame, with pixel rows after a `frame: 2`
+//! line:
+//! ```text
+//! ch: 33 !
+//! .@.
+//! .@.
+//! ...
+//! .@.
+//! frame: 2
+//! ...
+//! ...
+//! ...
+//! ...
+//! ```
+//!
+//! The last change to a glyph can be recorded with a `modified` line after
+//! its pixel rows, holding a timestamp and author:
+//! ```text
+//! modified: 2024-05-01T12:00:00Z Jane Doe
+//! ```
+//!
+//! In scaffold mode, a `ch` line without a pixel grid is parsed as a blank
+//! glyph, so that new glyphs can be sketched out before drawing them.
+use crate::common::{
+    Bitmap, Error, ParseContext, Prop, Result, WriteOptions, WriteReport,
+};
+use crate::cursor::{LineCursor, LinePolicy};
+use crate::font::Font;
+use crate::format::PropParser;
+use std::collections::HashSet;
 use std::io::Write;
-use std::str::{FromStr, Lines};
+use std::str::FromStr;
 
 /// Symbols for all ASCII + Latin 1 characters
 const SYMBOL: &[&str] = &[
@@ -56,9 +93,17 @@ const SYMBOL: &[&str] = &[
 /// Parser for `tfon` format
 pub struct Parser<'p> {
     /// Lines to parse
-    lines: Lines<'p>,
-    /// Pushed back line
-    line: Option<&'p str>,
+    lines: LineCursor<'p>,
+    /// Line number where the last property started
+    line: usize,
+    /// Pending bitmap of a parsed glyph
+    bitmap: Option<Bitmap>,
+    /// Scaffold mode: blank glyphs for `ch` without pixel grid
+    scaffold: bool,
+    /// Fixed font width
+    font_width: Option<u8>,
+    /// Cell size (height, width) of last glyph
+    cell: Option<(u8, u8)>,
 }
 
 impl<'p> Iterator for Parser<'p> {
@@ -69,37 +114,50 @@ impl<'p> Iterator for Parser<'p> {
     }
 }
 
+impl<'p> PropParser<'p> for Parser<'p> {
+    fn line_number(&self) -> Option<usize> {
+        Some(self.line)
+    }
+}
+
 impl<'p> Parser<'p> {
     /// Create a new `tfon` parser
     pub fn new(buf: &'p str) -> Self {
-        let lines = buf.lines();
-        Parser { lines, line: None }
-    }
-
-    /// Get the next line
-    fn next_line(&mut self) -> Option<&'p str> {
-        if self.line.is_some() {
-            self.line.take()
-        } else {
-            for line in self.lines.by_ref() {
-                if !line.is_empty() {
-                    return Some(line);
-                }
-            }
-            None
+        Parser {
+            lines: LineCursor::new(
+                buf,
+                LinePolicy {
+                    comments: &['#'],
+                    ..Default::default()
+                },
+            ),
+            line: 0,
+            bitmap: None,
+            scaffold: false,
+            font_width: None,
+            cell: None,
         }
     }
 
-    /// Push a line back
-    fn push_line(&mut self, line: &'p str) {
-        self.line = Some(line);
+    /// Enable scaffold mode
+    ///
+    /// A `ch` line without a pixel grid is parsed as a blank glyph, with the
+    /// same height as other glyphs.  Its width is the fixed font width, or
+    /// the width of the previous glyph.
+    pub fn scaffold(mut self) -> Self {
+        self.scaffold = true;
+        self
     }
 
     /// Parse one property
     fn prop(&mut self) -> Option<Prop<'p>> {
-        let line = self.next_line()?;
+        if let Some(bmap) = self.bitmap.take() {
+            return Some(Prop::Bitmap(bmap));
+        }
+        let line = self.lines.next_line()?;
+        self.line = self.lines.line_number();
         match line.split_once(": ") {
-            Some(("font_name", val)) => Some(Prop::FontName(val)),
+            Some(("font_name", val)) => Some(Prop::FontName(val.into())),
             Some(("font_number", val)) => {
                 u8::from_str(val).ok().map(Prop::FontNumber)
             }
@@ -109,40 +167,171 @@ impl<'p> Parser<'p> {
             Some(("line_spacing", val)) => {
                 u8::from_str(val).ok().map(Prop::LineSpacing)
             }
-            Some(("ch", val)) => {
-                val.split_once(' ').and_then(|(cp, symbol)| {
-                    u16::from_str(cp).ok().and_then(|cp| {
-                        if symbol == SYMBOL[usize::from(cp)] {
-                            Some(Prop::CodePoint(cp))
-                        } else {
-                            None
-                        }
-                    })
-                })
+            Some(("font_width", val)) => {
+                let fw = u8::from_str(val).ok()?;
+                self.font_width = Some(fw).filter(|fw| *fw > 0);
+                Some(Prop::FontWidth(fw))
             }
-            Some((key, _val)) => Some(Prop::Unknown(key)),
-            _ => self.character(line),
+            Some(("baseline", val)) => {
+                u8::from_str(val).ok().map(Prop::Baseline)
+            }
+            Some(("max_char_number", val)) => {
+                u16::from_str(val).ok().map(Prop::MaxCharNumber)
+            }
+            Some(("family", val)) => Some(Prop::Family(val.into())),
+            Some(("style", val)) => Some(Prop::Style(val.into())),
+            Some(("copyright", val)) => Some(Prop::Copyright(val.into())),
+            Some(("foundry", val)) => Some(Prop::Foundry(val.into())),
+            Some(("ligature", val)) => {
+                parse_ligature(val).or_else(|| Some(Prop::skipped(line)))
+            }
+            Some(("ch", val)) => Some(self.glyph(line, val)),
+            Some(("frame", "2")) => Some(self.frame(line)),
+            Some(("modified", val)) => match val.split_once(' ') {
+                Some((time, author)) => Some(Prop::Modified {
+                    time: time.into(),
+                    author: author.into(),
+                }),
+                None => Some(self.skip(line)),
+            },
+            Some((_key, _val)) => Some(Prop::Unknown(line)),
+            _ => Some(self.skip(line)),
         }
     }
 
-    /// Parse a bitmap character property
-    fn character(&mut self, line: &'p str) -> Option<Prop<'p>> {
-        let width = u8::try_from(line.len()).unwrap_or(0);
-        if width == 0 || !is_pixel_row(line) {
-            return None;
+    /// Parse a glyph block: a `ch` header followed by pixel rows
+    ///
+    /// If the block is malformed, it is skipped.
+    fn glyph(&mut self, line: &'p str, val: &str) -> Prop<'p> {
+        let cp = val.split_once(' ').and_then(|(cp, symbol)| {
+            u16::from_str(cp)
+                .ok()
+                .filter(|cp| SYMBOL.get(usize::from(*cp)) == Some(&symbol))
+        });
+        let bitmap = match self.bitmap() {
+            None if self.scaffold => self.blank(),
+            bitmap => bitmap,
+        };
+        // block must end at another header, or end of input
+        let ended = match self.lines.peek() {
+            Some(next) => next.contains(": "),
+            None => true,
+        };
+        match (cp, bitmap) {
+            (Some(cp), Some(bmap)) if ended => {
+                self.bitmap = Some(bmap);
+                Prop::CodePoint(cp)
+            }
+            _ => self.skip(line),
+        }
+    }
+
+    /// Parse a frame block: a `frame` header followed by pixel rows
+    fn frame(&mut self, line: &'p str) -> Prop<'p> {
+        match self.bitmap() {
+            Some(bmap) => Prop::Frame(bmap),
+            None => self.skip(line),
         }
+    }
+
+    /// Parse pixel rows of a bitmap
+    fn bitmap(&mut self) -> Option<Bitmap> {
+        let line = self.lines.next_line()?;
+        let Some(row) = pixel_row(line) else {
+            self.lines.push_back(line);
+            return None;
+        };
+        let Ok(width) = u8::try_from(row.len()) else {
+            self.lines.push_back(line);
+            return None;
+        };
         let mut bitmap = Bitmap::new(width);
-        bitmap.push_row(row_pixels(line));
+        bitmap.push_row(row_pixels(row));
         let width = usize::from(width);
-        while let Some(line) = self.next_line() {
-            if is_pixel_row(line) && line.len() == width {
-                bitmap.push_row(row_pixels(line));
-            } else {
-                self.push_line(line);
+        while let Some(line) = self.lines.next_line() {
+            let pushed = pixel_row(line)
+                .filter(|r| r.len() == width)
+                .is_some_and(|r| bitmap.try_push_row(row_pixels(r)).is_ok());
+            if !pushed {
+                self.lines.push_back(line);
                 break;
             }
         }
-        Some(Prop::Bitmap(bitmap))
+        self.cell = Some((bitmap.height, bitmap.width));
+        Some(bitmap)
+    }
+
+    /// Make a blank scaffold bitmap
+    ///
+    /// If no glyph has been parsed yet, the next one is used for its size.
+    fn blank(&self) -> Option<Bitmap> {
+        let (height, width) = self.cell.or_else(|| {
+            let mut lines = self.lines.remaining();
+            let row = lines.find_map(pixel_row)?;
+            let height = 1 + lines
+                .map_while(pixel_row)
+                .take_while(|r| r.len() == row.len())
+                .count();
+            Some((u8::try_from(height).ok()?, u8::try_from(row.len()).ok()?))
+        })?;
+        let width = self.font_width.unwrap_or(width);
+        Some(Bitmap::new_blank(height, width))
+    }
+
+    /// Skip a malformed block, up to the next `ch` header
+    fn skip(&mut self, line: &'p str) -> Prop<'p> {
+        while let Some(next) = self.lines.next_line() {
+            if next.starts_with("ch: ") {
+                self.lines.push_back(next);
+                break;
+            }
+        }
+        Prop::skipped(line)
+    }
+}
+
+/// Write header keys and ligatures missing from an exact source
+fn write_missing<'a, W: Write>(
+    mut writer: W,
+    header: &[(&'a str, String)],
+    keys: &mut HashSet<&'a str>,
+    added: &mut Option<Vec<(String, u32)>>,
+    eol: &str,
+) -> Result<()> {
+    for (key, val) in header {
+        if keys.insert(key) {
+            write!(writer, "{key}: {val}{eol}")?;
+        }
+    }
+    for (seq, cp) in added.take().into_iter().flatten() {
+        write!(writer, "ligature: {cp} {seq}{eol}")?;
+    }
+    Ok(())
+}
+
+/// Parse a `ligature` value, as an owned sequence and code point
+fn owned_ligature(val: &str) -> Option<(String, u32)> {
+    parse_ligature(val)?
+        .ligature()
+        .map(|(seq, cp)| (seq.to_string(), cp))
+}
+
+/// Parse a `ligature` value: code point and character sequence
+fn parse_ligature(val: &str) -> Option<Prop<'_>> {
+    let (cp, sequence) = val.split_once(' ')?;
+    let code_point = u32::from_str(cp).ok()?;
+    check_ligature(code_point).ok()?;
+    (!sequence.is_empty()).then_some(Prop::Ligature {
+        sequence: sequence.into(),
+        code_point,
+    })
+}
+
+/// Check that a ligature code point has a symbol
+fn check_ligature(cp: u32) -> Result<()> {
+    match usize::try_from(cp).ok().and_then(|cp| SYMBOL.get(cp)) {
+        Some(_) => Ok(()),
+        None => Err(Error::LigatureOutOfRange(cp)),
     }
 }
 
@@ -151,6 +340,12 @@ fn is_pixel_row(line: &str) -> bool {
     line.chars().all(|c| c == '.' || c == '@')
 }
 
+/// Get the pixels of a row, without any trailing comment
+fn pixel_row(line: &str) -> Option<&str> {
+    let row = line.split_once('#').map_or(line, |(row, _)| row).trim_end();
+    (!row.is_empty() && is_pixel_row(row)).then_some(row)
+}
+
 /// Parse a row into on/off pixels
 fn row_pixels(line: &str) -> impl Iterator<Item = bool> + '_ {
     line.chars().map(|c| c == '@')
@@ -158,52 +353,528 @@ fn row_pixels(line: &str) -> impl Iterator<Item = bool> + '_ {
 
 /// Write a font in `tfon` format
 pub fn write<'a, W: Write>(
-    mut writer: W,
+    writer: W,
     props: impl Iterator<Item = Prop<'a>>,
 ) -> Result<()> {
+    write_with(writer, props, &WriteOptions::default())?;
+    Ok(())
+}
+
+/// Write a font in `tfon` format, with options
+///
+/// Unknown `key: value` properties can be kept; any others are dropped.
+/// Blank scaffold glyphs can be added for missing code points in a range.
+#[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+pub fn write_with<'a, W: Write>(
+    mut writer: W,
+    props: impl Iterator<Item = Prop<'a>>,
+    options: &WriteOptions,
+) -> Result<WriteReport> {
+    let ctx = ParseContext::write("tfon");
+    let mut report = WriteReport::default();
     let props: Vec<_> = props.collect();
     let font_name = props
         .iter()
         .find_map(|v| v.font_name())
-        .ok_or(Error::Expected("font_name"))?;
+        .ok_or_else(|| ctx.wrap(Error::Expected("font_name")))?;
     let font_number = props.iter().find_map(|v| v.font_number()).unwrap_or(1);
     let char_spacing = props.iter().find_map(|v| v.char_spacing()).unwrap_or(0);
     let line_spacing = props.iter().find_map(|v| v.line_spacing()).unwrap_or(0);
+    let font_width = props.iter().find_map(|v| v.font_width());
+    let baseline = props.iter().find_map(|v| v.baseline());
     writeln!(writer, "font_name: {font_name:.64}")?;
     writeln!(writer, "font_number: {font_number}")?;
     writeln!(writer, "char_spacing: {char_spacing}")?;
     writeln!(writer, "line_spacing: {line_spacing}")?;
+    if let Some(font_width) = font_width {
+        writeln!(writer, "font_width: {font_width}")?;
+    }
+    if let Some(baseline) = baseline {
+        writeln!(writer, "baseline: {baseline}")?;
+    }
+    if let Some(mx) = props.iter().find_map(|v| v.max_char_number()) {
+        writeln!(writer, "max_char_number: {mx}")?;
+    }
+    if let Some(family) = props.iter().find_map(|v| v.family()) {
+        writeln!(writer, "family: {family}")?;
+    }
+    if let Some(style) = props.iter().find_map(|v| v.style()) {
+        writeln!(writer, "style: {style}")?;
+    }
+    if let Some(copyright) = props.iter().find_map(|v| v.copyright()) {
+        writeln!(writer, "copyright: {copyright}")?;
+    }
+    if let Some(foundry) = props.iter().find_map(|v| v.foundry()) {
+        writeln!(writer, "foundry: {foundry}")?;
+    }
+    for (sequence, code_point) in props.iter().filter_map(|v| v.ligature()) {
+        check_ligature(code_point)
+            .map_err(|e| ctx.at("Ligature", None).wrap(e))?;
+        writeln!(writer, "ligature: {code_point} {sequence}")?;
+    }
+    let scaffold = scaffold_bitmap(&props, font_width);
+    let mut scaffolds = scaffold_code_points(&props, options).peekable();
     let mut ch = true;
+    let mut framed = true;
+    let mut stamped = true;
+    let mut code_point = None;
     for prop in props {
+        let at = ctx.at(prop.kind(), code_point);
         match prop {
             Prop::CodePoint(cp) => match SYMBOL.get(usize::from(cp)) {
                 Some(symbol) => {
+                    while let Some(sc) = scaffolds.next_if(|sc| *sc < cp) {
+                        write_scaffold(&mut writer, sc, &scaffold, options)?;
+                    }
                     ch = false;
+                    framed = true;
+                    stamped = true;
+                    code_point = Some(cp);
                     writeln!(writer)?;
                     writeln!(writer, "ch: {cp} {symbol}")?;
                 }
-                _ => return Err(Error::Expected("ch")),
+                _ => {
+                    let at = ctx.at(prop.kind(), Some(cp));
+                    return Err(at.wrap(Error::Expected("ch")));
+                }
             },
             Prop::Bitmap(bmap) => {
                 if ch {
-                    return Err(Error::Expected("ch"));
+                    return Err(at.misplaced("ch"));
                 }
                 ch = true;
-                let mut col = 0;
-                for pix in bmap.pixels() {
-                    if pix {
-                        write!(writer, "@")?;
+                framed = false;
+                stamped = false;
+                let cp = code_point.unwrap_or(0);
+                bmap.check_width(font_width.unwrap_or(0), cp)
+                    .map_err(|e| at.wrap(e))?;
+                write_bitmap(&mut writer, &bmap, "\n", options.guides)?;
+            }
+            Prop::Frame(bmap) => {
+                if framed {
+                    return Err(at.misplaced("ch"));
+                }
+                framed = true;
+                writeln!(writer, "frame: 2")?;
+                write_bitmap(&mut writer, &bmap, "\n", options.guides)?;
+            }
+            Prop::Modified { time, author } => {
+                if stamped {
+                    return Err(at.misplaced("ch"));
+                }
+                stamped = true;
+                framed = true;
+                writeln!(writer, "modified: {time} {author}")?;
+            }
+            Prop::Unknown(line)
+                if options.keep_unknown && line.contains(": ") =>
+            {
+                writeln!(writer, "{line}")?;
+            }
+            Prop::Unknown(_) => report.drop_unknown(&prop),
+            _ => (),
+        }
+    }
+    for sc in scaffolds {
+        write_scaffold(&mut writer, sc, &scaffold, options)?;
+    }
+    Ok(report)
+}
+
+/// Make a blank bitmap for scaffold glyphs
+///
+/// The width is the fixed font width, or the widest glyph.
+fn scaffold_bitmap(props: &[Prop], font_width: Option<u8>) -> Bitmap {
+    let height = props.iter().find_map(|v| v.font_height()).unwrap_or(0);
+    let width = font_width.filter(|fw| *fw > 0).unwrap_or_else(|| {
+        props
+            .iter()
+            .filter_map(|v| match v {
+                Prop::Bitmap(bmap) => Some(bmap.width),
+                _ => None,
+            })
+            .max()
+            .unwrap_or(0)
+    });
+    Bitmap::new_blank(height, width)
+}
+
+/// Get missing code points to write scaffold glyphs for
+fn scaffold_code_points(
+    props: &[Prop],
+    options: &WriteOptions,
+) -> impl Iterator<Item = u16> {
+    let present: HashSet<u16> =
+        props.iter().filter_map(|v| v.code_point()).collect();
+    let (lo, hi) = options.scaffold.unwrap_or((1, 0));
+    (lo..=hi).filter(move |cp| {
+        !present.contains(cp) && SYMBOL.get(usize::from(*cp)).is_some()
+    })
+}
+
+/// Write a blank scaffold glyph
+fn write_scaffold<W: Write>(
+    writer: &mut W,
+    cp: u16,
+    bmap: &Bitmap,
+    options: &WriteOptions,
+) -> Result<()> {
+    let symbol = SYMBOL.get(usize::from(cp)).ok_or(Error::Expected("ch"))?;
+    writeln!(writer)?;
+    writeln!(writer, "ch: {cp} {symbol}")?;
+    write_bitmap(writer, bmap, "\n", options.guides)
+}
+
+/// Parse a `ch` value into a code point
+fn parse_ch(val: &str) -> Option<u16> {
+    val.split_once(' ')
+        .and_then(|(cp, _symbol)| u16::from_str(cp).ok())
+}
+
+/// Write a pixel grid
+///
+/// With guides, a ruler comment is written above the grid, and each row
+/// has its number as a trailing comment.
+fn write_bitmap<W: Write>(
+    writer: &mut W,
+    bmap: &Bitmap,
+    eol: &str,
+    guides: bool,
+) -> Result<()> {
+    if guides {
+        let ruler: String =
+            (0..bmap.width).map(|x| char::from(b'0' + x % 10)).collect();
+        write!(writer, "# {ruler}{eol}")?;
+    }
+    for y in 0..bmap.height {
+        for x in 0..bmap.width {
+            if bmap.pixel(x, y) {
+                write!(writer, "@")?;
+            } else {
+                write!(writer, ".")?;
+            }
+        }
+        if guides {
+            write!(writer, " # {y}")?;
+        }
+        write!(writer, "{eol}")?;
+    }
+    Ok(())
+}
+
+/// Rewrite a `tfon` source in canonical form
+///
+/// Header properties are written in a fixed order, followed by unknown
+/// `key: value` properties (sorted), then glyphs sorted by code point.
+/// Trailing spaces and comments are removed.  Rewriting canonical output
+/// gives the same result, so this can be used as a git clean filter.
+///
+/// Malformed blocks are an error, rather than being dropped.
+pub fn write_canonical<W: Write>(writer: W, buf: &str) -> Result<()> {
+    let trimmed: String = buf
+        .lines()
+        .flat_map(|line| [line.trim_end(), "\n"])
+        .collect();
+    let mut parser = Parser::new(&trimmed);
+    let mut props = Vec::new();
+    while let Some(prop) = parser.next() {
+        if let Prop::Skipped(_) = prop {
+            let ctx = ParseContext::parse("tfon").line(parser.line);
+            return Err(ctx.at("Skipped", None).wrap(Error::Expected("ch")));
+        }
+        props.push(prop);
+    }
+    let mut unknown: Vec<&str> = props
+        .iter()
+        .filter_map(|prop| match prop {
+            Prop::Unknown(line) => Some(*line),
+            _ => None,
+        })
+        .collect();
+    unknown.sort_unstable();
+    unknown.dedup();
+    let font = Font::from_props(props.iter().cloned())?;
+    let props: Vec<_> = font.props().collect();
+    let glyphs = props
+        .iter()
+        .position(|p| matches!(p, Prop::CodePoint(_)))
+        .unwrap_or(props.len());
+    let (header, glyphs) = props.split_at(glyphs);
+    let props = header
+        .iter()
+        .cloned()
+        .chain(unknown.into_iter().map(Prop::Unknown))
+        .chain(glyphs.iter().cloned());
+    let options = WriteOptions {
+        keep_unknown: true,
+        ..Default::default()
+    };
+    write_with(writer, props, &options)?;
+    Ok(())
+}
+
+/// Make a bitmap from source pixel rows (ignoring comment lines)
+///
+/// Returns `None` if any row doesn't match the width, or there are too many.
+fn source_bitmap(rows: &[&str], width: u8) -> Option<Bitmap> {
+    let mut bitmap = Bitmap::new(width);
+    for row in rows {
+        if let Some(row) = pixel_row(row.trim_end_matches(['\r', '\n'])) {
+            if row.len() != usize::from(width) {
+                return None;
+            }
+            bitmap.try_push_row(row_pixels(row)).ok()?;
+        }
+    }
+    (bitmap.height() > 0).then_some(bitmap)
+}
+
+/// Glyph for exact writing: code point, bitmap, frame and `modified` line
+type ExactGlyph = (u16, Bitmap, Option<Bitmap>, Option<String>);
+
+/// Write a font in `tfon` format, preserving the formatting of a source
+///
+/// Source lines are copied byte-for-byte, except for header values and
+/// glyphs which differ from `props`.  Comments, unknown keys and glyph
+/// ordering are preserved.  Glyphs missing from `props` are removed, and
+/// new glyphs are appended.
+pub fn write_exact<'a, W: Write>(
+    mut writer: W,
+    source: &str,
+    props: impl Iterator<Item = Prop<'a>>,
+) -> Result<()> {
+    let props: Vec<_> = props.collect();
+    let eol = if source.contains("\r\n") {
+        "\r\n"
+    } else {
+        "\n"
+    };
+    let mut header: Vec<(&str, String)> = Vec::new();
+    if let Some(name) = props.iter().find_map(|v| v.font_name()) {
+        header.push(("font_name", format!("{name:.64}")));
+    }
+    if let Some(num) = props.iter().find_map(|v| v.font_number()) {
+        header.push(("font_number", num.to_string()));
+    }
+    if let Some(cs) = props.iter().find_map(|v| v.char_spacing()) {
+        header.push(("char_spacing", cs.to_string()));
+    }
+    if let Some(ls) = props.iter().find_map(|v| v.line_spacing()) {
+        header.push(("line_spacing", ls.to_string()));
+    }
+    let font_width = props.iter().find_map(|v| v.font_width());
+    if let Some(fw) = font_width {
+        header.push(("font_width", fw.to_string()));
+    }
+    if let Some(bl) = props.iter().find_map(|v| v.baseline()) {
+        header.push(("baseline", bl.to_string()));
+    }
+    if let Some(mx) = props.iter().find_map(|v| v.max_char_number()) {
+        header.push(("max_char_number", mx.to_string()));
+    }
+    if let Some(family) = props.iter().find_map(|v| v.family()) {
+        header.push(("family", family.to_string()));
+    }
+    if let Some(style) = props.iter().find_map(|v| v.style()) {
+        header.push(("style", style.to_string()));
+    }
+    if let Some(copyright) = props.iter().find_map(|v| v.copyright()) {
+        header.push(("copyright", copyright.to_string()));
+    }
+    if let Some(foundry) = props.iter().find_map(|v| v.foundry()) {
+        header.push(("foundry", foundry.to_string()));
+    }
+    let ligatures: Vec<_> = props
+        .iter()
+        .filter_map(|v| v.ligature())
+        .map(|(seq, cp)| (seq.to_string(), cp))
+        .collect();
+    // ligatures missing from source
+    let mut added: Option<Vec<_>> = Some(
+        ligatures
+            .iter()
+            .filter(|lig| {
+                !source.lines().any(|line| {
+                    line.trim_end_matches('\r')
+                        .strip_prefix("ligature: ")
+                        .and_then(owned_ligature)
+                        .as_ref()
+                        == Some(*lig)
+                })
+            })
+            .cloned()
+            .collect(),
+    );
+    let ctx = ParseContext::write("tfon");
+    for (_seq, cp) in &ligatures {
+        check_ligature(*cp).map_err(|e| ctx.at("Ligature", None).wrap(e))?;
+    }
+    let mut glyphs: Vec<ExactGlyph> = Vec::new();
+    let mut code_point = None;
+    for prop in props {
+        let at = ctx.at(prop.kind(), code_point.or(glyphs.last().map(|g| g.0)));
+        match prop {
+            Prop::CodePoint(cp) => code_point = Some(cp),
+            Prop::Bitmap(bmap) => {
+                let cp = code_point.take().ok_or_else(|| at.misplaced("ch"))?;
+                bmap.check_width(font_width.unwrap_or(0), cp)
+                    .map_err(|e| at.wrap(e))?;
+                glyphs.push((cp, bmap, None, None));
+            }
+            Prop::Frame(bmap) => match glyphs.last_mut() {
+                Some((_cp, _bmap, frame, None)) if code_point.is_none() => {
+                    *frame = Some(bmap);
+                }
+                _ => return Err(at.misplaced("ch")),
+            },
+            Prop::Modified { time, author } => match glyphs.last_mut() {
+                Some((_cp, _bmap, _frame, stamp)) if code_point.is_none() => {
+                    *stamp = Some(format!("modified: {time} {author}"));
+                }
+                _ => return Err(at.misplaced("ch")),
+            },
+            _ => (),
+        }
+    }
+    let mut written = HashSet::new();
+    let mut keys = HashSet::new();
+    let mut lines = source.split_inclusive('\n').peekable();
+    while let Some(raw) = lines.next() {
+        let line = raw.trim_end_matches(['\r', '\n']);
+        match line.split_once(": ") {
+            Some(("ch", val)) => {
+                write_missing(
+                    &mut writer,
+                    &header,
+                    &mut keys,
+                    &mut added,
+                    eol,
+                )?;
+                // pixel rows, with any comments above them
+                let mut rows = Vec::new();
+                let mut grid = false;
+                while let Some(row) = lines.next_if(|r| {
+                    let r = r.trim_end_matches(['\r', '\n']);
+                    pixel_row(r).is_some() || (!grid && r.starts_with('#'))
+                }) {
+                    grid |= !row.starts_with('#');
+                    rows.push(row);
+                }
+                // second frame, with its header line
+                let mut frame_rows = Vec::new();
+                if let Some(fr) = lines
+                    .next_if(|r| r.trim_end_matches(['\r', '\n']) == "frame: 2")
+                {
+                    frame_rows.push(fr);
+                    while let Some(row) = lines.next_if(|r| {
+                        pixel_row(r.trim_end_matches(['\r', '\n'])).is_some()
+                    }) {
+                        frame_rows.push(row);
+                    }
+                }
+                // last change, replaced if different
+                let stamp_row = lines.next_if(|r| r.starts_with("modified: "));
+                let Some(cp) = parse_ch(val) else {
+                    writer.write_all(raw.as_bytes())?;
+                    for row in rows.iter().chain(&frame_rows).chain(&stamp_row)
+                    {
+                        writer.write_all(row.as_bytes())?;
+                    }
+                    continue;
+                };
+                let Some((_cp, bmap, frame, stamp)) =
+                    glyphs.iter().find(|(c, _, _, _)| *c == cp)
+                else {
+                    continue;
+                };
+                written.insert(cp);
+                writer.write_all(raw.as_bytes())?;
+                let src = source_bitmap(&rows, bmap.width());
+                if src.as_ref() == Some(bmap) {
+                    for row in rows {
+                        writer.write_all(row.as_bytes())?;
+                    }
+                } else {
+                    let guides = rows.iter().any(|r| r.starts_with('#'));
+                    write_bitmap(&mut writer, bmap, eol, guides)?;
+                }
+                if let Some(frame) = frame {
+                    let src = source_bitmap(
+                        frame_rows.get(1..).unwrap_or_default(),
+                        frame.width(),
+                    );
+                    if src.as_ref() == Some(frame) {
+                        for row in frame_rows {
+                            writer.write_all(row.as_bytes())?;
+                        }
                     } else {
-                        write!(writer, ".")?;
+                        write!(writer, "frame: 2{eol}")?;
+                        write_bitmap(&mut writer, frame, eol, false)?;
                     }
-                    col += 1;
-                    if col >= bmap.width {
-                        writeln!(writer)?;
-                        col = 0;
+                }
+                if let Some(stamp) = stamp {
+                    match stamp_row {
+                        Some(row)
+                            if row.trim_end_matches(['\r', '\n']) == stamp =>
+                        {
+                            writer.write_all(row.as_bytes())?;
+                        }
+                        _ => write!(writer, "{stamp}{eol}")?,
                     }
                 }
             }
-            _ => (),
+            Some(("ligature", val)) => {
+                if owned_ligature(val).is_some_and(|l| ligatures.contains(&l)) {
+                    writer.write_all(raw.as_bytes())?;
+                }
+            }
+            Some((key, val)) => match header.iter().find(|(k, _)| *k == key) {
+                Some((key, new)) => {
+                    keys.insert(*key);
+                    let same = match *key {
+                        "font_name" | "family" | "style" | "copyright"
+                        | "foundry" => val == new,
+                        _ => u16::from_str(val).ok() == u16::from_str(new).ok(),
+                    };
+                    if same {
+                        writer.write_all(raw.as_bytes())?;
+                    } else {
+                        write!(writer, "{key}: {new}{eol}")?;
+                    }
+                }
+                None => writer.write_all(raw.as_bytes())?,
+            },
+            None => {
+                // keep the blank line between header and first glyph
+                if line.is_empty()
+                    && lines.peek().is_some_and(|r| r.starts_with("ch: "))
+                {
+                    write_missing(
+                        &mut writer,
+                        &header,
+                        &mut keys,
+                        &mut added,
+                        eol,
+                    )?;
+                }
+                writer.write_all(raw.as_bytes())?;
+            }
+        }
+    }
+    write_missing(&mut writer, &header, &mut keys, &mut added, eol)?;
+    for (cp, bmap, frame, stamp) in &glyphs {
+        if written.insert(*cp) {
+            let symbol =
+                SYMBOL.get(usize::from(*cp)).ok_or(Error::Expected("ch"))?;
+            write!(writer, "{eol}ch: {cp} {symbol}{eol}")?;
+            write_bitmap(&mut writer, bmap, eol, false)?;
+            if let Some(frame) = frame {
+                write!(writer, "frame: 2{eol}")?;
+                write_bitmap(&mut writer, frame, eol, false)?;
+            }
+            if let Some(stamp) = stamp {
+                write!(writer, "{stamp}{eol}")?;
+            }
         }
     }
     Ok(())
@@ -0,0 +1,303 @@
+//! Export fonts in u8g2 compressed format
+//!
+//! This is the format produced by `bdfconv` for the u8g2 library.  A
+//! 23-byte header is followed by glyphs, each with run-length encoded
+//! pixels in a least-significant-bit-first bit stream.  Code points 256
+//! and above are stored in a separate unicode section.
+use crate::codegen;
+use crate::common::{Bitmap, Error, ParseContext, Result};
+use crate::font::Font;
+use std::io::Write;
+
+/// Size of font header
+const HEADER_SIZE: usize = 23;
+
+/// Encoded size of each glyph (code point, bytes)
+pub(crate) type GlyphSizes = Vec<(u16, usize)>;
+
+/// Bit stream writer (least significant bit first)
+#[derive(Default)]
+struct BitWriter {
+    /// Completed bytes
+    bytes: Vec<u8>,
+    /// Number of bits used in last byte
+    used: u8,
+}
+
+impl BitWriter {
+    /// Write an unsigned value
+    fn unsigned(&mut self, val: u32, bits: u8) {
+        for i in 0..bits {
+            if self.used == 0 {
+                self.bytes.push(0);
+            }
+            if val & (1 << i) != 0 {
+                *self.bytes.last_mut().unwrap() |= 1 << self.used;
+            }
+            self.used = (self.used + 1) % 8;
+        }
+    }
+
+    /// Write a signed value (offset by half the range)
+    fn signed(&mut self, val: i32, bits: u8) {
+        self.unsigned((val + (1 << (bits - 1))) as u32, bits);
+    }
+}
+
+/// Glyph bounding box and advance
+struct Glyph {
+    /// Code point
+    cp: u16,
+    /// Ink bitmap
+    ink: Bitmap,
+    /// Left offset
+    x: i32,
+    /// Bottom offset, above baseline
+    y: i32,
+    /// Cursor advance
+    delta: i32,
+}
+
+/// Field sizes (bits) for glyph data
+#[derive(Clone, Copy)]
+struct Fields {
+    /// Bits per run of 0 pixels
+    m0: u8,
+    /// Bits per run of 1 pixels
+    m1: u8,
+    /// Bits per width
+    width: u8,
+    /// Bits per height
+    height: u8,
+    /// Bits per x offset
+    x: u8,
+    /// Bits per y offset
+    y: u8,
+    /// Bits per advance
+    delta: u8,
+}
+
+/// Get bits needed for an unsigned value
+fn unsigned_bits(val: u32) -> u8 {
+    (32 - val.leading_zeros()) as u8
+}
+
+/// Get bits needed for a signed value
+fn signed_bits(val: i32) -> u8 {
+    let mut bits = 1;
+    while val < -(1 << (bits - 1)) || val >= 1 << (bits - 1) {
+        bits += 1;
+    }
+    bits
+}
+
+/// Get runs of (0 pixels, 1 pixels), limited by field sizes
+fn runs(ink: &Bitmap, m0: u8, m1: u8) -> Vec<(u32, u32)> {
+    let max0 = (1 << m0) - 1;
+    let max1 = (1 << m1) - 1;
+    let mut pixels = ink.pixels().peekable();
+    let mut runs = Vec::new();
+    while pixels.peek().is_some() {
+        let mut a = 0;
+        while a < max0 && pixels.next_if(|p| !p).is_some() {
+            a += 1;
+        }
+        let mut b = 0;
+        while b < max1 && pixels.next_if(|p| *p).is_some() {
+            b += 1;
+        }
+        runs.push((a, b));
+    }
+    runs
+}
+
+impl Glyph {
+    /// Encode glyph data (without encoding / size prefix)
+    fn encode(&self, f: Fields) -> Vec<u8> {
+        let mut bits = BitWriter::default();
+        bits.unsigned(u32::from(self.ink.width()), f.width);
+        bits.unsigned(u32::from(self.ink.height()), f.height);
+        bits.signed(self.x, f.x);
+        bits.signed(self.y, f.y);
+        bits.signed(self.delta, f.delta);
+        if self.ink.width() > 0 {
+            let runs = runs(&self.ink, f.m0, f.m1);
+            let mut i = 0;
+            while i < runs.len() {
+                let (a, b) = runs[i];
+                bits.unsigned(a, f.m0);
+                bits.unsigned(b, f.m1);
+                i += 1;
+                // repeat flags for identical runs
+                while i < runs.len() && runs[i] == (a, b) {
+                    bits.unsigned(1, 1);
+                    i += 1;
+                }
+                bits.unsigned(0, 1);
+            }
+        }
+        bits.bytes
+    }
+
+    /// Get ascent (top of ink above baseline)
+    fn ascent(&self) -> i32 {
+        self.y + i32::from(self.ink.height())
+    }
+}
+
+/// Encode a font in u8g2 format
+pub fn encode(font: &Font) -> Result<Vec<u8>> {
+    encode_sized(font).map(|(out, _sizes)| out)
+}
+
+/// Encode a font in u8g2 format, with the size of each glyph
+pub(crate) fn encode_sized(font: &Font) -> Result<(Vec<u8>, GlyphSizes)> {
+    let ctx = ParseContext::write("u8g2");
+    let too_large =
+        |cp| ctx.at("Bitmap", Some(cp)).wrap(Error::GlyphTooLarge(cp));
+    let baseline = i32::from(font.baseline());
+    let glyphs: Vec<Glyph> = font
+        .glyphs()
+        .map(|(cp, bmap)| {
+            let bounds = bmap.ink_bounds().unwrap_or_default();
+            let bottom = i32::from(bounds.y) + i32::from(bounds.height);
+            Glyph {
+                cp,
+                ink: bmap.crop(bounds),
+                x: i32::from(bounds.x),
+                y: if bounds.height > 0 {
+                    baseline - bottom
+                } else {
+                    0
+                },
+                delta: i32::from(bmap.width()) + i32::from(font.char_spacing()),
+            }
+        })
+        .collect();
+    let glyph_cnt =
+        u8::try_from(glyphs.len()).map_err(|_| ctx.wrap(Error::TooLarge()))?;
+    let max_w = glyphs.iter().map(|g| g.ink.width()).max().unwrap_or(0);
+    let max_h = glyphs.iter().map(|g| g.ink.height()).max().unwrap_or(0);
+    let mut fields = Fields {
+        m0: 1,
+        m1: 1,
+        width: unsigned_bits(u32::from(max_w)),
+        height: unsigned_bits(u32::from(max_h)),
+        x: glyphs.iter().map(|g| signed_bits(g.x)).max().unwrap_or(1),
+        y: glyphs.iter().map(|g| signed_bits(g.y)).max().unwrap_or(1),
+        delta: glyphs
+            .iter()
+            .map(|g| signed_bits(g.delta))
+            .max()
+            .unwrap_or(1),
+    };
+    // choose run length field sizes producing the smallest font
+    let mut best = usize::MAX;
+    for m0 in 1..=7 {
+        for m1 in 1..=7 {
+            let f = Fields { m0, m1, ..fields };
+            let size = glyphs.iter().map(|g| g.encode(f).len()).sum();
+            if size < best {
+                best = size;
+                fields = f;
+            }
+        }
+    }
+    // font bounding box
+    let x_off = glyphs.iter().map(|g| g.x).min().unwrap_or(0);
+    let y_off = glyphs.iter().map(|g| g.y).min().unwrap_or(0);
+    let right = glyphs
+        .iter()
+        .map(|g| g.x + i32::from(g.ink.width()))
+        .max()
+        .unwrap_or(0);
+    let top = glyphs.iter().map(|g| g.ascent()).max().unwrap_or(0);
+    let metric = |c: char, f: fn(&Glyph) -> i32| {
+        glyphs
+            .iter()
+            .find(|g| u32::from(g.cp) == u32::from(c))
+            .map_or(0, f)
+    };
+    let mut data = Vec::new();
+    let mut sizes = Vec::with_capacity(glyphs.len());
+    let mut start_upper_a = None;
+    let mut start_lower_a = None;
+    for g in glyphs.iter().filter(|g| g.cp < 256) {
+        if g.cp >= u16::from(b'A') && start_upper_a.is_none() {
+            start_upper_a = Some(data.len());
+        }
+        if g.cp >= u16::from(b'a') && start_lower_a.is_none() {
+            start_lower_a = Some(data.len());
+        }
+        let bytes = g.encode(fields);
+        let size =
+            u8::try_from(bytes.len() + 2).map_err(|_| too_large(g.cp))?;
+        sizes.push((g.cp, usize::from(size)));
+        data.push(g.cp as u8);
+        data.push(size);
+        data.extend(bytes);
+    }
+    let end = data.len();
+    data.extend([0, 0]);
+    let start_unicode = data.len();
+    // one lookup table entry, covering all unicode glyphs
+    data.extend([0, 4, 0xFF, 0xFF]);
+    for g in glyphs.iter().filter(|g| g.cp >= 256) {
+        let bytes = g.encode(fields);
+        let size =
+            u8::try_from(bytes.len() + 3).map_err(|_| too_large(g.cp))?;
+        sizes.push((g.cp, usize::from(size)));
+        data.extend(g.cp.to_be_bytes());
+        data.push(size);
+        data.extend(bytes);
+    }
+    data.extend([0, 0]);
+    let pos = |p: Option<usize>| -> Result<[u8; 2]> {
+        let p = u16::try_from(p.unwrap_or(end))
+            .map_err(|_| ctx.wrap(Error::TooLarge()))?;
+        Ok(p.to_be_bytes())
+    };
+    let byte = |v: i32| v as u8;
+    let mut out = Vec::with_capacity(HEADER_SIZE + data.len());
+    out.extend([
+        glyph_cnt,
+        0, // proportional bounding box mode
+        fields.m0,
+        fields.m1,
+        fields.width,
+        fields.height,
+        fields.x,
+        fields.y,
+        fields.delta,
+        byte(right - x_off),
+        byte(top - y_off),
+        byte(x_off),
+        byte(y_off),
+        byte(metric('A', Glyph::ascent)),
+        byte(metric('g', |g| g.y)),
+        byte(metric('(', Glyph::ascent)),
+        byte(metric('(', |g| g.y)),
+    ]);
+    out.extend(pos(start_upper_a)?);
+    out.extend(pos(start_lower_a)?);
+    out.extend(pos(Some(start_unicode))?);
+    out.extend(data);
+    Ok((out, sizes))
+}
+
+/// Write a font in u8g2 format as a C array
+pub fn write_c<W: Write>(mut writer: W, font: &Font, name: &str) -> Result<()> {
+    let id = codegen::ident(name);
+    let data = encode(font)?;
+    writeln!(writer, "// {}", font.name())?;
+    writeln!(
+        writer,
+        "const uint8_t {id}[{}] U8G2_FONT_SECTION(\"{id}\") = {{",
+        data.len()
+    )?;
+    for chunk in data.chunks(12) {
+        writeln!(writer, "    {},", codegen::hex_list(chunk))?;
+    }
+    writeln!(writer, "}};")?;
+    Ok(())
+}
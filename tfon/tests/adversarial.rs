@@ -0,0 +1,89 @@
+use tfon::fixtures::FIXTURES;
+use tfon::fuzz_utils::{exercise, INTERESTING};
+
+/// Deterministic pseudo-random number generator
+struct Lcg(u64);
+
+impl Lcg {
+    fn next(&mut self) -> usize {
+        self.0 = self
+            .0
+            .wrapping_mul(6_364_136_223_846_793_005)
+            .wrapping_add(1_442_695_040_888_963_407);
+        (self.0 >> 33) as usize
+    }
+}
+
+/// Make a tall or wide glyph block in each readable format
+fn oversized() -> Vec<String> {
+    let tall_rows = ".@\n".repeat(300);
+    let wide_row = "@".repeat(300);
+    let ifnt_rows: String =
+        (1..=300).map(|r| format!("row{r:02}= X .\n")).collect();
+    vec![
+        format!("font_name: tall\n\nch: 65 A\n{tall_rows}"),
+        format!("font_name: wide\n\nch: 65 A\n{wide_row}\n{wide_row}\n"),
+        format!("font_name: x\nfont_width: 0\n\nch: 65535 A\n@\n"),
+        format!("font_name: x\n\nch: 65 A\n@\nframe: 2\n{tall_rows}"),
+        "font_name: x\nmodified: now\nligature: 99999 ab\n".to_string(),
+        format!(
+            "[FontInfo]\nFontName=x\nFontHeight=300\nMaxCharNumber=65\n\n\
+             [Char_65]\n{ifnt_rows}"
+        ),
+        "[FontInfo]\nFontHeight=99999\n[Char_99999]\nrow01=X\n".to_string(),
+        format!("name: x\nheight: 300\nwidth: 2\n\nch: 65 A\n{tall_rows}"),
+        format!(
+            "STARTFONT 2.1\nFONT x\nSIZE 300 75 75\nSTARTCHAR A\n\
+             ENCODING 65\nDWIDTH 300 0\nBBX 300 300 0 0\nBITMAP\n{}\
+             ENDCHAR\n",
+            format!("{}\n", "FF".repeat(40)).repeat(300)
+        ),
+        "STARTFONT 2.1\nFONT x\nSIZE 8 75 75\nSTARTCHAR A\nENCODING 99999\n\
+         DWIDTH 8 0\nBBX -9 -9 -300 300\nBITMAP\nZZ\nENDCHAR\n"
+            .to_string(),
+        r#"<font name="x" height="300" width="300"><glyph code_point="65" width="300" pixels="////"/></font>"#.to_string(),
+        r#"<font name="x" height="0" width="0"><glyph code_point="65535" width="255" pixels="===="/><glyph "#.to_string(),
+        "<!-- <font name='".to_string(),
+        "<".to_string(),
+        "<!-->".to_string(),
+    ]
+}
+
+/// Make adversarial inputs from fixtures: truncations and mutations
+fn mutations() -> Vec<String> {
+    let mut rng = Lcg(1470);
+    let mut inputs = Vec::new();
+    for (_name, src) in FIXTURES {
+        let step = src.len() / 31 + 1;
+        for end in (0..src.len()).step_by(step) {
+            if src.is_char_boundary(end) {
+                inputs.push(src[..end].to_string());
+            }
+        }
+        for _ in 0..50 {
+            let mut bytes = src.as_bytes().to_vec();
+            for _ in 0..1 + rng.next() % 8 {
+                let pos = rng.next() % bytes.len();
+                bytes[pos] = INTERESTING[rng.next() % INTERESTING.len()];
+            }
+            if let Ok(text) = String::from_utf8(bytes) {
+                inputs.push(text);
+            }
+        }
+    }
+    inputs
+}
+
+#[test]
+fn oversized_glyphs() {
+    for input in oversized() {
+        exercise(&input);
+    }
+}
+
+#[test]
+fn mutated_fixtures() {
+    for input in mutations() {
+        exercise(&input);
+    }
+}
@@ -0,0 +1,40 @@
+use tfon::{Bitmap, Error};
+
+const ARROW: &str = "\
+..@..
+.@@@.
+@.@.@
+..@..
+..@..
+";
+
+#[test]
+fn round_trip() {
+    let bmap = Bitmap::from_art(ARROW).unwrap();
+    assert_eq!(bmap.height(), 5);
+    assert_eq!(bmap.width(), 5);
+    assert!(bmap.pixel(2, 0));
+    assert!(!bmap.pixel(0, 0));
+    assert_eq!(bmap.to_art(), ARROW);
+}
+
+#[test]
+fn comments_and_indent() {
+    let art = "# arrow\n    ..@..\n\n    .@@@.\n";
+    let bmap = Bitmap::from_art(art).unwrap();
+    assert_eq!(bmap.to_art(), "..@..\n.@@@.\n");
+}
+
+#[test]
+fn invalid_art() {
+    assert!(matches!(
+        Bitmap::from_art("..@\n.@\n"),
+        Err(Error::RaggedRows())
+    ));
+    assert!(matches!(
+        Bitmap::from_art("..X\n"),
+        Err(Error::InvalidPixel('X'))
+    ));
+    let tall = ".\n".repeat(256);
+    assert!(matches!(Bitmap::from_art(&tall), Err(Error::TooLarge())));
+}
@@ -0,0 +1,69 @@
+use tfon::fixtures;
+use tfon::{Font, Format, Prop};
+
+/// Insert property lines into the digits fixture
+fn with_properties(lines: &str) -> String {
+    let src = fixtures::source("digits3x5.bdf").unwrap();
+    src.replace("FONT_ASCENT 5\n", &format!("{lines}FONT_ASCENT 5\n"))
+}
+
+#[test]
+fn family_style() {
+    let src = with_properties("FAMILY_NAME \"Digits\"\nWEIGHT_NAME \"Bold\"\n");
+    let font = Font::from_props(Format::Bdf.parse(&src).unwrap()).unwrap();
+    assert_eq!(font.family(), Some("Digits"));
+    assert_eq!(font.style(), "Bold");
+}
+
+#[test]
+fn unquoted_family_style() {
+    let src = with_properties("FAMILY_NAME Digits\nWEIGHT_NAME Bold\n");
+    let props: Vec<_> = Format::Bdf.parse(&src).unwrap().collect();
+    assert!(props.contains(&Prop::Unknown("FAMILY_NAME Digits")));
+    assert!(props.contains(&Prop::Unknown("WEIGHT_NAME Bold")));
+    let font = Font::from_props(props.into_iter()).unwrap();
+    assert_eq!(font.family(), None);
+    assert_eq!(font.glyphs().count(), 12);
+}
+
+#[test]
+fn unquoted_copyright_foundry() {
+    let src = with_properties("COPYRIGHT Public domain\nFOUNDRY Misc\n");
+    let props: Vec<_> = Format::Bdf.parse(&src).unwrap().collect();
+    assert!(props.contains(&Prop::Unknown("COPYRIGHT Public domain")));
+    assert!(props.contains(&Prop::Unknown("FOUNDRY Misc")));
+    let font = Font::from_props(props.into_iter()).unwrap();
+    assert_eq!(font.copyright(), None);
+    assert_eq!(font.height(), 5);
+    assert_eq!(font.glyphs().count(), 12);
+}
+
+#[test]
+fn quoted_quotes() {
+    let src = with_properties("COPYRIGHT \"The \"\"Digits\"\" font\"\n");
+    let font = Font::from_props(Format::Bdf.parse(&src).unwrap()).unwrap();
+    assert_eq!(font.copyright(), Some("The \"Digits\" font"));
+    let mut buf = Vec::new();
+    tfon::bdf::write(&mut buf, font.props()).unwrap();
+    let out = String::from_utf8(buf).unwrap();
+    assert!(out.contains("COPYRIGHT \"The \"\"Digits\"\" font\"\n"));
+    let parsed = Font::from_props(Format::Bdf.parse(&out).unwrap()).unwrap();
+    assert_eq!(parsed.copyright(), font.copyright());
+}
+
+#[test]
+fn chars_mismatch() {
+    let src = fixtures::source("digits3x5.bdf").unwrap();
+    let props: Vec<_> = Format::Bdf.parse(src).unwrap().collect();
+    assert!(!props.iter().any(|p| matches!(p, Prop::Skipped(_))));
+    let src = src.replace("CHARS 12\n", "CHARS 13\n");
+    let mut parser = Format::Bdf.parse(&src).unwrap();
+    let props: Vec<_> = parser.by_ref().collect();
+    let Some(Prop::Skipped(line)) = props.last() else {
+        panic!("CHARS mismatch not reported");
+    };
+    assert_eq!(*line, "CHARS 13");
+    assert_eq!(parser.line_number(), Some(9));
+    let font = Font::from_props(props.into_iter()).unwrap();
+    assert_eq!(font.glyphs().count(), 12);
+}
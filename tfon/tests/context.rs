@@ -0,0 +1,99 @@
+use tfon::{Bitmap, Error, Format, Limits, Prop, WriteOptions};
+
+fn bitmap(art: &str) -> Bitmap {
+    Bitmap::from_art(art).unwrap()
+}
+
+fn header() -> Vec<Prop<'static>> {
+    vec![
+        Prop::FontName("test".into()),
+        Prop::FontHeight(2),
+        Prop::CharSpacing(1),
+        Prop::LineSpacing(1),
+    ]
+}
+
+fn write(format: Format, props: Vec<Prop>) -> Error {
+    let options = WriteOptions::default();
+    format
+        .write_with(std::io::sink(), props.into_iter(), &options)
+        .unwrap_err()
+}
+
+#[test]
+fn code_writers() {
+    for format in [Format::Ctable, Format::Gfx, Format::U8g2] {
+        let mut props = header();
+        props.push(Prop::CodePoint(65));
+        props.push(Prop::Bitmap(bitmap("@.\n.@\n")));
+        props.push(Prop::Bitmap(bitmap("@.\n.@\n")));
+        let err = write(format, props);
+        let ctx = err.context().unwrap();
+        assert_eq!(ctx.format, Some(format.name()));
+        assert_eq!(ctx.operation, "write");
+        assert_eq!(ctx.previous, Some(65));
+        assert!(matches!(err.root(), Error::Misplaced("ch")));
+    }
+}
+
+#[test]
+fn ctable_glyph_height() {
+    let mut props = header();
+    props.push(Prop::CodePoint(65));
+    props.push(Prop::Bitmap(bitmap("@\n@\n@\n")));
+    let err = write(Format::Ctable, props);
+    assert_eq!(
+        err.to_string(),
+        "ctable write: Bitmap for U+0041: Glyph 65 does not fit character cell"
+    );
+}
+
+#[test]
+fn load() {
+    let src = "font_name: test\nfont_width: 2\n\nch: 65 A\n@@@\n...\n";
+    let err = Format::Tfon.load(src).unwrap_err();
+    let ctx = err.context().unwrap();
+    assert_eq!(ctx.format, Some("tfon"));
+    assert_eq!(ctx.operation, "parse");
+    assert_eq!(ctx.code_point, Some(65));
+}
+
+#[test]
+fn line_length() {
+    let src = "font_name: test\n\nch: 65 AAAAAAAAAAAAAAAAAAAA\n@\n";
+    let limits = Limits {
+        max_line: 16,
+        ..Default::default()
+    };
+    let err = Format::Tfon.parse_limited(src, &limits).unwrap_err();
+    let ctx = err.context().unwrap();
+    assert_eq!(ctx.format, Some("tfon"));
+    assert_eq!(ctx.line, Some(3));
+    assert!(matches!(
+        err.root(),
+        Error::LimitExceeded("line length", 16)
+    ));
+}
+
+#[test]
+fn glyph_limit_line() {
+    let src = "font_name: test\n\nch: 65 A\n@\n\nch: 66 B\n@\n";
+    let limits = Limits {
+        max_glyphs: 1,
+        ..Default::default()
+    };
+    let err = Format::Tfon.parse_limited(src, &limits).unwrap_err();
+    assert_eq!(err.context().unwrap().line, Some(6));
+    assert!(matches!(err.root(), Error::LimitExceeded("glyphs", 1)));
+}
+
+#[test]
+fn skipped_line() {
+    let src = "font_name: test\n\nch: 65 A\n@\n\nch: x\n@\n";
+    let err = tfon::tfon::write_canonical(std::io::sink(), src).unwrap_err();
+    assert_eq!(err.context().unwrap().line, Some(6));
+    let mut parser = Format::Tfon.parse(src).unwrap();
+    let skipped = parser.by_ref().find(|p| matches!(p, Prop::Skipped(_)));
+    assert_eq!(skipped, Some(Prop::Skipped("ch: x")));
+    assert_eq!(parser.line_number(), Some(6));
+}
@@ -0,0 +1,118 @@
+use tfon::fixtures;
+use tfon::{Bitmap, Font, Format, Modified};
+
+fn modified() -> Modified {
+    Modified {
+        time: "2024-05-01T12:00:00Z".into(),
+        author: "Jane Doe".into(),
+    }
+}
+
+fn write(font: &Font) -> String {
+    let mut buf = Vec::new();
+    tfon::tfon::write(&mut buf, font.props()).unwrap();
+    String::from_utf8(buf).unwrap()
+}
+
+#[test]
+fn set_glyph() {
+    let mut font = fixtures::font("digits3x5.tfon").unwrap();
+    let bmap = Bitmap::from_art("@@@\n@..\n@@@\n..@\n@@@\n").unwrap();
+    font.set_glyph(53, Some(bmap.clone()), Some(modified()))
+        .unwrap();
+    assert_eq!(font.glyph(53), Some(&bmap));
+    assert_eq!(font.modified(53), Some(&modified()));
+    font.set_glyph(53, Some(bmap), None).unwrap();
+    assert_eq!(font.modified(53), None);
+    font.set_glyph(53, None, None).unwrap();
+    assert_eq!(font.glyph(53), None);
+}
+
+#[test]
+fn set_glyph_wrong_height() {
+    let mut font = fixtures::font("digits3x5.tfon").unwrap();
+    let bmap = Bitmap::from_art("@@@\n@.@\n").unwrap();
+    assert!(font.set_glyph(53, Some(bmap), None).is_err());
+}
+
+#[test]
+fn set_glyph_prunes_frame() {
+    let mut font = fixtures::font("blink7x7.tfon").unwrap();
+    assert!(font.frame(33).is_some());
+    font.set_glyph(33, None, None).unwrap();
+    assert_eq!(font.frame(33), None);
+}
+
+#[test]
+fn modified_round_trip() {
+    let mut font = fixtures::font("digits3x5.tfon").unwrap();
+    let bmap = font.glyph(49).unwrap().clone();
+    font.set_glyph(49, Some(bmap), Some(modified())).unwrap();
+    let src = write(&font);
+    assert!(src.contains("modified: 2024-05-01T12:00:00Z Jane Doe\n"));
+    let parsed = Font::from_props(Format::Tfon.parse(&src).unwrap()).unwrap();
+    assert_eq!(parsed.modified(49), Some(&modified()));
+    assert_eq!(parsed, font);
+}
+
+#[test]
+fn modified_write_exact() {
+    let src = fixtures::source("digits3x5.tfon").unwrap();
+    let mut font = fixtures::font("digits3x5.tfon").unwrap();
+    let bmap = font.glyph(49).unwrap().clone();
+    font.set_glyph(49, Some(bmap), Some(modified())).unwrap();
+    let mut buf = Vec::new();
+    tfon::tfon::write_exact(&mut buf, src, font.props()).unwrap();
+    let out = String::from_utf8(buf).unwrap();
+    let expected = src.replace(
+        "@@@\n\nch: 50",
+        "@@@\nmodified: 2024-05-01T12:00:00Z Jane Doe\n\nch: 50",
+    );
+    assert_eq!(out, expected);
+}
+
+#[test]
+fn resolve_names() {
+    use tfon::names::resolve;
+    assert_eq!(resolve("1"), Some(0x31));
+    assert_eq!(resolve("é"), Some(0xE9));
+    assert_eq!(resolve("233"), Some(233));
+    assert_eq!(resolve("U+0001"), Some(1));
+    assert_eq!(resolve("0x41"), Some(0x41));
+    assert_eq!(resolve("nbsp"), Some(0xA0));
+    assert_eq!(resolve("bogus"), None);
+}
+
+#[test]
+fn ensure_charset_unsorted() {
+    let mut font = fixtures::font("blink7x7.tfon").unwrap();
+    let before = font.clone();
+    let err = font
+        .ensure_charset(&[200..=200, 32..=34], tfon::FillPolicy::Blank)
+        .unwrap_err();
+    assert!(matches!(err, tfon::Error::OutOfRange(200, 127)));
+    assert_eq!(font, before);
+}
+
+#[test]
+fn write_exact_font_width() {
+    let src = fixtures::source("ascii5x7.tfon").unwrap();
+    let font = fixtures::font("ascii5x7.tfon").unwrap();
+    let exact = |src: &str, width| {
+        let mut props: Vec<_> = font.props().collect();
+        props.insert(1, tfon::Prop::FontWidth(width));
+        let mut buf = Vec::new();
+        tfon::tfon::write_exact(&mut buf, src, props.into_iter())
+            .map(|_| String::from_utf8(buf).unwrap())
+    };
+    // added
+    let out = exact(src, 5).unwrap();
+    let fixed = src.replace("\n\nch: 32", "\nfont_width: 5\n\nch: 32");
+    assert_eq!(out, fixed);
+    // changed
+    let out = exact(&fixed, 0).unwrap();
+    assert_eq!(out, fixed.replace("font_width: 5", "font_width: 0"));
+    // glyphs must match
+    let err = exact(src, 4).unwrap_err();
+    assert!(matches!(err.root(), tfon::Error::WidthMismatch(_)), "{err}");
+}
@@ -0,0 +1,53 @@
+use tfon::fixtures::{self, FIXTURES};
+use tfon::{Bitmap, Font};
+
+/// Get all glyphs of a font
+fn glyphs(font: &Font) -> Vec<(u16, Bitmap)> {
+    font.glyphs().map(|(cp, bmap)| (cp, bmap.clone())).collect()
+}
+
+#[test]
+fn load_all() {
+    for (name, _src) in FIXTURES {
+        let font = fixtures::font(name).unwrap();
+        assert!(font.glyphs().count() > 0, "{name}");
+    }
+    assert!(fixtures::font("missing.tfon").is_err());
+}
+
+#[test]
+fn formats_match() {
+    for base in ["ascii5x7", "digits3x5", "misc-fixed-5x7"] {
+        let expected = fixtures::font(&format!("{base}.tfon")).unwrap();
+        for (name, _src) in FIXTURES
+            .iter()
+            .filter(|(nm, _)| nm.starts_with(base) && !nm.ends_with(".tfon"))
+        {
+            let font = fixtures::font(name).unwrap();
+            assert_eq!(font.name(), expected.name(), "{name}");
+            assert_eq!(font.height(), expected.height(), "{name}");
+            assert!(glyphs(&font) == glyphs(&expected), "{name}");
+        }
+    }
+}
+
+#[test]
+fn tfon_round_trip() {
+    for (name, src) in FIXTURES.iter().filter(|(nm, _)| nm.ends_with(".tfon")) {
+        let mut out = Vec::new();
+        tfon::tfon::write(&mut out, tfon::tfon::Parser::new(src)).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), *src, "{name}");
+    }
+}
+
+#[test]
+fn real_world_font() {
+    let font = fixtures::font("misc-fixed-5x7.bdf").unwrap();
+    assert_eq!(font.glyphs().count(), 191);
+    assert_eq!(font.width(), 5);
+    assert_eq!(font.baseline(), 6);
+    assert_eq!(
+        font.copyright(),
+        Some("Public domain font.  Share and enjoy.")
+    );
+}
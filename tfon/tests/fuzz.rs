@@ -0,0 +1,61 @@
+use tfon::fuzz_utils::arbitrary::{Arbitrary, Unstructured};
+use tfon::fuzz_utils::{self, ValidProps};
+use tfon::{Font, Format};
+
+/// Make fuzzer input from a seed
+fn data(seed: u64) -> Vec<u8> {
+    let mut state = seed;
+    (0..512)
+        .map(|_| {
+            state = state
+                .wrapping_mul(6_364_136_223_846_793_005)
+                .wrapping_add(1_442_695_040_888_963_407);
+            (state >> 56) as u8
+        })
+        .collect()
+}
+
+#[test]
+fn input_exhausted() {
+    let mut u = Unstructured::new(&[7]);
+    assert_eq!(u8::arbitrary(&mut u).unwrap(), 7);
+    assert!(u.is_empty());
+    let props = fuzz_utils::props(&mut u).unwrap();
+    assert!(Font::from_props(props.into_iter()).is_ok());
+    let mut props = Vec::new();
+    fuzz_utils::mutate_props(&mut u, &mut props).unwrap();
+    assert!(props.is_empty());
+    assert!(Font::arbitrary(&mut Unstructured::new(&[])).is_ok());
+}
+
+#[test]
+fn generated_round_trip() {
+    for seed in 0..40 {
+        let data = data(seed);
+        let font = Font::arbitrary(&mut Unstructured::new(&data)).unwrap();
+        for format in [Format::Tfon, Format::Bdf, Format::Ifnt, Format::Iris] {
+            let parsed = fuzz_utils::round_trip(&font, format).unwrap();
+            assert!(parsed.glyphs().eq(font.glyphs()), "{format} seed {seed}");
+        }
+    }
+}
+
+#[test]
+fn mutated_props_and_text() {
+    for seed in 0..40 {
+        let data = data(seed);
+        let mut u = Unstructured::new(&data);
+        let ValidProps(mut props) = ValidProps::arbitrary(&mut u).unwrap();
+        for _ in 0..4 {
+            fuzz_utils::mutate_props(&mut u, &mut props).unwrap();
+        }
+        let font = Font::from_props(props.iter().cloned());
+        let mut src = Vec::new();
+        let _ = tfon::tfon::write(&mut src, props.into_iter());
+        let src = String::from_utf8_lossy(&src);
+        fuzz_utils::exercise(&fuzz_utils::mutate_text(&mut u, &src).unwrap());
+        if let Ok(font) = font {
+            let _ = fuzz_utils::round_trip(&font, Format::Ifnt);
+        }
+    }
+}
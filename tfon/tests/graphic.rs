@@ -0,0 +1,36 @@
+use tfon::graphic::{Animation, Frame};
+use tfon::{Bitmap, Error};
+
+/// Make an animation with a number of frames
+fn animation(count: usize) -> Animation {
+    let frame = Frame {
+        bitmap: Bitmap::from_art("@.\n.@").unwrap(),
+        delay: 500,
+    };
+    Animation {
+        frames: vec![frame; count],
+    }
+}
+
+#[test]
+fn multi_numbers() {
+    let anim = animation(2);
+    assert_eq!(anim.ntcip_multi(4).unwrap(), "[pt5o0][g4][np][pt5o0][g5]");
+    assert_eq!(animation(1).ntcip_multi(255).unwrap(), "[pt5o0][g255]");
+    let graphics = anim.ntcip_graphics(254).unwrap();
+    assert_eq!(graphics.len(), 2);
+    assert_eq!(graphics[1].number, 255);
+}
+
+#[test]
+fn numbers_overflow() {
+    assert!(matches!(
+        animation(2).ntcip_multi(255),
+        Err(Error::TooLarge())
+    ));
+    assert!(matches!(
+        animation(10).ntcip_graphics(250),
+        Err(Error::TooLarge())
+    ));
+    assert!(animation(0).ntcip_graphics(255).unwrap().is_empty());
+}
@@ -0,0 +1,135 @@
+use tfon::ifnt::{self, Parser};
+use tfon::{Bitmap, Error, Font, LineEnding, Prop, PropParser, WriteOptions};
+
+/// Make a font with one tall glyph, in `tfon` format
+fn tall_font(rows: usize) -> String {
+    let mut src = String::from(
+        "font_name: tall\nchar_spacing: 1\nline_spacing: 1\n\nch: 65 A\n",
+    );
+    for row in 0..rows {
+        let pix = if row % 3 == 0 { "@..@@" } else { ".@@.." };
+        src.push_str(pix);
+        src.push('\n');
+    }
+    src
+}
+
+/// Load a font from `tfon` format
+fn load(src: &str) -> Font {
+    Font::from_props(tfon::tfon::Parser::new(src)).unwrap()
+}
+
+/// Write a font in `ifnt` format
+fn write_ifnt(font: &Font) -> Result<String, Error> {
+    let mut out = Vec::new();
+    ifnt::write(&mut out, font.props())?;
+    Ok(String::from_utf8(out).unwrap())
+}
+
+/// Get bitmaps parsed from `ifnt` format
+fn bitmaps(src: &str) -> Vec<Bitmap> {
+    Parser::new(src)
+        .filter_map(|prop| match prop {
+            Prop::Bitmap(bmap) => Some(bmap),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn rows_32() {
+    let font = load(&tall_font(32));
+    let out = write_ifnt(&font).unwrap();
+    assert!(out.contains("\nrow01= X . . X X\n"));
+    assert!(out.contains("\nrow32= . X X . .\n"));
+    assert!(!out.contains("row33"));
+    assert_eq!(bitmaps(&out).first(), font.glyph(65));
+}
+
+#[test]
+fn rows_beyond_99() {
+    let font = load(&tall_font(120));
+    let out = write_ifnt(&font).unwrap();
+    assert!(out.contains("\nrow001= X . . X X\n"));
+    assert!(out.contains("\nrow100= X . . X X\n"));
+    assert!(out.contains("\nrow120= . X X . .\n"));
+    assert_eq!(bitmaps(&out).first(), font.glyph(65));
+}
+
+#[test]
+fn height_mismatch() {
+    let font = load(&tall_font(32));
+    let src = write_ifnt(&font).unwrap();
+    let src = src.replace("FontHeight=32", "FontHeight=33");
+    let err = ifnt::write(std::io::sink(), Parser::new(&src)).unwrap_err();
+    assert!(matches!(err.root(), Error::GlyphTooLarge(65)), "{err}");
+}
+
+#[test]
+fn font_info_last() {
+    let font = load(&tall_font(3));
+    let src = write_ifnt(&font).unwrap();
+    let (info, chars) = src.split_at(src.find("[Char_").unwrap());
+    let info = info.replace("FontHeight=3", "FontHeight = 3");
+    let src = format!("; exported\r\n{chars}\r\n# header\r\n{info}")
+        .replace('\n', "\r\n");
+    let props: Vec<Prop> = Parser::new(&src).collect();
+    assert_eq!(props.first(), Some(&Prop::FontName("tall".into())));
+    assert!(!props.iter().any(|p| matches!(p, Prop::Unknown(_))));
+    let parsed = Font::from_props(props.into_iter()).unwrap();
+    assert_eq!(parsed.height(), 3);
+    assert_eq!(parsed.glyph(65), font.glyph(65));
+}
+
+#[test]
+fn line_numbers() {
+    let src = concat!(
+        "[Char_65]\n",
+        "Character='A'\n",
+        "Extra=1\n",
+        "row01=X.\n",
+        "\n",
+        "[FontInfo]\n",
+        "FontName=lines\n",
+        "FontHeight=1\n",
+    );
+    let mut parser = Parser::new(src);
+    let mut lines = Vec::new();
+    while let Some(prop) = parser.next() {
+        lines.push((prop.kind(), parser.line_number().unwrap()));
+    }
+    assert_eq!(
+        lines,
+        [
+            ("FontName", 7),
+            ("FontHeight", 8),
+            ("CodePoint", 1),
+            ("Unknown", 3),
+            ("Bitmap", 1),
+        ]
+    );
+}
+
+#[test]
+fn crlf() {
+    let props = [
+        Prop::FontName("crlf".into()),
+        Prop::FontHeight(1),
+        Prop::CharSpacing(1),
+        Prop::LineSpacing(1),
+        Prop::CodePoint(65),
+        Prop::Bitmap(Bitmap::from_art("@.").unwrap()),
+    ];
+    let options = WriteOptions {
+        line_ending: LineEnding::Crlf,
+        ..Default::default()
+    };
+    let mut buf = Vec::new();
+    ifnt::write_with(&mut buf, props.iter().cloned(), &options).unwrap();
+    let text = String::from_utf8(buf).unwrap();
+    assert!(text.starts_with("[FontInfo]\r\n"));
+    assert!(text.ends_with("row01= X .\r\n"));
+    assert_eq!(text.matches('\n').count(), text.matches("\r\n").count());
+    let parsed: Vec<_> = ifnt::Parser::new(&text).collect();
+    assert_eq!(parsed.last(), props.last());
+}
@@ -0,0 +1,39 @@
+use tfon::{fixtures, iris, Error, Font, Format};
+
+fn write(font: &Font) -> String {
+    let mut buf = Vec::new();
+    iris::write(&mut buf, font.props()).unwrap();
+    String::from_utf8(buf).unwrap()
+}
+
+#[test]
+fn escaped_name_round_trip() {
+    let src = fixtures::source("digits3x5.tfon")
+        .unwrap()
+        .replace("font_name: digits 3x5", "font_name: A&B <\"x\"> 'y'");
+    let font = Font::from_props(Format::Tfon.parse(&src).unwrap()).unwrap();
+    let xml = write(&font);
+    assert!(xml.contains(r#"name="A&amp;B &lt;&quot;x&quot;&gt; 'y'""#));
+    let parsed = Font::from_props(Format::Iris.parse(&xml).unwrap()).unwrap();
+    assert_eq!(parsed.name(), font.name());
+    assert_eq!(parsed, font);
+}
+
+#[test]
+fn entity_references() {
+    let xml = r#"<font name="&apos;&#65;&#x42;&bogus;&" height="1"
+        char_spacing="1" line_spacing="1"></font>"#;
+    let font = Font::from_props(Format::Iris.parse(xml).unwrap()).unwrap();
+    assert_eq!(font.name(), "'AB&bogus;&");
+}
+
+#[test]
+fn multiple_fonts() {
+    let font = fixtures::font("digits3x5.tfon").unwrap();
+    let xml = write(&font).repeat(2);
+    assert_eq!(iris::font_count(&xml), 2);
+    let err = Format::Iris.parse(&xml).err().unwrap();
+    assert!(matches!(err.root(), Error::MultipleFonts(2)));
+    let fonts = iris::read_fonts(&xml).unwrap();
+    assert_eq!(fonts, [font.clone(), font]);
+}
@@ -0,0 +1,65 @@
+use tfon::{fixtures, render, Error, Font, Format, WriteOptions};
+
+fn with_ligature(cp: u32) -> Font {
+    let mut font = fixtures::font("digits3x5.tfon").unwrap();
+    font.add_ligature("11", cp);
+    font
+}
+
+#[test]
+fn tfon_round_trip() {
+    let font = with_ligature(58);
+    let mut buf = Vec::new();
+    tfon::tfon::write(&mut buf, font.props()).unwrap();
+    let src = String::from_utf8(buf).unwrap();
+    assert!(src.contains("ligature: 58 11\n"));
+    let parsed = Font::from_props(Format::Tfon.parse(&src).unwrap()).unwrap();
+    assert_eq!(parsed, font);
+    let bitmap = render::text(&parsed, "11").unwrap();
+    assert_eq!(bitmap.width(), 1);
+}
+
+#[test]
+fn tfon_out_of_range() {
+    let font = with_ligature(0xE000);
+    let err = tfon::tfon::write(std::io::sink(), font.props()).unwrap_err();
+    assert!(matches!(err.root(), Error::LigatureOutOfRange(0xE000)));
+    let src = fixtures::source("digits3x5.tfon").unwrap();
+    let err = tfon::tfon::write_exact(std::io::sink(), src, font.props())
+        .unwrap_err();
+    assert!(matches!(err.root(), Error::LigatureOutOfRange(0xE000)));
+    let src = src.replace("char_spacing", "ligature: 57344 11\nchar_spacing");
+    let parsed = Font::from_props(Format::Tfon.parse(&src).unwrap()).unwrap();
+    assert_eq!(parsed.ligatures().count(), 0);
+}
+
+#[test]
+fn dropped_by_other_formats() {
+    let font = with_ligature(58);
+    for format in Format::ALL.iter().filter(|f| f.can_write()) {
+        let report = format
+            .write_with(std::io::sink(), font.props(), &WriteOptions::default())
+            .unwrap();
+        let expected: &[&str] = if *format == Format::Tfon {
+            &[]
+        } else {
+            &["11"]
+        };
+        assert_eq!(report.ligatures, expected, "{format}");
+    }
+}
+
+#[test]
+fn fit_with_ligatures() {
+    use render::TruncatePolicy;
+    let font = with_ligature(58);
+    assert_eq!(render::measure(&font, "1111").unwrap(), (3, 5));
+    for policy in [TruncatePolicy::Clip, TruncatePolicy::Ellipsis] {
+        let (text, width) = render::fit(&font, "1111", 2, policy).unwrap();
+        assert_eq!(text, "11");
+        assert_eq!(width, render::measure(&font, &text).unwrap().0);
+        assert_eq!(width, 1);
+    }
+    let err = render::fit(&font, "11x", 2, TruncatePolicy::Clip).unwrap_err();
+    assert!(matches!(err, Error::MissingGlyph('x')));
+}
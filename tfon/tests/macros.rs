@@ -0,0 +1,13 @@
+use tfon::fixtures;
+use tfon::{include_font, Font};
+
+#[test]
+fn include_font_matches_parser() {
+    let font: Font = include_font!("fixtures/blink7x7.tfon");
+    assert_eq!(font, fixtures::font("blink7x7.tfon").unwrap());
+    assert_eq!(font.family(), Some("blink"));
+    assert_eq!(font.max_char_number(), 127);
+    let frame = font.frame(33).unwrap();
+    assert_ne!(Some(frame), font.glyph(33));
+    assert_eq!(font.modified(33).unwrap().author, "Jane Doe");
+}
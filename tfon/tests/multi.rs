@@ -0,0 +1,85 @@
+use tfon::{fixtures, multi, Bitmap, Error, Font};
+
+fn fonts() -> Vec<Font> {
+    vec![
+        fixtures::font("ascii5x7.tfon").unwrap(),
+        fixtures::font("digits3x5.tfon").unwrap(),
+    ]
+}
+
+/// Get the columns of a bitmap which have any on pixels
+fn ink_columns(bmap: &Bitmap) -> Vec<u8> {
+    (0..bmap.width())
+        .filter(|x| (0..bmap.height()).any(|y| bmap.pixel(*x, y)))
+        .collect()
+}
+
+#[test]
+fn justification() {
+    let fonts = fonts();
+    let left = multi::render(&fonts, 20, 7, "[fo3]1").unwrap();
+    assert_eq!(ink_columns(&left), [0, 1, 2]);
+    let center = multi::render(&fonts, 20, 7, "[fo3][jl3]1").unwrap();
+    assert_eq!(ink_columns(&center), [8, 9, 10]);
+    let right = multi::render(&fonts, 20, 7, "[fo3][jl4]1").unwrap();
+    assert_eq!(ink_columns(&right), [17, 18, 19]);
+}
+
+#[test]
+fn new_line() {
+    let fonts = fonts();
+    let bmap = multi::render(&fonts, 5, 17, "[fo3]8[nl]8").unwrap();
+    assert!(bmap.pixel(0, 4));
+    assert!(!bmap.pixel(0, 5) && !bmap.pixel(0, 6));
+    assert!(bmap.pixel(0, 7));
+    let bmap = multi::render(&fonts, 5, 17, "[fo3]8[nl5]8").unwrap();
+    assert!(!bmap.pixel(0, 9));
+    assert!(bmap.pixel(0, 10));
+    assert!(multi::render(&fonts, 5, 11, "[fo3]8[nl5]8").is_err());
+}
+
+#[test]
+fn spacing() {
+    let fonts = fonts();
+    let bmap = multi::render(&fonts, 20, 5, "[fo3]88").unwrap();
+    assert_eq!(ink_columns(&bmap), [0, 1, 2, 4, 5, 6]);
+    let bmap = multi::render(&fonts, 20, 5, "[fo3][sc3]88[/sc]8").unwrap();
+    assert_eq!(ink_columns(&bmap), [0, 1, 2, 6, 7, 8, 12, 13, 14]);
+    let bmap = multi::render(&fonts, 20, 5, "[fo3]8[sp4]8").unwrap();
+    assert_eq!(ink_columns(&bmap), [0, 1, 2, 7, 8, 9]);
+    let bmap = multi::render(&fonts, 20, 5, "[fo3][sp2][sp1]8").unwrap();
+    assert_eq!(ink_columns(&bmap), [3, 4, 5]);
+    let bmap = multi::render(&fonts, 20, 5, "[fo3][jl4]8[sp2]").unwrap();
+    assert_eq!(ink_columns(&bmap), [15, 16, 17]);
+}
+
+#[test]
+fn fonts_share_baseline() {
+    let fonts = fonts();
+    let bmap = multi::render(&fonts, 20, 7, "8[fo3]8").unwrap();
+    assert!(bmap.pixel(6, 2) && !bmap.pixel(6, 1));
+    let bmap = multi::render(&fonts, 20, 7, "8[sp1][fo3]8").unwrap();
+    assert!(bmap.pixel(6, 2) && !bmap.pixel(6, 1));
+}
+
+#[test]
+fn escapes() {
+    let fonts = fonts();
+    let bmap = multi::render(&fonts, 20, 7, "[[]]").unwrap();
+    assert_eq!(bmap.width(), 20);
+    assert!(!ink_columns(&bmap).is_empty());
+}
+
+#[test]
+fn errors() {
+    let fonts = fonts();
+    let err = multi::render(&fonts, 20, 7, "[fo9]1").unwrap_err();
+    assert!(matches!(err, Error::MissingFont(9)));
+    let err = multi::render(&fonts, 20, 7, "[xx]").unwrap_err();
+    assert!(matches!(err, Error::UnsupportedTag(t) if t == "xx"));
+    let err = multi::render(&fonts, 20, 7, "[spx]").unwrap_err();
+    assert!(matches!(err, Error::UnsupportedTag(t) if t == "spx"));
+    assert!(multi::render(&fonts, 20, 7, "1]").is_err());
+    assert!(multi::render(&fonts, 4, 7, "[fo3]88").is_err());
+    assert!(multi::render(&[], 20, 7, "1").is_err());
+}
@@ -0,0 +1,75 @@
+use tfon::fixtures;
+use tfon::ntcip::{self, FontRow};
+
+/// OER encoding of `digits3x5` for `fontVersionID`, written out by hand
+const DIGITS_OER: &[&str] = &[
+    // number, height, char spacing, line spacing
+    "03 05 01 02",
+    // quantity of characters: length 1, count 12
+    "01 0c",
+    // character number, width, bitmap length, bitmap (rows packed MSB first)
+    "0020 03 02 0000",
+    "0030 03 02 f6de",
+    "0031 03 02 592e",
+    "0032 03 02 e7ce",
+    "0033 03 02 e59e",
+    "0034 03 02 b792",
+    "0035 03 02 f39e",
+    "0036 03 02 f3de",
+    "0037 03 02 e524",
+    "0038 03 02 f7de",
+    "0039 03 02 f79e",
+    "003a 01 01 50",
+];
+
+/// Decode hexadecimal bytes, ignoring spaces
+fn unhex(parts: &[&str]) -> Vec<u8> {
+    let digits: Vec<u8> = parts
+        .iter()
+        .flat_map(|part| part.bytes())
+        .filter(|b| *b != b' ')
+        .collect();
+    digits
+        .chunks(2)
+        .map(|pair| {
+            u8::from_str_radix(std::str::from_utf8(pair).unwrap(), 16).unwrap()
+        })
+        .collect()
+}
+
+/// CRC-16/X-25, computed MSB first on bit-reversed bytes
+fn crc16_x25(bytes: &[u8]) -> u16 {
+    let mut crc: u16 = 0xFFFF;
+    for b in bytes {
+        crc ^= u16::from(b.reverse_bits()) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x1021
+            } else {
+                crc << 1
+            };
+        }
+    }
+    crc.reverse_bits() ^ 0xFFFF
+}
+
+#[test]
+fn crc16_x25_check_value() {
+    assert_eq!(crc16_x25(b"123456789"), 0x906E);
+}
+
+#[test]
+fn version_id_known_answer() {
+    let font = fixtures::font("digits3x5.tfon").unwrap();
+    let rows = ntcip::character_rows(&font, 1).unwrap();
+    assert_eq!(rows.len(), 12);
+    let oer = unhex(DIGITS_OER);
+    assert_eq!(oer.len(), 77);
+    assert_eq!(crc16_x25(&oer), 0x64FA);
+    assert_eq!(ntcip::version_id(&font, &rows), 0x64FA);
+    let row = FontRow::new(&font, 1, &rows);
+    assert_eq!(
+        row.version_id_object().to_string(),
+        format!("{}.7.1 i {}", ntcip::FONT_ENTRY, 0x64FA)
+    );
+}
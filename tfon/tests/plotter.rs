@@ -0,0 +1,43 @@
+use tfon::plotter::{self, Dialect, PlotOptions, Stroke};
+use tfon::Bitmap;
+
+#[test]
+fn strokes() {
+    let bmap = Bitmap::from_art("@@.@\n.@@.").unwrap();
+    assert_eq!(
+        plotter::strokes(&bmap),
+        [
+            Stroke {
+                start: (0.5, 1.5),
+                end: (1.5, 1.5),
+            },
+            Stroke {
+                start: (3.5, 1.5),
+                end: (3.5, 1.5),
+            },
+            // second row is plotted right to left
+            Stroke {
+                start: (2.5, 0.5),
+                end: (1.5, 0.5),
+            },
+        ]
+    );
+}
+
+#[test]
+fn hpgl_and_gcode() {
+    let bmap = Bitmap::from_art("@@").unwrap();
+    let mut out = Vec::new();
+    plotter::write(&mut out, &bmap, &PlotOptions::default()).unwrap();
+    assert_eq!(out, b"IN;SP1;PU20,20;PD60,20;PU;SP0;\n");
+    let options = PlotOptions {
+        dialect: Dialect::Gcode,
+        pitch: 2.0,
+        ..Default::default()
+    };
+    let mut out = Vec::new();
+    plotter::write(&mut out, &bmap, &options).unwrap();
+    let gcode = String::from_utf8(out).unwrap();
+    assert!(gcode.contains("G0 X1.000 Y1.000\nG1 Z0 F600\nG1 X3.000 Y1.000\n"));
+    assert!(gcode.ends_with("M2\n"));
+}
@@ -0,0 +1,37 @@
+#![cfg(feature = "png")]
+
+use tfon::quick::text_png;
+use tfon::Error;
+
+const FONT: &str = "\
+font_name: quick
+char_spacing: 1
+line_spacing: 1
+
+ch: 65 A
+.@.
+@.@
+@@@
+
+ch: 66 B
+@@.
+@@@
+@@.
+";
+
+#[test]
+fn render_png() {
+    let png = text_png(FONT.as_bytes(), "AB", 2).unwrap();
+    assert!(png.starts_with(b"\x89PNG\r\n\x1a\n"));
+    // IHDR width and height: (3 + 1 + 3) x 3 pixels, scaled by 2
+    assert_eq!(&png[16..24], &[0, 0, 0, 14, 0, 0, 0, 6]);
+}
+
+#[test]
+fn unknown_input() {
+    assert!(matches!(
+        text_png(b"not a font", "A", 1),
+        Err(Error::UnknownFormat())
+    ));
+    assert!(text_png(FONT.as_bytes(), "C", 1).is_err());
+}
@@ -0,0 +1,31 @@
+use tfon::render::{blit, blit_opaque, blit_text};
+use tfon::{fixtures, Bitmap};
+
+#[test]
+fn blit_keeps_background() {
+    let src = Bitmap::from_art(".@\n@.\n").unwrap();
+    let mut dst = Bitmap::from_art("@@@\n@@@\n").unwrap();
+    blit(&src, &mut dst, 1, 0);
+    assert_eq!(dst.to_art(), "@@@\n@@@\n");
+    blit_opaque(&src, &mut dst, 1, 0);
+    assert_eq!(dst.to_art(), "@.@\n@@.\n");
+}
+
+#[test]
+fn blit_clips_overflow() {
+    let src = Bitmap::from_art("@@\n@@\n").unwrap();
+    let mut pixels = Vec::new();
+    let mut sink = |x, y, _on| pixels.push((x, y));
+    blit(&src, &mut sink, u32::MAX, u32::MAX - 1);
+    assert_eq!(pixels, [(u32::MAX, u32::MAX - 1), (u32::MAX, u32::MAX)]);
+}
+
+#[test]
+fn blit_text_clips_overflow() {
+    let font = fixtures::font("digits3x5.tfon").unwrap();
+    let mut count = 0;
+    let mut sink = |_x, _y, _on| count += 1;
+    let width = blit_text(&font, "88", &mut sink, u32::MAX - 2, 0).unwrap();
+    assert_eq!(width, 7);
+    assert_eq!(count, 13);
+}
@@ -0,0 +1,46 @@
+use tfon::static_font::{self, GlyphBits};
+use tfon::{fixtures, include_static_font, StaticFont};
+
+const FONT: StaticFont = include_static_font!("fixtures/digits3x5.tfon");
+
+const _: () = assert!(FONT.height() == 5 && FONT.max_width() == 3);
+
+#[test]
+fn macro_matches_encode() {
+    let font = fixtures::font("digits3x5.tfon").unwrap();
+    let data = static_font::encode(&font).unwrap();
+    let expected = StaticFont::new("digits 3x5", data.leak());
+    assert_eq!(FONT, expected);
+    assert_eq!(FONT.len(), font.glyphs().count());
+}
+
+#[test]
+fn encode_const() {
+    const GLYPHS: &[GlyphBits] = &[(65, 2, &[0b1001_0000]), (66, 1, &[0x80])];
+    const LEN: usize = static_font::encoded_len(2, GLYPHS);
+    const DATA: [u8; LEN] =
+        static_font::encode_const([1, 2, 0, 1, 1, 2], GLYPHS);
+    let font = StaticFont::new("x", &DATA);
+    let a = font.glyph(65).unwrap();
+    assert!(a.pixel(0, 0) && !a.pixel(1, 0) && !a.pixel(0, 1) && a.pixel(1, 1));
+    assert_eq!(font.glyph(66).unwrap().width(), 1);
+    assert!(font.glyph(67).is_none());
+}
+
+#[test]
+#[should_panic(expected = "glyphs not sorted")]
+fn encode_unsorted() {
+    let glyphs: &[GlyphBits] = &[(66, 1, &[0]), (65, 1, &[0])];
+    let mut out = vec![0; static_font::encoded_len(1, glyphs)];
+    static_font::encode_into([1, 1, 0, 1, 1, 1], glyphs, &mut out);
+}
+
+#[test]
+fn blit_text_clips_overflow() {
+    let mut pixels = Vec::new();
+    let mut sink = |x, y, on| pixels.push((x, y, on));
+    let width = FONT.blit_text("11", &mut sink, u32::MAX - 1, 0).unwrap();
+    assert_eq!(width, 7);
+    assert!(pixels.iter().all(|(x, _y, on)| *on && *x >= u32::MAX - 1));
+    assert_eq!(pixels.len(), 7);
+}
@@ -0,0 +1,46 @@
+use tfon::Bitmap;
+
+fn art(rows: &[&str]) -> Bitmap {
+    Bitmap::from_art(&rows.join("\n")).unwrap()
+}
+
+#[test]
+fn thin_vertical_bar() {
+    let bar = art(&[".@@@.", ".@@@.", ".@@@.", ".@@@.", ".@@@."]);
+    let two = bar.thin();
+    assert_eq!(two, art(&[".@@..", ".@@..", ".@@..", ".@@..", ".@@.."]));
+    let one = two.thin();
+    assert_eq!(one, art(&[".@...", ".@...", ".@...", ".@...", ".@..."]));
+    assert_eq!(one.thin(), one);
+}
+
+#[test]
+fn thin_horizontal_bar() {
+    let bar = art(&[".....", "@@@@@", "@@@@@", "@@@@@", "....."]);
+    let one = bar.thin().thin();
+    assert_eq!(one, art(&[".....", "@@@@@", ".....", ".....", "....."]));
+}
+
+#[test]
+fn thicken_restores_stroke() {
+    let one = art(&[".@...", ".@...", ".@...", ".@...", ".@..."]);
+    let two = one.thicken();
+    assert_eq!(two, art(&[".@@..", ".@@..", ".@@..", ".@@..", ".@@.."]));
+    assert_eq!(two.thin(), one);
+    let bar = art(&[".....", "@@@@@", ".....", ".....", "....."]);
+    assert_eq!(bar.thicken().thin(), bar);
+}
+
+#[test]
+fn counters_preserved() {
+    let ring = art(&["@@@@@", "@...@", "@...@", "@...@", "@@@@@"]);
+    assert_eq!(ring.thin(), ring);
+    let small = art(&["@@@", "@.@", "@@@"]);
+    assert_eq!(small.thicken(), small);
+}
+
+#[test]
+fn thicken_keeps_strokes_apart() {
+    let bars = art(&["@.@..", "@.@..", "@.@..", "@.@.."]);
+    assert_eq!(bars.thicken(), art(&["@.@@.", "@.@@.", "@.@@.", "@.@@."]));
+}